@@ -5,7 +5,7 @@ use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use crossbeam_channel::{Receiver, Sender};
 use log::{error, warn};
 use notify::{Event as NotifyEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
@@ -14,24 +14,27 @@ use tray_icon::{TrayIcon, TrayIconBuilder};
 use winit::event::{Event, StartCause};
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
 
-use crate::config::{
-    get_config_path, load_and_validate_config, load_or_create_config, save_config,
-};
-#[cfg(target_os = "macos")]
-use crate::integrations::brew::{query_brew_services_map, run_brew_stop};
-use crate::integrations::docker::{query_docker_port_map, run_docker_stop};
-#[cfg(target_os = "windows")]
-use crate::integrations::windows_services::{query_windows_services_map, run_service_stop};
+use crate::catalog::Catalog;
+use crate::config::{ensure_log_dir, get_config_path};
+use crate::config_layers::{LayeredConfig, load_base_layers, set_user_setting};
+use crate::ignore::IgnoreList;
+use crate::integrations::health::GuardedRefresh;
+use crate::integrations::registry::IntegrationRegistry;
+use crate::integrations::routes;
+use crate::integrations::tunnels::{self, detect_public_tunnels};
 use crate::model::*;
-use crate::notify::maybe_notify_changes;
-use crate::process::kill::terminate_pid;
+use crate::notify::{maybe_notify_changes, maybe_notify_tunnels};
+use crate::port_set::PortSet;
+use crate::process::kill::{force_kill_pid, terminate_pid};
 use crate::process::ports::scan_ports;
+use crate::project::{GIT_STATUS_INTERVAL, GitStatusReader, ProjectCache};
+use crate::restart::{self, LaunchSpec};
 use crate::ui::icon::{IconVariant, create_template_icon};
 use crate::ui::menu::{
     build_menu_with_context, build_tooltip, collect_targets_for_all, format_command_label,
     parse_menu_action,
 };
-use crate::utils::hidden_command;
+use crate::utils::{copy_to_clipboard, hidden_command, open_path, open_url};
 
 const IDLE_THRESHOLD: Duration = Duration::from_secs(30);
 const IDLE_MULTIPLIER: u64 = 2; // Idle poll interval = base * IDLE_MULTIPLIER
@@ -40,19 +43,35 @@ const MENU_POLL_INTERVAL: Duration = Duration::from_millis(100);
 // menu constants moved under ui::menu
 
 pub fn run() -> Result<()> {
-    let config = load_or_create_config().context("failed to load configuration")?;
+    let port_override = ports_from_args()?;
+    let mut config_layers =
+        LayeredConfig::new(load_base_layers().context("failed to load configuration")?);
+    // Trusted project folders are known before any of their processes run
+    config_layers.reload_projects();
+    let effective = config_layers
+        .resolve()
+        .context("failed to load configuration")?;
+    for skipped in &effective.skipped {
+        warn!("Skipped project config {}", skipped);
+    }
+    let config = effective.config;
     let shared_config = Arc::new(RwLock::new(config.clone()));
 
     let mut state = AppState {
         processes: Vec::new(),
         last_feedback: None,
         config: config.clone(),
-        project_cache: HashMap::new(),
-        docker_port_map: HashMap::new(),
-        #[cfg(target_os = "macos")]
-        brew_services_map: HashMap::new(),
-        #[cfg(target_os = "windows")]
-        windows_services_map: HashMap::new(),
+        config_layers,
+        config_origins: effective.origins,
+        project_cache: ProjectCache::default(),
+        catalog: Catalog::new(&config.catalog),
+        ignore: IgnoreList::new(&config.monitoring.ignore),
+        integrations: IntegrationRegistry::from_config(&config),
+        integration_units: HashMap::new(),
+        integration_health: Vec::new(),
+        public_tunnels: Vec::new(),
+        proxy_routes: Vec::new(),
+        listener_health: HashMap::new(),
     };
 
     let event_loop = EventLoop::<UserEvent>::with_user_event()
@@ -60,10 +79,18 @@ pub fn run() -> Result<()> {
         .context("failed to create event loop")?;
     let proxy = event_loop.create_proxy();
     let (worker_tx, worker_rx) = crossbeam_channel::unbounded();
+    let (refresh_tx, refresh_rx) = crossbeam_channel::unbounded();
+    let (probe_tx, probe_rx) = crossbeam_channel::unbounded();
+    let (git_tx, git_rx) = crossbeam_channel::unbounded();
+    let (ignore_tx, ignore_rx) = crossbeam_channel::unbounded();
 
-    let _monitor_thread = spawn_monitor_thread(proxy.clone(), shared_config.clone());
-    let _config_watcher = spawn_config_watcher(proxy.clone(), shared_config.clone());
+    let _monitor_thread = spawn_monitor_thread(proxy.clone(), shared_config.clone(), port_override);
+    let _config_watcher = spawn_config_watcher(proxy.clone());
     let _worker = spawn_worker(worker_rx, proxy.clone());
+    let _integration_refresher = spawn_integration_refresher(refresh_rx, proxy.clone());
+    let _health_prober = spawn_health_prober(probe_rx, proxy.clone());
+    let _git_status_reader = spawn_git_status_reader(git_rx, proxy.clone());
+    let _ignore_details_reader = spawn_ignore_details_reader(ignore_rx, proxy.clone());
     let menu_receiver = MenuEvent::receiver().clone();
 
     let icon =
@@ -88,6 +115,8 @@ pub fn run() -> Result<()> {
     let mut worker_sender: Option<Sender<WorkerCommand>> = Some(worker_tx);
     // Initialize to past time to force first integration refresh
     let mut last_integration_refresh = Instant::now() - INTEGRATION_REFRESH_INTERVAL;
+    let mut last_health_probe: Option<Instant> = None;
+    let mut last_git_status: Option<Instant> = None;
     // Clone shared_config for use in event loop (for manual reload)
     let shared_config_for_loop = shared_config.clone();
 
@@ -119,45 +148,100 @@ pub fn run() -> Result<()> {
                 // Refresh integrations when ports change OR on timer (to catch external changes)
                 let timer_refresh =
                     last_integration_refresh.elapsed() >= INTEGRATION_REFRESH_INTERVAL;
+                // External commands run on the refresher thread; results arrive as IntegrationsUpdated
                 if ports_changed || timer_refresh {
                     last_integration_refresh = Instant::now();
-                    if state.config.integrations.docker_enabled {
-                        state.docker_port_map = query_docker_port_map().unwrap_or_default();
-                    }
-                    #[cfg(target_os = "macos")]
-                    if state.config.integrations.brew_enabled {
-                        state.brew_services_map = query_brew_services_map().unwrap_or_default();
-                    }
-                    #[cfg(target_os = "windows")]
-                    if state.config.integrations.windows_services_enabled {
-                        state.windows_services_map =
-                            query_windows_services_map().unwrap_or_default();
-                    }
+                    let _ = refresh_tx.send(state.integrations.clone());
                 }
-                // Clear maps if integrations disabled (check every time)
-                if !state.config.integrations.docker_enabled {
-                    state.docker_port_map.clear();
+                // Probe listeners on the prober thread; results arrive as HealthUpdated
+                let health = &state.config.health;
+                let probe_due = last_health_probe
+                    .is_none_or(|at| at.elapsed() >= Duration::from_secs(health.interval_secs));
+                if health.enabled && (ports_changed || probe_due) {
+                    last_health_probe = Some(Instant::now());
+                    let mut listeners: Vec<crate::probe::Listener> = state
+                        .processes
+                        .iter()
+                        .map(|p| (p.port, p.address))
+                        .collect();
+                    listeners.sort();
+                    listeners.dedup_by_key(|(port, _)| *port);
+                    let _ = probe_tx.send((listeners, health.clone()));
                 }
-                #[cfg(target_os = "macos")]
-                if !state.config.integrations.brew_enabled {
-                    state.brew_services_map.clear();
+                // Derive project info in best-effort mode
+                let new_work_tree = state
+                    .project_cache
+                    .resolve_missing(&state.processes, &state.config.projects);
+                // Git state is read on its own thread; results arrive as GitStatusUpdated
+                let git_due = last_git_status.is_none_or(|at| at.elapsed() >= GIT_STATUS_INTERVAL);
+                if new_work_tree || git_due {
+                    last_git_status = Some(Instant::now());
+                    let _ = git_tx.send(state.project_cache.work_trees());
                 }
-                #[cfg(target_os = "windows")]
-                if !state.config.integrations.windows_services_enabled {
-                    state.windows_services_map.clear();
+                // Newly seen projects may bring their own config
+                if state
+                    .config_layers
+                    .add_projects(state.project_cache.projects())
+                {
+                    match reload_layers(&mut state, &shared_config_for_loop, &refresh_tx) {
+                        Ok(feedback) => {
+                            if feedback.is_some() {
+                                state.last_feedback = feedback;
+                            }
+                        }
+                        Err(e) => {
+                            state.last_feedback =
+                                Some(KillFeedback::error(format!("Project config failed: {}", e)));
+                        }
+                    }
+                }
+                state.catalog.read_missing(&state.processes);
+                // Ignore rules' process details are read on their own thread
+                if let Some(request) = state.ignore.request_missing(&state.processes) {
+                    let _ = ignore_tx.send(request);
                 }
-                // Derive project info in best-effort mode
-                refresh_projects_for(&mut state);
                 // Notifications on change (before cache cleanup so stopped ports still have project info)
                 maybe_notify_changes(&state, &prev);
                 // Clean up stale cache entries for terminated processes
-                let active_pids: HashSet<i32> = state.processes.iter().map(|p| p.pid).collect();
-                state
-                    .project_cache
-                    .retain(|pid, _| active_pids.contains(pid));
+                state.project_cache.retain_active(&state.processes);
+                state.catalog.retain_active(&state.processes);
+                state.ignore.retain_active(&state.processes);
                 sync_menu_with_context(&tray_icon, &state);
                 update_tray_display(&tray_icon, &state);
             }
+            UserEvent::IntegrationsUpdated(mut snapshot) => {
+                // Drop results for integrations disabled while the refresh was running
+                snapshot
+                    .units
+                    .retain(|id, _| state.integrations.get(id).is_some());
+                state.integration_units = snapshot.units;
+                state.integration_health = snapshot.health;
+                let prev_tunnels = std::mem::replace(&mut state.public_tunnels, snapshot.tunnels);
+                maybe_notify_tunnels(&state, &prev_tunnels);
+                state.proxy_routes = snapshot.routes;
+                sync_menu_with_context(&tray_icon, &state);
+                update_tray_display(&tray_icon, &state);
+            }
+            UserEvent::HealthUpdated(mut health) => {
+                // Drop ports that closed (or probing that was turned off) while probing
+                let ports: HashSet<u16> = state.processes.iter().map(|p| p.port).collect();
+                health.retain(|port, _| state.config.health.enabled && ports.contains(port));
+                if health != state.listener_health {
+                    state.listener_health = health;
+                    sync_menu_with_context(&tray_icon, &state);
+                }
+            }
+            UserEvent::GitStatusUpdated(statuses) => {
+                if state.project_cache.apply_git_status(&statuses) {
+                    sync_menu_with_context(&tray_icon, &state);
+                }
+            }
+            UserEvent::IgnoreDetailsRead(details) => {
+                if state.ignore.apply_details(details) {
+                    sync_menu_with_context(&tray_icon, &state);
+                    update_tray_display(&tray_icon, &state);
+                }
+            }
             UserEvent::MenuAction(action) => match action {
                 MenuAction::EditConfig => {
                     let config_path = get_config_path();
@@ -175,6 +259,113 @@ pub fn run() -> Result<()> {
                     )));
                     update_tray_display(&tray_icon, &state);
                 }
+                MenuAction::ShowEffectiveConfig => {
+                    let report = state
+                        .config_origins
+                        .report(&state.config, &state.config_layers.untrusted);
+                    let written = ensure_log_dir().and_then(|dir| {
+                        let path = dir.join("effective-config.txt");
+                        std::fs::write(&path, report)?;
+                        open_path(&path)?;
+                        Ok(path)
+                    });
+                    state.last_feedback = Some(match written {
+                        Ok(path) => KillFeedback::info(format!(
+                            "Opened effective config: {}",
+                            path.display()
+                        )),
+                        Err(e) => {
+                            KillFeedback::error(format!("Failed to show effective config: {}", e))
+                        }
+                    });
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::OpenTunnelUrl { port, index } => {
+                    let url = state
+                        .public_tunnels
+                        .iter()
+                        .filter(|t| t.port == port)
+                        .nth(index)
+                        .and_then(|t| t.url.clone());
+                    state.last_feedback = Some(match url {
+                        Some(url) => match open_url(&url) {
+                            Ok(()) => KillFeedback::info(format!("Opened {}", url)),
+                            Err(err) => {
+                                KillFeedback::error(format!("Failed to open {}: {}", url, err))
+                            }
+                        },
+                        None => KillFeedback::warning(format!(
+                            "Port {} is no longer exposed publicly",
+                            port
+                        )),
+                    });
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::OpenRoute { port, index } => {
+                    let url = state
+                        .proxy_routes
+                        .iter()
+                        .filter(|r| r.port == port)
+                        .nth(index)
+                        .map(|r| r.url.clone());
+                    state.last_feedback = Some(match url {
+                        Some(url) => match open_url(&url) {
+                            Ok(()) => KillFeedback::info(format!("Opened {}", url)),
+                            Err(err) => {
+                                KillFeedback::error(format!("Failed to open {}: {}", url, err))
+                            }
+                        },
+                        None => KillFeedback::warning(format!(
+                            "Port {} is no longer routed by a proxy",
+                            port
+                        )),
+                    });
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::OpenLocalhost { port } => {
+                    let url = state.local_url(port);
+                    state.last_feedback = Some(match open_url(&url) {
+                        Ok(()) => KillFeedback::info(format!("Opened {}", url)),
+                        Err(err) => KillFeedback::error(format!("Failed to open {}: {}", url, err)),
+                    });
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::OpenProjectFolder { pid } | MenuAction::OpenInEditor { pid } => {
+                    let project = describe_pid(pid, &state.processes)
+                        .and_then(|target| state.project_cache.get(&target.process))
+                        .map(|project| project.path.clone());
+                    state.last_feedback = Some(match project {
+                        Some(path) => {
+                            let result = if matches!(action, MenuAction::OpenInEditor { .. }) {
+                                open_in_editor(&state.config.projects.editor_command, &path)
+                            } else {
+                                open_path(&path).map_err(anyhow::Error::from)
+                            };
+                            match result {
+                                Ok(()) => KillFeedback::info(format!("Opened {}", path.display())),
+                                Err(err) => KillFeedback::error(format!(
+                                    "Failed to open {}: {}",
+                                    path.display(),
+                                    err
+                                )),
+                            }
+                        }
+                        None => KillFeedback::warning(format!(
+                            "No project folder known for PID {}",
+                            pid
+                        )),
+                    });
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::CopyPid { pid } => {
+                    state.last_feedback = Some(copy_feedback(&pid.to_string()));
+                    update_tray_display(&tray_icon, &state);
+                }
+                MenuAction::CopyUrl { port } => {
+                    let url = state.local_url(port);
+                    state.last_feedback = Some(copy_feedback(&url));
+                    update_tray_display(&tray_icon, &state);
+                }
                 MenuAction::LaunchAtLogin => {
                     use crate::launch::{
                         disable_launch_at_login, enable_launch_at_login, is_launch_at_login_enabled,
@@ -194,7 +385,10 @@ pub fn run() -> Result<()> {
                             match is_launch_at_login_enabled() {
                                 Ok(actual_state) => {
                                     state.config.system.launch_at_login = actual_state;
-                                    if let Err(e) = save_config(&state.config) {
+                                    if let Err(e) = set_user_setting(
+                                        &["system", "launch_at_login"],
+                                        actual_state.into(),
+                                    ) {
                                         state.last_feedback = Some(KillFeedback::error(format!(
                                             "Failed to save config: {}",
                                             e
@@ -224,7 +418,10 @@ pub fn run() -> Result<()> {
                                 match is_launch_at_login_enabled() {
                                     Ok(actual_state) => {
                                         state.config.system.launch_at_login = actual_state;
-                                        let _ = save_config(&state.config);
+                                        let _ = set_user_setting(
+                                            &["system", "launch_at_login"],
+                                            actual_state.into(),
+                                        );
                                         state.last_feedback = Some(KillFeedback::warning(
                                             "Please approve in System Settings > Login Items"
                                                 .to_string(),
@@ -249,10 +446,12 @@ pub fn run() -> Result<()> {
                     sync_menu_with_context(&tray_icon, &state);
                     update_tray_display(&tray_icon, &state);
                 }
-                MenuAction::KillPid { pid, .. } => {
+                MenuAction::KillPid { pid } | MenuAction::ForceKillPid { pid } => {
+                    let force = matches!(action, MenuAction::ForceKillPid { .. });
                     if let Some(target) = describe_pid(pid, &state.processes) {
                         if let Some(sender) = worker_sender.as_ref() {
-                            if let Err(err) = sender.send(WorkerCommand::KillPid(target)) {
+                            if let Err(err) = sender.send(WorkerCommand::KillPid { target, force })
+                            {
                                 let feedback = KillFeedback::error(format!(
                                     "Unable to dispatch kill command: {}",
                                     err
@@ -275,38 +474,48 @@ pub fn run() -> Result<()> {
                         update_tray_display(&tray_icon, &state);
                     }
                 }
+                MenuAction::RestartPid { pid } => {
+                    if let Some(target) = describe_pid(pid, &state.processes) {
+                        let mut ports: Vec<u16> = state
+                            .processes
+                            .iter()
+                            .filter(|p| p.pid == pid)
+                            .map(|p| p.port)
+                            .collect();
+                        ports.sort();
+                        ports.dedup();
+                        let dispatched = worker_sender.as_ref().is_some_and(|sender| {
+                            sender
+                                .send(WorkerCommand::RestartPid { target, ports })
+                                .is_ok()
+                        });
+                        if !dispatched {
+                            state.last_feedback = Some(KillFeedback::error(format!(
+                                "Worker unavailable for PID {}.",
+                                pid
+                            )));
+                            update_tray_display(&tray_icon, &state);
+                        }
+                    } else {
+                        state.last_feedback = Some(KillFeedback::info(format!(
+                            "PID {} is no longer active.",
+                            pid
+                        )));
+                        update_tray_display(&tray_icon, &state);
+                    }
+                }
                 MenuAction::KillAll => {
-                    // Filter to only regular processes (exclude Docker and managed services)
+                    // Filter to only regular processes (exclude anything an integration
+                    // manages, hidden listeners and listeners on protected ranges)
                     let regular_processes: Vec<ProcessInfo> = state
                         .processes
                         .iter()
+                        .filter(|p| state.integration_for(p).is_none() && !state.is_hidden(p))
                         .filter(|p| {
-                            // Exclude Docker containers
-                            if state.docker_port_map.contains_key(&p.port) {
-                                return false;
-                            }
-                            // Exclude managed services (Brew on macOS, Windows Services on Windows)
-                            #[cfg(target_os = "macos")]
-                            if crate::integrations::brew::get_brew_managed_service(
-                                &p.command,
-                                p.port,
-                                &state.brew_services_map,
-                            )
-                            .is_some()
-                            {
-                                return false;
-                            }
-                            #[cfg(target_os = "windows")]
-                            if crate::integrations::windows_services::get_windows_managed_service(
-                                &p.command,
-                                p.port,
-                                &state.windows_services_map,
-                            )
-                            .is_some()
-                            {
-                                return false;
-                            }
-                            true
+                            !state.processes.iter().any(|other| {
+                                other.identity() == p.identity()
+                                    && state.config.monitoring.is_protected(other.port)
+                            })
                         })
                         .cloned()
                         .collect();
@@ -338,101 +547,84 @@ pub fn run() -> Result<()> {
                 MenuAction::Quit => {
                     event_loop.exit();
                 }
-                MenuAction::DockerStop { container } => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        let _ = sender.send(WorkerCommand::DockerStop { container });
-                    }
-                }
-                MenuAction::DockerStopAll => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        // Collect all unique Docker containers from current processes
-                        let containers: Vec<String> = state
-                            .docker_port_map
-                            .values()
-                            .map(|dc| dc.name.clone())
-                            .collect::<HashSet<_>>()
-                            .into_iter()
-                            .collect();
-
-                        for container in containers {
-                            let _ = sender.send(WorkerCommand::DockerStop {
-                                container: container.clone(),
-                            });
+                MenuAction::IntegrationStop { integration, unit } => {
+                    match state.integrations.get(&integration) {
+                        Some(integration) => {
+                            if let Some(sender) = worker_sender.as_ref() {
+                                let _ = sender.send(WorkerCommand::IntegrationStop {
+                                    integration: integration.clone(),
+                                    unit,
+                                });
+                            }
                         }
-                    }
-                }
-                #[cfg(target_os = "macos")]
-                MenuAction::BrewStop { service } => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        let _ = sender.send(WorkerCommand::BrewStop { service });
-                    }
-                }
-                #[cfg(target_os = "macos")]
-                MenuAction::BrewStopAll => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        // Collect all unique brew services from current processes
-                        let services: Vec<String> = state
-                            .processes
-                            .iter()
-                            .filter_map(|p| {
-                                crate::integrations::brew::get_brew_managed_service(
-                                    &p.command,
-                                    p.port,
-                                    &state.brew_services_map,
-                                )
-                            })
-                            .collect::<HashSet<_>>()
-                            .into_iter()
-                            .collect();
-
-                        for service in services {
-                            let _ = sender.send(WorkerCommand::BrewStop {
-                                service: service.clone(),
-                            });
+                        None => {
+                            state.last_feedback = Some(KillFeedback::warning(format!(
+                                "Integration {} is no longer enabled.",
+                                integration
+                            )));
+                            update_tray_display(&tray_icon, &state);
                         }
                     }
                 }
-                #[cfg(target_os = "windows")]
-                MenuAction::WindowsServiceStop { service } => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        let _ = sender.send(WorkerCommand::WindowsServiceStop { service });
+                MenuAction::IntegrationAction {
+                    integration,
+                    action,
+                    unit,
+                } => {
+                    if let Some(integration) = state.integrations.get(&integration)
+                        && let Some(sender) = worker_sender.as_ref()
+                    {
+                        let _ = sender.send(WorkerCommand::IntegrationAction {
+                            integration: integration.clone(),
+                            action,
+                            unit,
+                        });
                     }
                 }
-                #[cfg(target_os = "windows")]
-                MenuAction::WindowsServiceStopAll => {
-                    if let Some(sender) = worker_sender.as_ref() {
-                        // Collect all unique Windows services from current processes
-                        let services: Vec<String> = state
+                MenuAction::IntegrationStopAll { integration } => {
+                    if let Some(integration) = state.integrations.get(&integration)
+                        && let Some(sender) = worker_sender.as_ref()
+                    {
+                        // Collect all unique units claimed by this integration
+                        let mut units: HashSet<String> = state
                             .processes
                             .iter()
                             .filter_map(|p| {
-                                crate::integrations::windows_services::get_windows_managed_service(
-                                    &p.command,
-                                    p.port,
-                                    &state.windows_services_map,
-                                )
+                                state
+                                    .integration_for(p)
+                                    .filter(|(owner, _)| owner.id() == integration.id())
+                                    .map(|(_, unit)| unit)
                             })
-                            .collect::<HashSet<_>>()
-                            .into_iter()
                             .collect();
-
-                        for service in services {
-                            let _ = sender.send(WorkerCommand::WindowsServiceStop {
-                                service: service.clone(),
-                            });
+                        // ...plus the idle units the menu lists for it
+                        if integration.lists_idle_units()
+                            && let Some(idle) = state.integration_units.get(integration.id())
+                        {
+                            units.extend(idle.iter().map(|unit| unit.name.clone()));
                         }
+                        let units: Vec<String> = units.into_iter().collect();
+
+                        let _ = sender.send(WorkerCommand::IntegrationStopAll {
+                            integration: integration.clone(),
+                            units,
+                        });
                     }
                 }
                 MenuAction::ReloadConfig => {
-                    match load_and_validate_config() {
-                        Ok(new_config) => {
-                            // Update shared config for monitor thread
-                            if let Ok(mut cfg) = shared_config_for_loop.write() {
-                                *cfg = new_config.clone();
-                            }
-                            state.config = new_config;
-                            state.last_feedback =
-                                Some(KillFeedback::info("Configuration reloaded".to_string()));
+                    let reloaded = load_base_layers().and_then(|base| {
+                        let previous = std::mem::replace(&mut state.config_layers.base, base);
+                        state.config_layers.reload_projects();
+                        reload_layers(&mut state, &shared_config_for_loop, &refresh_tx).inspect_err(
+                            |_| {
+                                state.config_layers.base = previous;
+                            },
+                        )
+                    });
+                    match reloaded {
+                        Ok(feedback) => {
+                            state.last_feedback = Some(feedback.unwrap_or_else(|| {
+                                KillFeedback::info("Configuration reloaded".to_string())
+                            }));
                         }
                         Err(e) => {
                             state.last_feedback =
@@ -452,10 +644,23 @@ pub fn run() -> Result<()> {
                 state.last_feedback = Some(KillFeedback::error(message));
                 update_tray_display(&tray_icon, &state);
             }
-            UserEvent::ConfigReloaded(new_config) => {
-                state.config = new_config;
-                state.last_feedback =
-                    Some(KillFeedback::info("Configuration reloaded".to_string()));
+            UserEvent::ConfigReloaded(base) => {
+                let previous = std::mem::replace(&mut state.config_layers.base, base);
+                // The trusted roots may have changed with the user's file
+                state.config_layers.reload_projects();
+                match reload_layers(&mut state, &shared_config_for_loop, &refresh_tx) {
+                    Ok(feedback) => {
+                        state.last_feedback = Some(feedback.unwrap_or_else(|| {
+                            KillFeedback::info("Configuration reloaded".to_string())
+                        }));
+                    }
+                    Err(e) => {
+                        state.config_layers.base = previous;
+                        let msg = format!("Config reload failed: {:#}", e);
+                        warn!("{}", msg);
+                        state.last_feedback = Some(KillFeedback::error(msg));
+                    }
+                }
                 sync_menu_with_context(&tray_icon, &state);
                 update_tray_display(&tray_icon, &state);
             }
@@ -474,19 +679,40 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// `--ports "3000-3010, !3001"` (or `--ports=all`) monitors these ports instead
+/// of the configured ranges for this run.
+fn ports_from_args() -> Result<Option<PortSet>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let spec = match arg.strip_prefix("--ports=") {
+            Some(spec) => spec.to_string(),
+            None if arg == "--ports" => args.next().context("--ports needs a value")?,
+            None => continue,
+        };
+        return spec
+            .parse()
+            .map(Some)
+            .map_err(|err: String| anyhow::anyhow!("--ports: {}", err));
+    }
+    Ok(None)
+}
+
 fn spawn_monitor_thread(
     proxy: EventLoopProxy<UserEvent>,
     shared_config: Arc<RwLock<crate::config::Config>>,
+    port_override: Option<PortSet>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let mut previous: Vec<ProcessInfo> = Vec::new();
         let mut last_change = Instant::now();
         loop {
             // Read current config at each iteration to pick up hot-reloaded changes
-            let (port_ranges, poll_interval_secs) = {
+            let (ports, poll_interval_secs) = {
                 let cfg = shared_config.read().unwrap();
                 (
-                    cfg.monitoring.port_ranges.clone(),
+                    port_override
+                        .clone()
+                        .unwrap_or_else(|| cfg.monitoring.port_set()),
                     cfg.monitoring.poll_interval_secs,
                 )
             };
@@ -494,7 +720,7 @@ fn spawn_monitor_thread(
             let poll_interval_idle = Duration::from_secs(poll_interval_secs * IDLE_MULTIPLIER);
 
             let scan_start = Instant::now();
-            match scan_ports(&port_ranges) {
+            match scan_ports(&ports) {
                 Ok(mut processes) => {
                     let scan_duration = scan_start.elapsed();
                     processes.sort();
@@ -542,10 +768,7 @@ fn spawn_monitor_thread(
 
 const CONFIG_DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
 
-fn spawn_config_watcher(
-    proxy: EventLoopProxy<UserEvent>,
-    shared_config: Arc<RwLock<crate::config::Config>>,
-) -> thread::JoinHandle<()> {
+fn spawn_config_watcher(proxy: EventLoopProxy<UserEvent>) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let config_path = get_config_path();
         let (tx, rx) = std::sync::mpsc::channel();
@@ -586,14 +809,10 @@ fn spawn_config_watcher(
 
                         log::debug!("Config file changed, attempting reload");
 
-                        // Attempt to load and validate
-                        match load_and_validate_config() {
-                            Ok(new_config) => {
-                                // Update shared config for monitor thread
-                                if let Ok(mut cfg) = shared_config.write() {
-                                    *cfg = new_config.clone();
-                                }
-                                let _ = proxy.send_event(UserEvent::ConfigReloaded(new_config));
+                        // Merged with the project layers and validated on the UI thread
+                        match load_base_layers() {
+                            Ok(base) => {
+                                let _ = proxy.send_event(UserEvent::ConfigReloaded(base));
                             }
                             Err(e) => {
                                 let msg = format!("Config reload failed: {}", e);
@@ -616,22 +835,30 @@ fn spawn_worker(
     proxy: EventLoopProxy<UserEvent>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
+        let later = FeedbackSender::new(proxy.clone());
         for command in rx.iter() {
             let should_continue = match command {
-                WorkerCommand::KillPid(target) => handle_single_kill(&proxy, target),
+                WorkerCommand::KillPid { target, force } => {
+                    handle_single_kill(&proxy, target, force)
+                }
+                WorkerCommand::RestartPid { target, ports } => {
+                    handle_restart(&proxy, target, &ports)
+                }
                 WorkerCommand::KillAll(targets) => handle_batch_kill(&proxy, targets),
-                WorkerCommand::DockerStop { container } => {
-                    let feedback = run_docker_stop(&container);
+                WorkerCommand::IntegrationStop { integration, unit } => {
+                    let feedback = integration.stop(&unit);
                     proxy.send_event(UserEvent::KillFeedback(feedback)).is_ok()
                 }
-                #[cfg(target_os = "macos")]
-                WorkerCommand::BrewStop { service } => {
-                    let feedback = run_brew_stop(&service);
+                WorkerCommand::IntegrationStopAll { integration, units } => {
+                    let feedback = integration.stop_all(&units);
                     proxy.send_event(UserEvent::KillFeedback(feedback)).is_ok()
                 }
-                #[cfg(target_os = "windows")]
-                WorkerCommand::WindowsServiceStop { service } => {
-                    let feedback = run_service_stop(&service);
+                WorkerCommand::IntegrationAction {
+                    integration,
+                    action,
+                    unit,
+                } => {
+                    let feedback = integration.run_action(&action, &unit, &later);
                     proxy.send_event(UserEvent::KillFeedback(feedback)).is_ok()
                 }
             };
@@ -642,33 +869,228 @@ fn spawn_worker(
     })
 }
 
-fn handle_single_kill(proxy: &EventLoopProxy<UserEvent>, target: KillTarget) -> bool {
-    let outcome = terminate_pid(target.pid);
-    let feedback = match outcome {
-        KillOutcome::Success => {
-            KillFeedback::info(format!("Terminated {} (PID {}).", target.label, target.pid))
+fn spawn_integration_refresher(
+    rx: Receiver<IntegrationRegistry>,
+    proxy: EventLoopProxy<UserEvent>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        // Breakers outlive registry rebuilds so a config reload doesn't reset
+        // backoff or drop the last units of a failing integration
+        let mut refreshes: HashMap<String, GuardedRefresh<Vec<ManagedUnit>>> = HashMap::new();
+        let mut tunnel_refresh: GuardedRefresh<Vec<PublicTunnel>> = GuardedRefresh::default();
+
+        while let Ok(mut registry) = rx.recv() {
+            // Coalesce requests that queued up while the previous refresh was running
+            while let Ok(newer) = rx.try_recv() {
+                registry = newer;
+            }
+
+            let mut snapshot = IntegrationSnapshot::default();
+            for integration in registry.iter() {
+                let refresh = refreshes.entry(integration.id().to_string()).or_default();
+                let units = refresh.run(integration.display_name(), Instant::now(), || {
+                    integration.refresh()
+                });
+                snapshot.units.insert(integration.id().to_string(), units);
+                snapshot.health.push((
+                    integration.display_name().to_string(),
+                    refresh.health(Instant::now()),
+                ));
+            }
+            if registry.detects_tunnels() {
+                snapshot.tunnels = tunnel_refresh.run(
+                    tunnels::DISPLAY_NAME,
+                    Instant::now(),
+                    detect_public_tunnels,
+                );
+                snapshot.health.push((
+                    tunnels::DISPLAY_NAME.to_string(),
+                    tunnel_refresh.health(Instant::now()),
+                ));
+            }
+
+            snapshot.routes = routes::load_routes(registry.proxy_configs());
+
+            if proxy
+                .send_event(UserEvent::IntegrationsUpdated(snapshot))
+                .is_err()
+            {
+                break;
+            }
+        }
+    })
+}
+
+fn spawn_health_prober(
+    rx: Receiver<(Vec<crate::probe::Listener>, crate::config::HealthConfig)>,
+    proxy: EventLoopProxy<UserEvent>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        while let Ok(mut request) = rx.recv() {
+            // Only the latest port list matters
+            while let Ok(newer) = rx.try_recv() {
+                request = newer;
+            }
+            let (listeners, config) = request;
+            let health = crate::probe::probe_ports(&listeners, &config);
+            if proxy.send_event(UserEvent::HealthUpdated(health)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn spawn_ignore_details_reader(
+    rx: Receiver<crate::ignore::DetailsRequest>,
+    proxy: EventLoopProxy<UserEvent>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        // Each request covers different processes, so none are skipped
+        while let Ok(request) = rx.recv() {
+            if proxy
+                .send_event(UserEvent::IgnoreDetailsRead(request.read()))
+                .is_err()
+            {
+                break;
+            }
         }
+    })
+}
+
+fn spawn_git_status_reader(
+    rx: Receiver<Vec<std::path::PathBuf>>,
+    proxy: EventLoopProxy<UserEvent>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = GitStatusReader::default();
+        while let Ok(mut work_trees) = rx.recv() {
+            // Only the latest list matters
+            while let Ok(newer) = rx.try_recv() {
+                work_trees = newer;
+            }
+            let statuses = reader.read_all(&work_trees);
+            if proxy
+                .send_event(UserEvent::GitStatusUpdated(statuses))
+                .is_err()
+            {
+                break;
+            }
+        }
+    })
+}
+
+fn copy_feedback(text: &str) -> KillFeedback {
+    match copy_to_clipboard(text) {
+        Ok(()) => KillFeedback::info(format!("Copied {}", text)),
+        Err(err) => KillFeedback::error(format!("Failed to copy {}: {}", text, err)),
+    }
+}
+
+/// Run the configured editor on `path`, substituted for `{path}` or appended.
+fn open_in_editor(command: &[String], path: &std::path::Path) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("projects.editor_command is empty"))?;
+    let path = path.to_string_lossy();
+    let mut args: Vec<String> = args.to_vec();
+    if args.iter().any(|a| a.contains("{path}")) {
+        args = args.iter().map(|a| a.replace("{path}", &path)).collect();
+    } else {
+        args.push(path.into_owned());
+    }
+    // Editors are usually `.cmd` shims on Windows. Run by full path, std passes
+    // them to cmd.exe with each argument escaped, and refuses ones it can't escape.
+    let mut cmd = hidden_command(if program.contains(['/', '\\']) {
+        program.as_str()
+    } else {
+        crate::utils::find_command(program)
+    });
+    cmd.args(&args)
+        .spawn()
+        .with_context(|| format!("failed to run {}", program))?;
+    Ok(())
+}
+
+/// Terminate a scanned process, unless its PID now belongs to a newer process.
+/// `force` skips the graceful shutdown window.
+fn terminate_process(process: ProcessIdentity, force: bool) -> KillOutcome {
+    if !process.is_current() {
+        warn!(
+            "PID {} now belongs to a different process, skipping kill",
+            process.pid
+        );
+        return KillOutcome::AlreadyExited;
+    }
+    if force {
+        force_kill_pid(process.pid)
+    } else {
+        terminate_pid(process.pid)
+    }
+}
+
+fn handle_single_kill(proxy: &EventLoopProxy<UserEvent>, target: KillTarget, force: bool) -> bool {
+    let outcome = terminate_process(target.process, force);
+    let feedback = match outcome {
+        KillOutcome::Success => KillFeedback::info(format!(
+            "{} {} (PID {}).",
+            if force { "Force killed" } else { "Terminated" },
+            target.label,
+            target.process.pid
+        )),
         KillOutcome::AlreadyExited => KillFeedback::warning(format!(
             "{} (PID {}) was already stopped.",
-            target.label, target.pid
+            target.label, target.process.pid
         )),
         KillOutcome::PermissionDenied => KillFeedback::error(format!(
             "Permission denied terminating {} (PID {}).",
-            target.label, target.pid
+            target.label, target.process.pid
         )),
         KillOutcome::TimedOut => KillFeedback::error(format!(
             "Timed out terminating {} (PID {}).",
-            target.label, target.pid
+            target.label, target.process.pid
         )),
         KillOutcome::Failed(err) => KillFeedback::error(format!(
             "Failed to terminate {} (PID {}): {}.",
-            target.label, target.pid, err
+            target.label, target.process.pid, err
         )),
     };
 
     proxy.send_event(UserEvent::KillFeedback(feedback)).is_ok()
 }
 
+fn handle_restart(proxy: &EventLoopProxy<UserEvent>, target: KillTarget, ports: &[u16]) -> bool {
+    let feedback = match restart_process(&target, ports) {
+        Ok(new_pid) => KillFeedback::info(format!(
+            "Restarted {} (PID {} → {}).",
+            target.label, target.process.pid, new_pid
+        )),
+        Err(err) => KillFeedback::error(format!(
+            "Failed to restart {} (PID {}): {}.",
+            target.label, target.process.pid, err
+        )),
+    };
+    proxy.send_event(UserEvent::KillFeedback(feedback)).is_ok()
+}
+
+/// Stop a process and start it again with the same command, directory and environment.
+fn restart_process(target: &KillTarget, ports: &[u16]) -> Result<i32> {
+    // Capture before killing (afterwards there's nothing left to read), and
+    // only if the PID still belongs to the process the menu showed
+    if !target.process.is_current() {
+        bail!("it has already exited");
+    }
+    let launch = LaunchSpec::capture(target.process.pid)?;
+    let log_path = restart::log_path(ports.first().copied().unwrap_or_default())?;
+    match terminate_process(target.process, false) {
+        KillOutcome::Success | KillOutcome::AlreadyExited => {}
+        KillOutcome::PermissionDenied => bail!("permission denied stopping it"),
+        KillOutcome::TimedOut => bail!("timed out stopping it"),
+        KillOutcome::Failed(err) => bail!("could not stop it: {}", err),
+    }
+    restart::wait_for_ports_free(ports, restart::PORT_RELEASE_TIMEOUT)?;
+    launch.spawn(&log_path)
+}
+
 fn handle_batch_kill(proxy: &EventLoopProxy<UserEvent>, targets: Vec<KillTarget>) -> bool {
     if targets.is_empty() {
         return proxy
@@ -685,7 +1107,7 @@ fn handle_batch_kill(proxy: &EventLoopProxy<UserEvent>, targets: Vec<KillTarget>
     let mut failures: Vec<(KillTarget, i32)> = Vec::new();
 
     for target in targets {
-        match terminate_pid(target.pid) {
+        match terminate_process(target.process, false) {
             KillOutcome::Success => successes += 1,
             KillOutcome::AlreadyExited => already += 1,
             KillOutcome::PermissionDenied => {
@@ -740,7 +1162,7 @@ fn handle_batch_kill(proxy: &EventLoopProxy<UserEvent>, targets: Vec<KillTarget>
     if let Some((failed_target, err)) = failures.first() {
         message.push_str(&format!(
             " First failure: {} (PID {}) — {}.",
-            failed_target.label, failed_target.pid, err
+            failed_target.label, failed_target.process.pid, err
         ));
     }
 
@@ -757,7 +1179,12 @@ fn handle_batch_kill(proxy: &EventLoopProxy<UserEvent>, targets: Vec<KillTarget>
 fn describe_pid(pid: i32, processes: &[ProcessInfo]) -> Option<KillTarget> {
     let mut ports = Vec::new();
     let mut command: Option<String> = None;
+    let mut identity = ProcessIdentity {
+        pid,
+        start_time: None,
+    };
     for process in processes.iter().filter(|p| p.pid == pid) {
+        identity = process.identity();
         if !ports.contains(&process.port) {
             ports.push(process.port);
         }
@@ -772,7 +1199,10 @@ fn describe_pid(pid: i32, processes: &[ProcessInfo]) -> Option<KillTarget> {
 
     ports.sort();
     let label = format_command_label(command.as_deref().unwrap_or(""), &ports);
-    Some(KillTarget { pid, label })
+    Some(KillTarget {
+        process: identity,
+        label,
+    })
 }
 
 // format_command_label now in ui::menu
@@ -781,6 +1211,62 @@ fn describe_pid(pid: i32, processes: &[ProcessInfo]) -> Option<KillTarget> {
 
 // process_menu_id moved to ui::menu
 
+/// Resolve the config layers and, when the result differs, swap it in (also
+/// for the monitor thread) and refresh the integrations.
+/// Returns a warning when project layers had to be skipped.
+fn reload_layers(
+    state: &mut AppState,
+    shared_config: &RwLock<crate::config::Config>,
+    refresh_tx: &Sender<IntegrationRegistry>,
+) -> Result<Option<KillFeedback>> {
+    let effective = state.config_layers.resolve()?;
+    state.config_origins = effective.origins;
+    let changed =
+        serde_json::to_value(&effective.config).ok() != serde_json::to_value(&state.config).ok();
+    if changed {
+        if let Ok(mut cfg) = shared_config.write() {
+            *cfg = effective.config.clone();
+        }
+        apply_config(state, effective.config);
+        let _ = refresh_tx.send(state.integrations.clone());
+    }
+    for skipped in &effective.skipped {
+        warn!("Skipped project config {}", skipped);
+    }
+    Ok(effective
+        .skipped
+        .first()
+        .map(|first| KillFeedback::warning(format!("Skipped project config {}", first))))
+}
+
+/// Swap in a new config and rebuild everything derived from it.
+fn apply_config(state: &mut AppState, config: crate::config::Config) {
+    state.integrations = IntegrationRegistry::from_config(&config);
+    let integrations = &state.integrations;
+    state
+        .integration_units
+        .retain(|id, _| integrations.get(id).is_some());
+    state.integration_health.retain(|(name, _)| {
+        integrations.iter().any(|i| i.display_name() == name)
+            || (name == tunnels::DISPLAY_NAME && integrations.detects_tunnels())
+    });
+    if !integrations.detects_tunnels() {
+        state.public_tunnels.clear();
+    }
+    if !config.health.enabled {
+        state.listener_health.clear();
+    }
+    // Overrides or resolver order may have changed; names are re-resolved on the next scan.
+    // Project config files reload often, so keep the names when only other settings moved.
+    if config.projects != state.config.projects {
+        state.project_cache.clear();
+    }
+    state.catalog.reconfigure(&config.catalog);
+    // Details the new rules need are requested with the next scan
+    state.ignore.reconfigure(&config.monitoring.ignore);
+    state.config = config;
+}
+
 fn sync_menu_with_context(tray_icon: &TrayIcon, state: &AppState) {
     match build_menu_with_context(state) {
         Ok(menu) => tray_icon.set_menu(Some(Box::new(menu))),
@@ -790,7 +1276,8 @@ fn sync_menu_with_context(tray_icon: &TrayIcon, state: &AppState) {
 
 fn update_tray_display(tray_icon: &TrayIcon, state: &AppState) {
     // Swap icon based on whether ports are active
-    let variant = if state.processes.is_empty() {
+    let visible = state.visible_processes();
+    let variant = if visible.is_empty() {
         IconVariant::Inactive
     } else {
         IconVariant::Active
@@ -801,166 +1288,13 @@ fn update_tray_display(tray_icon: &TrayIcon, state: &AppState) {
         tray_icon.set_icon_as_template(true);
     }
 
-    let tooltip = build_tooltip(&state.processes, state.last_feedback.as_ref());
+    let tooltip = build_tooltip(&visible, state.last_feedback.as_ref());
     if let Err(err) = tray_icon.set_tooltip(Some(tooltip.as_str())) {
         error!("Failed to update tooltip: {}", err);
     }
 }
 
-fn refresh_projects_for(state: &mut AppState) {
-    let mut missing: HashSet<i32> = HashSet::new();
-    for p in &state.processes {
-        if !state.project_cache.contains_key(&p.pid) {
-            missing.insert(p.pid);
-        }
-    }
-    for pid in missing {
-        if let Some(info) = resolve_project_info(pid) {
-            state.project_cache.insert(pid, info);
-        }
-    }
-}
-
-fn resolve_project_info(pid: i32) -> Option<ProjectInfo> {
-    let path = get_process_cwd(pid)?;
-    // Validate path is in safe location (home dir or /tmp)
-    if !is_safe_path(&path) {
-        log::debug!("Skipping project resolution for unsafe path: {:?}", path);
-        return None;
-    }
-    let name = get_git_repo_name(&path)
-        .or_else(|| dir_name(&path))
-        .unwrap_or_else(|| "(unknown)".to_string());
-    Some(ProjectInfo { name, path })
-}
-
-#[cfg(target_os = "macos")]
-fn get_process_cwd(pid: i32) -> Option<std::path::PathBuf> {
-    let out = Command::new("lsof")
-        .args(["-a", "-p", &pid.to_string(), "-d", "cwd", "-Fn"])
-        .output()
-        .ok()?;
-    if !out.status.success() {
-        return None;
-    }
-    String::from_utf8_lossy(&out.stdout)
-        .lines()
-        .find_map(|line| line.strip_prefix('n'))
-        .map(std::path::PathBuf::from)
-}
-
-#[cfg(target_os = "windows")]
-fn get_process_cwd(pid: i32) -> Option<std::path::PathBuf> {
-    // On Windows, getting a process's CWD is more complex
-    // We use wmic which is available on most Windows versions
-    let out = hidden_command("wmic")
-        .args([
-            "process",
-            "where",
-            &format!("ProcessId={}", pid),
-            "get",
-            "ExecutablePath",
-            "/value",
-        ])
-        .output()
-        .ok()?;
-
-    if !out.status.success() {
-        return None;
-    }
-
-    // Parse output like "ExecutablePath=C:\path\to\app.exe"
-    let output = String::from_utf8_lossy(&out.stdout);
-    for line in output.lines() {
-        if let Some(path_str) = line.strip_prefix("ExecutablePath=") {
-            let path = std::path::Path::new(path_str.trim());
-            // Return parent directory of the executable as approximate CWD
-            return path.parent().map(|p| p.to_path_buf());
-        }
-    }
-    None
-}
-
-fn is_safe_path(path: &std::path::Path) -> bool {
-    // Resolve to canonical path to prevent traversal attacks
-    let canonical = match path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
-
-    #[cfg(target_os = "macos")]
-    {
-        // Allow paths under home directory
-        if let Ok(home) = std::env::var("HOME")
-            && canonical.starts_with(&home)
-        {
-            return true;
-        }
-        // Allow /tmp and /var/folders (macOS temp)
-        // Note: On macOS, /tmp -> /private/tmp and /var -> /private/var after canonicalization
-        if canonical.starts_with("/tmp")
-            || canonical.starts_with("/private/tmp")
-            || canonical.starts_with("/var/folders")
-            || canonical.starts_with("/private/var/folders")
-        {
-            return true;
-        }
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        // Allow paths under user profile
-        if let Ok(userprofile) = std::env::var("USERPROFILE")
-            && canonical.starts_with(&userprofile)
-        {
-            return true;
-        }
-        // Allow common dev locations
-        if let Some(path_str) = canonical.to_str() {
-            let path_lower = path_str.to_lowercase();
-            // Common development directories
-            if path_lower.contains("\\documents\\")
-                || path_lower.contains("\\projects\\")
-                || path_lower.contains("\\source\\repos\\")
-                || path_lower.contains("\\dev\\")
-                || path_lower.contains("\\code\\")
-            {
-                return true;
-            }
-        }
-        // Allow temp directories
-        if let Ok(temp) = std::env::var("TEMP")
-            && canonical.starts_with(&temp)
-        {
-            return true;
-        }
-    }
-
-    false
-}
-
-fn get_git_repo_name(path: &std::path::Path) -> Option<String> {
-    let out = hidden_command("git")
-        .args([
-            "-C",
-            &path.to_string_lossy(),
-            "rev-parse",
-            "--show-toplevel",
-        ])
-        .output()
-        .ok()?;
-    if !out.status.success() {
-        return None;
-    }
-    let root = String::from_utf8_lossy(&out.stdout);
-    std::path::Path::new(root.trim())
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-}
-
-fn dir_name(path: &std::path::Path) -> Option<String> {
-    path.file_name().map(|s| s.to_string_lossy().to_string())
-}
+// resolve_project_info and is_safe_path moved to crate::project
 
 // docker/brew integrations moved to crate::integrations::{docker,brew}
 
@@ -0,0 +1,218 @@
+//! Well-known ports and frameworks, used to describe listeners
+//!
+//! An entry matches a listener by port, by a keyword in its command line
+//! (`next` in `node …/.bin/next dev`), or by both when it lists both. Entries
+//! from the config's `catalog` are tried before the built-in ones, and within
+//! each list an entry that needs both a port and a keyword wins over one that
+//! needs only a keyword, which wins over a bare port.
+
+use std::collections::HashMap;
+
+use crate::config::CatalogEntry;
+use crate::model::{ProcessIdentity, ProcessInfo};
+
+/// `(name, ports, keywords)`
+const BUILT_IN: &[(&str, &[u16], &[&str])] = &[
+    // Frameworks and dev servers, recognised by their command line
+    ("Vite", &[5173, 4173, 24678], &["vite"]),
+    ("Next.js", &[], &["next"]),
+    ("Nuxt", &[], &["nuxt", "nuxi"]),
+    ("Astro", &[], &["astro"]),
+    ("Remix", &[], &["remix"]),
+    ("SvelteKit", &[], &["svelte-kit"]),
+    ("Gatsby", &[], &["gatsby"]),
+    ("Create React App", &[], &["react-scripts"]),
+    (
+        "webpack dev server",
+        &[],
+        &["webpack-dev-server", "webpack"],
+    ),
+    ("Angular CLI", &[4200], &["ng"]),
+    ("Storybook", &[6006], &["storybook", "start-storybook"]),
+    ("Metro bundler", &[8081], &["metro", "expo"]),
+    ("Django", &[], &["manage.py", "django-admin"]),
+    ("Flask", &[], &["flask"]),
+    ("Uvicorn", &[], &["uvicorn"]),
+    ("Gunicorn", &[], &["gunicorn"]),
+    ("Python HTTP server", &[], &["http.server"]),
+    (
+        "Jupyter",
+        &[8888],
+        &["jupyter", "jupyter-lab", "jupyter-notebook"],
+    ),
+    ("Rails", &[], &["rails", "puma"]),
+    ("Laravel", &[], &["artisan"]),
+    ("Phoenix", &[], &["phx.server"]),
+    ("Hugo", &[1313], &["hugo"]),
+    ("Jekyll", &[], &["jekyll"]),
+    ("Node inspector", &[9229], &[]),
+    // Databases and infrastructure; the keywords also match container names
+    ("PostgreSQL", &[5432], &["postgres", "postgresql"]),
+    ("MySQL", &[3306], &["mysqld", "mysql", "mariadb"]),
+    ("MongoDB", &[27017], &["mongod", "mongo", "mongodb"]),
+    ("Redis", &[6379], &["redis-server", "redis"]),
+    ("Memcached", &[11211], &["memcached"]),
+    ("RabbitMQ", &[5672, 15672], &["rabbitmq", "rabbitmq-server"]),
+    ("Elasticsearch", &[9200], &["elasticsearch"]),
+    ("Nginx", &[], &["nginx"]),
+    ("Caddy", &[2019], &["caddy"]),
+];
+
+/// Extensions dropped from command-line words so `next.js` or `vite.cmd` still match.
+const SCRIPT_EXTENSIONS: &[&str] = &[".js", ".cjs", ".mjs", ".exe", ".cmd", ".bat"];
+
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    entries: Vec<CatalogEntry>,
+    /// Command-line words per process, read once per process lifetime
+    words: HashMap<ProcessIdentity, Vec<String>>,
+}
+
+impl Catalog {
+    pub fn new(user_entries: &[CatalogEntry]) -> Self {
+        let mut catalog = Self::default();
+        catalog.reconfigure(user_entries);
+        catalog
+    }
+
+    /// Swap in new user entries, keeping the command lines already read.
+    pub fn reconfigure(&mut self, user_entries: &[CatalogEntry]) {
+        let built_in = BUILT_IN.iter().map(|(name, ports, keywords)| CatalogEntry {
+            name: name.to_string(),
+            ports: ports.to_vec(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        });
+        let mut user: Vec<CatalogEntry> = user_entries.to_vec();
+        let mut built_in: Vec<CatalogEntry> = built_in.collect();
+        // Stable sorts keep the listed order among equally specific entries
+        user.sort_by_key(specificity);
+        built_in.sort_by_key(specificity);
+        self.entries = user.into_iter().chain(built_in).collect();
+    }
+
+    /// Read the command lines of processes not seen before.
+    pub fn read_missing(&mut self, processes: &[ProcessInfo]) {
+        for process in processes {
+            let identity = process.identity();
+            if self.words.contains_key(&identity) {
+                continue;
+            }
+            let argv = crate::process::ports::process_argv(process.pid)
+                .unwrap_or_else(|| vec![process.command.clone()]);
+            let mut words = command_words(&argv);
+            words.extend(command_words(std::slice::from_ref(&process.command)));
+            self.words.insert(identity, words);
+        }
+    }
+
+    /// Drop command lines of processes that are gone.
+    pub fn retain_active(&mut self, processes: &[ProcessInfo]) {
+        self.words
+            .retain(|identity, _| processes.iter().any(|p| p.identity() == *identity));
+    }
+
+    /// Friendly name for a process listening on `ports`.
+    pub fn describe_process(&self, process: &ProcessIdentity, ports: &[u16]) -> Option<&str> {
+        let words = self.words.get(process).map(Vec::as_slice).unwrap_or(&[]);
+        self.entries
+            .iter()
+            .find(|entry| {
+                let port_ok = entry.ports.iter().any(|port| ports.contains(port));
+                let keyword_ok = entry
+                    .keywords
+                    .iter()
+                    .any(|keyword| words.iter().any(|word| word.eq_ignore_ascii_case(keyword)));
+                match (entry.ports.is_empty(), entry.keywords.is_empty()) {
+                    (false, false) => port_ok && keyword_ok,
+                    (true, false) => keyword_ok,
+                    (false, true) => port_ok,
+                    (true, true) => false,
+                }
+            })
+            .map(|entry| entry.name.as_str())
+    }
+
+    /// Friendly name for a container, e.g. `postgres` or `redis_1` → `Redis`.
+    pub fn describe_container(&self, name: &str) -> Option<&str> {
+        let base = name.split(['-', '_']).next().unwrap_or(name);
+        self.entries
+            .iter()
+            .find(|entry| {
+                entry.keywords.iter().any(|keyword| {
+                    keyword.eq_ignore_ascii_case(name) || keyword.eq_ignore_ascii_case(base)
+                })
+            })
+            .map(|entry| entry.name.as_str())
+    }
+}
+
+/// Port-and-keyword entries first, then keyword-only, then port-only.
+fn specificity(entry: &CatalogEntry) -> u8 {
+    match (entry.ports.is_empty(), entry.keywords.is_empty()) {
+        (false, false) => 0,
+        (true, false) => 1,
+        _ => 2,
+    }
+}
+
+/// File names of each argument (`/x/node_modules/.bin/next` → `next`), with
+/// and without a script extension.
+fn command_words(argv: &[String]) -> Vec<String> {
+    let mut words = Vec::new();
+    for arg in argv {
+        let name = arg.rsplit(['/', '\\']).next().unwrap_or(arg);
+        if name.is_empty() || name.starts_with('-') {
+            continue;
+        }
+        words.push(name.to_string());
+        if let Some(stem) = SCRIPT_EXTENSIONS
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext))
+        {
+            words.push(stem.to_string());
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog_with(argv: &[&str]) -> (Catalog, ProcessIdentity) {
+        let mut catalog = Catalog::new(&[CatalogEntry {
+            name: "Shop API".to_string(),
+            ports: vec![4000],
+            keywords: vec!["server.js".to_string()],
+        }]);
+        let identity = ProcessIdentity {
+            pid: 42,
+            start_time: None,
+        };
+        let argv: Vec<String> = argv.iter().map(|a| a.to_string()).collect();
+        catalog.words.insert(identity, command_words(&argv));
+        (catalog, identity)
+    }
+
+    #[test]
+    fn describes_by_keyword_port_and_both() {
+        let (catalog, id) = catalog_with(&["node", "/app/node_modules/.bin/next", "dev"]);
+        assert_eq!(catalog.describe_process(&id, &[3001]), Some("Next.js"));
+
+        let (catalog, id) = catalog_with(&["node", "--inspect", "server.js"]);
+        assert_eq!(catalog.describe_process(&id, &[4000]), Some("Shop API"));
+        assert_eq!(
+            catalog.describe_process(&id, &[9229]),
+            Some("Node inspector")
+        );
+        assert_eq!(catalog.describe_process(&id, &[4001]), None);
+    }
+
+    #[test]
+    fn describes_containers() {
+        let catalog = Catalog::new(&[]);
+        assert_eq!(catalog.describe_container("postgres"), Some("PostgreSQL"));
+        assert_eq!(catalog.describe_container("redis_1"), Some("Redis"));
+        assert_eq!(catalog.describe_container("billing"), None);
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -7,6 +8,9 @@ use std::os::unix::fs::PermissionsExt;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::config_format::find_config_file;
+use crate::port_set::PortSet;
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Config {
     #[serde(default)]
@@ -14,6 +18,16 @@ pub struct Config {
     #[serde(default)]
     pub integrations: IntegrationsConfig,
     #[serde(default)]
+    pub projects: ProjectsConfig,
+    /// Extra well-known ports and frameworks, tried before the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub catalog: Vec<CatalogEntry>,
+    /// Dev servers PortKiller can start, stop and restart from the tray
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<ServiceConfig>,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
     pub notifications: NotificationsConfig,
     #[serde(default)]
     pub system: SystemConfig,
@@ -23,8 +37,166 @@ pub struct Config {
 #[serde(default)]
 pub struct MonitoringConfig {
     pub poll_interval_secs: u64,
-    pub port_ranges: Vec<(u16, u16)>,
+    pub port_ranges: Vec<PortRange>,
     pub show_project_names: bool,
+    /// Listeners hidden from the menu, tooltip and notifications and left alone by Kill All
+    pub ignore: Vec<IgnoreRule>,
+}
+
+impl MonitoringConfig {
+    /// Every monitored port, for the port scanner.
+    pub fn port_set(&self) -> PortSet {
+        self.port_ranges
+            .iter()
+            .fold(PortSet::default(), |set, range| set.union(&range.ports))
+    }
+
+    /// The first configured range containing `port`.
+    pub fn range_for(&self, port: u16) -> Option<&PortRange> {
+        self.port_ranges.iter().find(|range| range.contains(port))
+    }
+
+    /// Menu group for a listener: the group of the first range containing one of its ports.
+    pub fn group_for(&self, ports: &[u16]) -> Option<&str> {
+        ports
+            .iter()
+            .find_map(|&port| self.range_for(port))
+            .and_then(PortRange::group)
+    }
+
+    /// Position of a menu group, in the order its first range is listed.
+    pub fn group_position(&self, group: &str) -> usize {
+        self.port_ranges
+            .iter()
+            .position(|range| range.group() == Some(group))
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Whether `port` falls in a range excluded from Kill All.
+    pub fn is_protected(&self, port: u16) -> bool {
+        self.range_for(port).is_some_and(|range| range.protected)
+    }
+}
+
+/// A monitored set of ports. Accepts the legacy `[start, end]` pair, a port
+/// string such as `"3000-3010, 5173, !3001"` or `"all"`, or
+/// `{"name": "Frontend", "ports": "3000-3010,5173", "notify": false, "protected": false, "section": "Web"}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(try_from = "PortRangeRepr", into = "PortRangeRepr")]
+pub struct PortRange {
+    /// Shown in notifications, and groups the menu when there is no `section`
+    pub name: Option<String>,
+    pub ports: PortSet,
+    /// Notify when listeners on these ports start or stop
+    pub notify: bool,
+    /// Left alone by Kill All
+    pub protected: bool,
+    /// Menu group shared by several ranges
+    pub section: Option<String>,
+}
+
+impl PortRange {
+    fn named(name: &str, section: &str, ports: &[(u16, u16)]) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            section: Some(section.to_string()),
+            ..Self::from(PortSet::from_ranges(ports))
+        }
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.ports.contains(port)
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.section.as_deref().or(self.name.as_deref())
+    }
+
+    /// Name for notifications, falling back to the section.
+    pub fn label(&self) -> Option<&str> {
+        self.name.as_deref().or(self.section.as_deref())
+    }
+
+    /// No name, section or options: written back as a bare pair or string.
+    fn is_plain(&self) -> bool {
+        self.name.is_none() && self.section.is_none() && self.notify && !self.protected
+    }
+}
+
+impl From<PortSet> for PortRange {
+    fn from(ports: PortSet) -> Self {
+        Self {
+            name: None,
+            ports,
+            notify: true,
+            protected: false,
+            section: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PortRangeRepr {
+    Pair(u16, u16),
+    // Port strings are parsed in `try_from` so their errors aren't lost to `untagged`
+    Spec(String),
+    Named {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        ports: String,
+        #[serde(default = "default_true")]
+        notify: bool,
+        #[serde(default)]
+        protected: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        section: Option<String>,
+    },
+}
+
+impl TryFrom<PortRangeRepr> for PortRange {
+    type Error = String;
+
+    fn try_from(repr: PortRangeRepr) -> std::result::Result<Self, String> {
+        match repr {
+            PortRangeRepr::Pair(start, end) if start > end => Err(format!(
+                "invalid port range: start ({}) > end ({})",
+                start, end
+            )),
+            PortRangeRepr::Pair(start, end) => Ok(PortSet::from_ranges(&[(start, end)]).into()),
+            PortRangeRepr::Spec(ports) => Ok(ports.parse::<PortSet>()?.into()),
+            PortRangeRepr::Named {
+                name,
+                ports,
+                notify,
+                protected,
+                section,
+            } => Ok(Self {
+                name,
+                ports: ports.parse()?,
+                notify,
+                protected,
+                section,
+            }),
+        }
+    }
+}
+
+impl From<PortRange> for PortRangeRepr {
+    fn from(range: PortRange) -> Self {
+        // Plain ranges keep their short form so existing files round-trip unchanged
+        match range.ports.as_range() {
+            Some((start, end)) if range.is_plain() => Self::Pair(start, end),
+            None if range.is_plain() => Self::Spec(range.ports.to_string()),
+            _ => Self::Named {
+                name: range.name,
+                ports: range.ports.to_string(),
+                notify: range.notify,
+                protected: range.protected,
+                section: range.section,
+            },
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -37,15 +209,242 @@ pub struct IntegrationsConfig {
     #[serde(default = "default_true")]
     pub docker_enabled: bool,
 
+    #[serde(default = "default_true")]
+    pub pm2_enabled: bool,
+
+    #[serde(default = "default_true")]
+    pub kubectl_enabled: bool,
+
+    #[serde(default = "default_true")]
+    pub ssh_enabled: bool,
+
+    #[serde(default = "default_true")]
+    pub vm_enabled: bool,
+
+    /// Watch for ngrok, cloudflared, tailscale funnel and localtunnel exposing local ports
+    #[serde(default = "default_true")]
+    pub tunnel_detection_enabled: bool,
+
     #[cfg(target_os = "windows")]
     #[serde(default = "default_true")]
     pub windows_services_enabled: bool,
+
+    /// supervisord XML-RPC endpoint: `unix:///path/to/supervisor.sock` or
+    /// `http://[user:pass@]host:port`. Disabled when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supervisord_url: Option<String>,
+
+    /// Caddyfiles, nginx configs and Traefik dynamic configs (files or directories)
+    /// whose routes label dev servers with their proxied hostnames
+    #[serde(default = "default_proxy_configs")]
+    pub proxy_configs: Vec<String>,
+
+    /// User-defined integrations backed by external commands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<ScriptIntegrationConfig>,
+}
+
+/// A custom integration: `list` prints a JSON array of `{name, ports, status}`,
+/// `stop` is run with the unit name appended (or substituted for `{name}`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScriptIntegrationConfig {
+    pub id: String,
+    pub name: String,
+    pub list: Vec<String>,
+    pub stop: Vec<String>,
+    #[serde(default = "default_script_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_script_timeout_secs() -> u64 {
+    10
+}
+
+/// Hides listeners that match every field given. Integration-managed
+/// listeners (containers, services, …) are never hidden.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IgnoreRule {
+    /// Glob on the command name, case-insensitive, e.g. `"Spotify*"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Glob on the full executable path, e.g. `"/System/Library/**"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable: Option<String>,
+    /// Owning account, case-insensitive, e.g. `"root"` or `"SYSTEM"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ports: Option<PortSet>,
+}
+
+impl IgnoreRule {
+    fn command(pattern: &str) -> Self {
+        Self {
+            command: Some(pattern.to_string()),
+            executable: None,
+            user: None,
+            ports: None,
+        }
+    }
+}
+
+/// Background listeners every machine has, which broad port ranges pick up.
+fn default_ignore_rules() -> Vec<IgnoreRule> {
+    #[cfg(target_os = "macos")]
+    let commands = ["ControlCenter", "rapportd", "Spotify", "com.docker.*"];
+    #[cfg(not(target_os = "macos"))]
+    let commands = ["svchost.exe", "Spotify.exe", "com.docker.*"];
+    commands.into_iter().map(IgnoreRule::command).collect()
+}
+
+/// A friendly name for listeners on one of `ports` and/or whose command line
+/// contains one of `keywords` (both must match when both are given).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatalogEntry {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+/// A dev server launched and supervised by PortKiller.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServiceConfig {
+    /// Menu name; letters, digits, `-`, `_` and `.`
+    pub name: String,
+    pub command: Vec<String>,
+    /// Working directory (`~` allowed); PortKiller's own when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Ports the service listens on; listeners on them are shown under the service
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<u16>,
+    /// When the service counts as started. Defaults to its first port accepting connections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready: Option<ReadinessProbe>,
+    #[serde(default = "default_ready_timeout_secs")]
+    pub ready_timeout_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReadinessProbe {
+    /// A TCP connection to `localhost:port` succeeds
+    Tcp { port: u16 },
+    /// An `http://` URL answers with a non-5xx status
+    Http { url: String },
+    /// The service's output contains `pattern`
+    Log { pattern: String },
+}
+
+fn default_ready_timeout_secs() -> u64 {
+    30
+}
+
+fn default_safe_roots() -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    let roots = vec!["~", "/tmp", "/var/folders"];
+    #[cfg(target_os = "windows")]
+    let roots = vec![
+        "~",
+        "%TEMP%",
+        "?:\\**\\Documents",
+        "?:\\**\\Projects",
+        "?:\\**\\source\\repos",
+        "?:\\**\\dev",
+        "?:\\**\\code",
+    ];
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let roots = vec!["~", "/tmp", "$XDG_RUNTIME_DIR"];
+    roots.into_iter().map(str::to_string).collect()
+}
+
+fn default_proxy_configs() -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    let paths = vec![
+        "/opt/homebrew/etc/Caddyfile",
+        "/usr/local/etc/Caddyfile",
+        "/opt/homebrew/etc/nginx/servers",
+        "/usr/local/etc/nginx/servers",
+        "~/Caddyfile",
+    ];
+    #[cfg(not(target_os = "macos"))]
+    let paths = vec![
+        "~/Caddyfile",
+        "C:\\caddy\\Caddyfile",
+        "C:\\nginx\\conf\\nginx.conf",
+        "C:\\nginx\\conf\\conf.d",
+        "~/scoop/persist/nginx/conf/nginx.conf",
+    ];
+    paths.into_iter().map(str::to_string).collect()
 }
 
 fn default_true() -> bool {
     true
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ProjectsConfig {
+    /// Tried in order in each directory from the process's cwd up to the repository root
+    pub resolvers: Vec<ProjectResolver>,
+    /// Directory (and everything below it) → project name, e.g. `"~/work/mono/apps/web": "storefront"`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+    /// Only processes running under these directories are inspected. Entries may use
+    /// `~`, `$VAR`/`%VAR%` and globs (`/srv/*/src`, `?:\**\repos`).
+    pub safe_roots: Vec<String>,
+    /// Directories skipped even when under a safe root, with the same syntax
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded_roots: Vec<String>,
+    /// Projects whose own `.portkiller.json` is loaded, with the same syntax.
+    /// Other project files are ignored since their services run commands.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_roots: Vec<String>,
+    /// "Open in Editor" command; the project folder replaces `{path}` or is appended
+    pub editor_command: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectResolver {
+    PackageJson,
+    Cargo,
+    Pyproject,
+    GoMod,
+    Composer,
+    Git,
+    Directory,
+}
+
+impl ProjectResolver {
+    /// Resolvers that read a manifest file in a single directory.
+    pub fn is_manifest(self) -> bool {
+        !matches!(self, Self::Git | Self::Directory)
+    }
+}
+
+/// Periodic probing of listeners (TCP connect, TLS detection, HTTP `GET`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HealthConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// Also check for TLS and send an HTTP `GET`, rather than only connecting
+    pub http: bool,
+    /// Path requested from HTTP listeners
+    pub http_path: String,
+    /// Per-port paths, e.g. `{"8080": "/healthz"}`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub paths: BTreeMap<u16, String>,
+    /// Replies slower than this are marked slow
+    pub slow_ms: u64,
+    pub timeout_ms: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct NotificationsConfig {
@@ -63,19 +462,42 @@ impl Default for MonitoringConfig {
         Self {
             poll_interval_secs: 2,
             port_ranges: vec![
-                (3000, 3010),   // Node.js, React, Next.js, Vite
-                (3306, 3306),   // MySQL
-                (4000, 4010),   // Alternative Node servers
-                (5001, 5010),   // Flask, general dev servers (excluding 5000)
-                (5173, 5173),   // Vite default
-                (5432, 5432),   // PostgreSQL
-                (6379, 6380),   // Redis (6379 default, 6380 for testing)
-                (8000, 8100),   // Django, Python HTTP servers
-                (8080, 8090),   // Tomcat, alternative HTTP
-                (9000, 9010),   // Various dev tools
-                (27017, 27017), // MongoDB
+                PortRange::named("Node.js", "Web", &[(3000, 3010), (4000, 4010)]),
+                // 5000 is AirPlay on macOS
+                PortRange::named("Flask", "Web", &[(5001, 5010)]),
+                PortRange::named("Vite", "Web", &[(5173, 5173)]),
+                PortRange::named("Tomcat", "Web", &[(8080, 8090)]),
+                PortRange::named("Django", "Web", &[(8000, 8100)]),
+                PortRange::named("MySQL", "Databases", &[(3306, 3306)]),
+                PortRange::named("PostgreSQL", "Databases", &[(5432, 5432)]),
+                // 6380 is commonly a second, test Redis
+                PortRange::named("Redis", "Databases", &[(6379, 6380)]),
+                PortRange::named("MongoDB", "Databases", &[(27017, 27017)]),
+                PortRange::named("Dev tools", "Tools", &[(9000, 9010)]),
             ],
             show_project_names: true,
+            ignore: default_ignore_rules(),
+        }
+    }
+}
+
+impl Default for ProjectsConfig {
+    fn default() -> Self {
+        Self {
+            resolvers: vec![
+                ProjectResolver::PackageJson,
+                ProjectResolver::Cargo,
+                ProjectResolver::Pyproject,
+                ProjectResolver::GoMod,
+                ProjectResolver::Composer,
+                ProjectResolver::Git,
+                ProjectResolver::Directory,
+            ],
+            overrides: BTreeMap::new(),
+            safe_roots: default_safe_roots(),
+            excluded_roots: Vec::new(),
+            trusted_roots: Vec::new(),
+            editor_command: vec!["code".to_string()],
         }
     }
 }
@@ -86,8 +508,30 @@ impl Default for IntegrationsConfig {
             #[cfg(target_os = "macos")]
             brew_enabled: true,
             docker_enabled: true,
+            pm2_enabled: true,
+            kubectl_enabled: true,
+            ssh_enabled: true,
+            vm_enabled: true,
+            tunnel_detection_enabled: true,
             #[cfg(target_os = "windows")]
             windows_services_enabled: true,
+            supervisord_url: None,
+            proxy_configs: default_proxy_configs(),
+            scripts: Vec::new(),
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 10,
+            http: false,
+            http_path: "/".to_string(),
+            paths: BTreeMap::new(),
+            slow_ms: 1000,
+            timeout_ms: 3000,
         }
     }
 }
@@ -98,7 +542,14 @@ impl Default for NotificationsConfig {
     }
 }
 
+/// The user's config file: `portkiller.toml`, `.yaml`/`.yml` or `.json`, whichever
+/// exists, and the JSON path when there is none yet.
 pub fn get_config_path() -> PathBuf {
+    let path = default_config_path();
+    find_config_file(&path).unwrap_or(path)
+}
+
+fn default_config_path() -> PathBuf {
     #[cfg(target_os = "macos")]
     {
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
@@ -120,42 +571,45 @@ pub fn get_config_path() -> PathBuf {
     }
 }
 
-pub fn load_or_create_config() -> Result<Config> {
-    let path = get_config_path();
-
-    if path.exists() {
-        // Validate file permissions (should be 0600 for security on Unix)
-        #[cfg(unix)]
-        ensure_secure_permissions(&path)?;
+/// Where service and restart logs go: `PortKiller/logs` in the local data
+/// directory (`~/Library/Application Support` on macOS, `%LOCALAPPDATA%` on Windows).
+pub fn log_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("PortKiller")
+        .join("logs")
+}
 
-        let content = fs::read_to_string(&path).context("failed to read config file")?;
-        let config: Config =
-            serde_json::from_str(&content).context("failed to parse config file")?;
-        validate_config(&config)?;
-        Ok(config)
-    } else {
-        let config = Config::default();
-        save_config(&config)?;
-        Ok(config)
+/// Create the log directory, accessible only by its owner, and return it.
+pub fn ensure_log_dir() -> Result<PathBuf> {
+    let dir = log_dir();
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
     }
+    builder
+        .create(&dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    Ok(dir)
 }
 
-pub fn save_config(config: &Config) -> Result<()> {
-    let path = get_config_path();
-
+/// Write a config file readable only by its owner.
+pub(crate) fn write_config_file(path: &Path, content: &str) -> Result<()> {
     // Ensure parent directory exists (important for Windows)
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).context("failed to create config directory")?;
     }
 
-    let content = serde_json::to_string_pretty(config).context("failed to serialize config")?;
-    fs::write(&path, &content).context("failed to write config file")?;
+    fs::write(path, content).context("failed to write config file")?;
 
     // Set secure permissions (owner read/write only) on Unix
     #[cfg(unix)]
     {
         use std::fs::Permissions;
-        fs::set_permissions(&path, Permissions::from_mode(0o600))
+        fs::set_permissions(path, Permissions::from_mode(0o600))
             .context("failed to set config file permissions")?;
     }
 
@@ -163,7 +617,7 @@ pub fn save_config(config: &Config) -> Result<()> {
 }
 
 #[cfg(unix)]
-fn ensure_secure_permissions(path: &PathBuf) -> Result<()> {
+pub(crate) fn ensure_secure_permissions(path: &Path) -> Result<()> {
     use std::fs::Permissions;
 
     let metadata = fs::metadata(path).context("failed to read config file metadata")?;
@@ -180,24 +634,7 @@ fn ensure_secure_permissions(path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-/// Load and validate config from disk. Returns error if file doesn't exist or is invalid.
-/// Used by hot-reload (file watcher and manual reload).
-pub fn load_and_validate_config() -> Result<Config> {
-    let path = get_config_path();
-    if !path.exists() {
-        anyhow::bail!("config file does not exist");
-    }
-
-    #[cfg(unix)]
-    ensure_secure_permissions(&path)?;
-
-    let content = fs::read_to_string(&path).context("failed to read config file")?;
-    let config: Config = serde_json::from_str(&content).context("failed to parse config file")?;
-    validate_config(&config)?;
-    Ok(config)
-}
-
-fn validate_config(config: &Config) -> Result<()> {
+pub(crate) fn validate_config(config: &Config) -> Result<()> {
     // Validate poll interval (1-300 seconds)
     if config.monitoring.poll_interval_secs == 0 || config.monitoring.poll_interval_secs > 300 {
         anyhow::bail!(
@@ -205,11 +642,192 @@ fn validate_config(config: &Config) -> Result<()> {
             config.monitoring.poll_interval_secs
         );
     }
-    // Validate port ranges (u16 already enforces 0-65535)
-    for (start, end) in &config.monitoring.port_ranges {
-        if start > end {
-            anyhow::bail!("invalid port range: start ({}) > end ({})", start, end);
+    // Port ranges are checked as they're parsed
+    for range in &config.monitoring.port_ranges {
+        if [&range.name, &range.section]
+            .into_iter()
+            .any(|label| label.as_deref().is_some_and(|l| l.trim().is_empty()))
+        {
+            anyhow::bail!("port range names and sections can't be empty");
+        }
+    }
+    for rule in &config.monitoring.ignore {
+        if rule.command.is_none()
+            && rule.executable.is_none()
+            && rule.user.is_none()
+            && rule.ports.is_none()
+        {
+            anyhow::bail!("ignore rules need a command, executable, user or ports");
+        }
+        for pattern in rule.command.iter().chain(&rule.executable) {
+            if let Err(err) = glob::Pattern::new(pattern) {
+                anyhow::bail!("invalid ignore pattern {:?}: {}", pattern, err);
+            }
+        }
+    }
+    for entry in &config.catalog {
+        if entry.name.trim().is_empty() {
+            anyhow::bail!("catalog entries need a name");
+        }
+        if entry.ports.is_empty() && entry.keywords.is_empty() {
+            anyhow::bail!(
+                "catalog entry {:?} needs ports, keywords or both",
+                entry.name
+            );
+        }
+    }
+    let health = &config.health;
+    if health.interval_secs == 0 || health.interval_secs > 3600 {
+        anyhow::bail!(
+            "health.interval_secs must be between 1 and 3600, got {}",
+            health.interval_secs
+        );
+    }
+    if health.timeout_ms < 100 || health.timeout_ms > 30_000 {
+        anyhow::bail!(
+            "health.timeout_ms must be between 100 and 30000, got {}",
+            health.timeout_ms
+        );
+    }
+    if let Some(path) = std::iter::once(&health.http_path)
+        .chain(health.paths.values())
+        .find(|path| !path.starts_with('/'))
+    {
+        anyhow::bail!("health check paths must start with '/', got {:?}", path);
+    }
+    if let Some(url) = &config.integrations.supervisord_url
+        && !url.starts_with("unix://")
+        && !url.starts_with("http://")
+    {
+        anyhow::bail!(
+            "supervisord_url must start with unix:// or http://, got {:?}",
+            url
+        );
+    }
+    for root in config
+        .projects
+        .safe_roots
+        .iter()
+        .chain(&config.projects.excluded_roots)
+        .chain(&config.projects.trusted_roots)
+    {
+        if let Err(err) = glob::Pattern::new(root) {
+            anyhow::bail!("invalid project root pattern {:?}: {}", root, err);
+        }
+    }
+    // Validate script integrations (ids become part of menu ids)
+    let mut seen_ids: Vec<&str> = vec![
+        "docker",
+        "pm2",
+        "brew",
+        "service",
+        "supervisor",
+        "kubectl",
+        "ssh",
+        "vm",
+        "tunnel",
+        "route",
+        "restart",
+        "hidden",
+    ];
+    seen_ids.push(crate::integrations::services::SERVICES_ID);
+    let mut service_names: Vec<&str> = Vec::new();
+    for service in &config.services {
+        if service.name.is_empty()
+            || !service
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            anyhow::bail!(
+                "service name {:?} must be non-empty and contain only letters, digits, '-', '_' and '.'",
+                service.name
+            );
+        }
+        if service_names.contains(&service.name.as_str()) {
+            anyhow::bail!("duplicate service name {:?}", service.name);
+        }
+        service_names.push(&service.name);
+        if service.command.is_empty() {
+            anyhow::bail!("service {:?} needs a non-empty command", service.name);
+        }
+        if service.ready_timeout_secs == 0 || service.ready_timeout_secs > 600 {
+            anyhow::bail!(
+                "service {:?}: ready_timeout_secs must be between 1 and 600, got {}",
+                service.name,
+                service.ready_timeout_secs
+            );
+        }
+        if let Some(ReadinessProbe::Http { url }) = &service.ready
+            && !url.starts_with("http://")
+        {
+            anyhow::bail!(
+                "service {:?}: readiness url must start with http://, got {:?}",
+                service.name,
+                url
+            );
+        }
+    }
+    for script in &config.integrations.scripts {
+        if script.id.is_empty()
+            || !script
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            anyhow::bail!(
+                "script integration id {:?} must be non-empty and contain only letters, digits and '-'",
+                script.id
+            );
+        }
+        if seen_ids.contains(&script.id.as_str()) {
+            anyhow::bail!("duplicate integration id {:?}", script.id);
+        }
+        seen_ids.push(&script.id);
+        if script.list.is_empty() || script.stop.is_empty() {
+            anyhow::bail!(
+                "script integration {:?} needs non-empty list and stop commands",
+                script.id
+            );
+        }
+        if script.timeout_secs == 0 || script.timeout_secs > 300 {
+            anyhow::bail!(
+                "script integration {:?}: timeout_secs must be between 1 and 300, got {}",
+                script.id,
+                script.timeout_secs
+            );
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_legacy_and_named_port_ranges() {
+        let monitoring: MonitoringConfig = serde_json::from_str(
+            r#"{"port_ranges": [[3000, 3010], "8000-8100, !8081", {"name": "Frontend", "ports": "5173, 4200-4201", "notify": false}]}"#,
+        )
+        .unwrap();
+        let ports = monitoring.port_set();
+        assert!(ports.contains(3010) && ports.contains(5173) && ports.contains(8080));
+        assert!(!ports.contains(8081) && !ports.contains(4202));
+        assert_eq!(monitoring.range_for(3005).unwrap().name, None);
+        let frontend = monitoring.range_for(4201).unwrap();
+        assert_eq!(frontend.label(), Some("Frontend"));
+        assert!(!frontend.notify && !frontend.protected);
+
+        // Plain ranges are written back in their short form
+        let json = serde_json::to_string(&monitoring.port_ranges).unwrap();
+        assert_eq!(
+            json,
+            r#"[[3000,3010],"8000-8100, !8081",{"name":"Frontend","ports":"5173, 4200-4201","notify":false,"protected":false}]"#
+        );
+
+        assert!(serde_json::from_str::<PortRange>("[3010, 3000]").is_err());
+        let err = serde_json::from_str::<PortRange>(r#"{"ports": "3000, http"}"#).unwrap_err();
+        assert!(err.to_string().contains("invalid port \"http\""));
+    }
+}
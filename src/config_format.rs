@@ -0,0 +1,329 @@
+//! JSON, TOML and YAML config files, told apart by extension
+//!
+//! Files are read into a `serde_json::Value` so the layers merge the same way
+//! whatever they were written in. Single settings are changed in place:
+//! TOML through `toml_edit` and YAML line by line, so comments, key order and
+//! formatting written by hand survive.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Extensions in the order they're preferred when several files exist.
+/// JSON comes last since it's the one created on first run.
+const EXTENSIONS: &[(&str, ConfigFormat)] = &[
+    ("toml", ConfigFormat::Toml),
+    ("yaml", ConfigFormat::Yaml),
+    ("yml", ConfigFormat::Yaml),
+    ("json", ConfigFormat::Json),
+];
+
+impl ConfigFormat {
+    /// The format of `path`; JSON for unknown extensions.
+    pub fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| extension.as_deref() == Some(*ext))
+            .map_or(Self::Json, |(_, format)| *format)
+    }
+
+    pub fn parse(self, content: &str) -> Result<Value> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(content)?,
+            Self::Toml => toml::from_str(content)?,
+            // An empty YAML document is null rather than an empty mapping
+            Self::Yaml if content.trim().is_empty() => Value::Object(Default::default()),
+            Self::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+
+    /// A whole file, for configs written from scratch.
+    pub fn render(self, value: &impl Serialize) -> Result<String> {
+        let value = serde_json::to_value(value)?;
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(&value)? + "\n",
+            // TOML has no null; unset options are simply left out
+            Self::Toml => toml::to_string_pretty(&without_nulls(value))?,
+            Self::Yaml => serde_yaml::to_string(&value)?,
+        })
+    }
+
+    /// `content` with the setting at `path` replaced or added, leaving the rest as written.
+    pub fn set(self, content: &str, path: &[&str], value: &Value) -> Result<String> {
+        let updated = match self {
+            Self::Json => {
+                let mut root = self.parse(content)?;
+                let mut target = &mut root;
+                for key in path {
+                    target = target
+                        .as_object_mut()
+                        .context("not an object")?
+                        .entry(*key)
+                        .or_insert_with(|| Value::Object(Default::default()));
+                }
+                *target = value.clone();
+                serde_json::to_string_pretty(&root)? + "\n"
+            }
+            Self::Toml => set_toml(content, path, value)?,
+            Self::Yaml => set_yaml(content, path, value)?,
+        };
+        // The line-based YAML edit can't handle every layout; never write a file that lost the change
+        let written = self.parse(&updated)?;
+        let key_path: String = path.iter().map(|key| format!("/{}", key)).collect();
+        if written.pointer(&key_path) != Some(value) {
+            anyhow::bail!("couldn't update {} in place", path.join("."));
+        }
+        Ok(updated)
+    }
+}
+
+/// The existing config file among `path` and its siblings with another
+/// config extension (`portkiller.toml` next to `portkiller.json`).
+pub fn find_config_file(path: &Path) -> Option<PathBuf> {
+    let mut existing = EXTENSIONS
+        .iter()
+        .map(|(ext, _)| path.with_extension(ext))
+        .filter(|candidate| candidate.is_file());
+    let found = existing.next()?;
+    if let Some(ignored) = existing.next() {
+        log::warn!(
+            "Using {} and ignoring {}",
+            found.display(),
+            ignored.display()
+        );
+    }
+    Some(found)
+}
+
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        value => value,
+    }
+}
+
+fn set_toml(content: &str, path: &[&str], value: &Value) -> Result<String> {
+    let (last, parents) = path.split_last().context("empty setting path")?;
+    let mut document: toml_edit::DocumentMut = content.parse()?;
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for key in parents {
+        table = table
+            .entry(key)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .with_context(|| format!("{} is not a table", key))?;
+    }
+    let mut new = without_nulls(value.clone()).serialize(toml_edit::ser::ValueSerializer::new())?;
+    match table.get_mut(last) {
+        Some(toml_edit::Item::Value(old)) => {
+            // Keep the spacing and any trailing comment of the old value
+            *new.decor_mut() = old.decor().clone();
+            *old = new;
+        }
+        _ => {
+            table.insert(last, toml_edit::Item::Value(new));
+        }
+    }
+    Ok(document.to_string())
+}
+
+/// Block-style YAML, edited by indentation. Values are written as JSON,
+/// which YAML reads as flow style.
+fn set_yaml(content: &str, path: &[&str], value: &Value) -> Result<String> {
+    let rendered = serde_json::to_string(value)?;
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let (mut start, mut end) = (0, lines.len());
+    let mut parent_indent: Option<usize> = None;
+
+    for (depth, key) in path.iter().enumerate() {
+        let last = depth + 1 == path.len();
+        let child_indent = (start..end)
+            .find(|&i| is_content(&lines[i]))
+            .map_or(parent_indent.map_or(0, |indent| indent + 2), |i| {
+                indent(&lines[i])
+            });
+        let found = (start..end).find(|&i| {
+            is_content(&lines[i])
+                && indent(&lines[i]) == child_indent
+                && key_value(&lines[i], key).is_some()
+        });
+        match found {
+            Some(i) if last => {
+                let block_end = block_end(&lines, i, end);
+                let line = &lines[i];
+                let rest = key_value(line, key).expect("matched above");
+                let prefix = &line[..line.len() - rest.len()];
+                let comment = comment_start(rest).map_or("", |at| {
+                    let spaces = rest[..at].len() - rest[..at].trim_end().len();
+                    &rest[at - spaces..]
+                });
+                lines[i] = format!("{} {}{}", prefix, rendered, comment);
+                // A nested block under the key is replaced too
+                lines.drain(i + 1..block_end);
+                break;
+            }
+            Some(i) => {
+                parent_indent = Some(indent(&lines[i]));
+                end = block_end(&lines, i, end);
+                start = i + 1;
+            }
+            None => {
+                let at = (start..end)
+                    .rev()
+                    .find(|&i| is_content(&lines[i]))
+                    .map_or(start, |i| i + 1);
+                let new = path[depth..].iter().enumerate().map(|(level, key)| {
+                    let indent = " ".repeat(child_indent + 2 * level);
+                    if depth + level + 1 == path.len() {
+                        format!("{}{}: {}", indent, key, rendered)
+                    } else {
+                        format!("{}{}:", indent, key)
+                    }
+                });
+                lines.splice(at..at, new.collect::<Vec<_>>());
+                break;
+            }
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---"
+}
+
+/// Lines after `line` that are indented deeper, up to `limit`.
+fn block_end(lines: &[String], line: usize, limit: usize) -> usize {
+    let own = indent(&lines[line]);
+    (line + 1..limit)
+        .find(|&i| is_content(&lines[i]) && indent(&lines[i]) <= own)
+        .unwrap_or(limit)
+}
+
+/// What follows `key:` on a mapping line, if the line holds that key.
+fn key_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let trimmed = line.trim_start();
+    [
+        key.to_string(),
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+    ]
+    .iter()
+    .find_map(|written| trimmed.strip_prefix(written.as_str())?.strip_prefix(':'))
+    .filter(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+/// Where a `# comment` starts in a value, skipping `#` inside quotes.
+fn comment_start(value: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (at, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '#') if previous.is_whitespace() => return Some(at),
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_defaults_in_every_format() {
+        let defaults = serde_json::to_value(crate::config::Config::default()).unwrap();
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let rendered = format.render(&defaults).unwrap();
+            let parsed = format.parse(&rendered).unwrap();
+            assert_eq!(
+                serde_json::from_value::<crate::config::Config>(parsed)
+                    .map(|config| serde_json::to_value(config).unwrap())
+                    .unwrap(),
+                defaults,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn edits_keep_comments_and_order() {
+        let toml = "# mine\n[system]\nlaunch_at_login = false # toggled from the tray\n\n[monitoring]\npoll_interval_secs = 2\n";
+        let updated = ConfigFormat::Toml
+            .set(toml, &["system", "launch_at_login"], &Value::Bool(true))
+            .unwrap();
+        assert_eq!(
+            updated,
+            "# mine\n[system]\nlaunch_at_login = true # toggled from the tray\n\n[monitoring]\npoll_interval_secs = 2\n"
+        );
+
+        let yaml = "# mine\nmonitoring:\n  poll_interval_secs: 2  # seconds\nsystem:\n  launch_at_login: false  # tray\nnotifications:\n  enabled: true\n";
+        let updated = ConfigFormat::Yaml
+            .set(yaml, &["system", "launch_at_login"], &Value::Bool(true))
+            .unwrap();
+        assert_eq!(updated, yaml.replace("login: false", "login: true"));
+
+        // Missing keys are added at the end of their block
+        let updated = ConfigFormat::Yaml
+            .set(
+                "monitoring:\n  poll_interval_secs: 2\n",
+                &["system", "launch_at_login"],
+                &Value::Bool(true),
+            )
+            .unwrap();
+        assert_eq!(
+            updated,
+            "monitoring:\n  poll_interval_secs: 2\nsystem:\n  launch_at_login: true\n"
+        );
+        let updated = ConfigFormat::Toml
+            .set("", &["system", "launch_at_login"], &Value::Bool(true))
+            .unwrap();
+        assert_eq!(
+            ConfigFormat::Toml.parse(&updated).unwrap()["system"]["launch_at_login"],
+            Value::Bool(true)
+        );
+
+        // Flow-style YAML is refused rather than mangled
+        assert!(
+            ConfigFormat::Yaml
+                .set(
+                    "{system: {launch_at_login: false}}\n",
+                    &["system", "launch_at_login"],
+                    &Value::Bool(true)
+                )
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Layered configuration
+//!
+//! The effective config is built from, in order: the built-in defaults, a
+//! system-wide file, the user's file, and a `.portkiller.json` (or a
+//! `portkiller` key in `package.json`) in the root of each detected project.
+//! Objects merge key by key and later layers win. Project files are only read
+//! from folders in `projects.trusted_roots`, and may only add entries to a few
+//! lists (port ranges, services and catalog entries), which they append to
+//! instead of replacing. Ignore rules and script integrations stay user-only:
+//! a cloned repository shouldn't be able to hide listeners or run commands
+//! without a click.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+
+use crate::config::{Config, get_config_path, validate_config, write_config_file};
+use crate::config_format::{ConfigFormat, find_config_file};
+use crate::model::ProjectInfo;
+use crate::project::{is_trusted, trusted_dirs};
+
+/// Looked up with any config extension: `.portkiller.toml`, `.portkiller.yaml`, …
+pub const PROJECT_CONFIG_FILE: &str = ".portkiller.json";
+/// Key holding PortKiller settings in a `package.json`
+const PACKAGE_JSON_KEY: &str = "portkiller";
+/// The lists a project layer may add entries to
+const PROJECT_LISTS: &[&str] = &["monitoring.port_ranges", "services", "catalog"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayerSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+}
+
+impl fmt::Display for LayerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("default"),
+            Self::System(path) => write!(f, "system {}", path.display()),
+            Self::User(path) => write!(f, "user {}", path.display()),
+            Self::Project(path) => write!(f, "project {}", path.display()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigLayer {
+    pub source: LayerSource,
+    pub value: Value,
+}
+
+/// The layers the effective config is resolved from.
+#[derive(Clone, Debug, Default)]
+pub struct LayeredConfig {
+    /// Defaults, system-wide and user files
+    pub base: Vec<ConfigLayer>,
+    /// Files found in detected projects, in path order
+    pub projects: Vec<ConfigLayer>,
+    /// Project directories and work trees the project layers were looked up for
+    project_dirs: Vec<(PathBuf, Option<PathBuf>)>,
+    /// Project config files left unread because their folder isn't trusted
+    pub untrusted: Vec<PathBuf>,
+}
+
+pub struct EffectiveConfig {
+    pub config: Config,
+    pub origins: Origins,
+    /// Project layers left out because they were invalid, with the reason
+    pub skipped: Vec<String>,
+}
+
+impl LayeredConfig {
+    pub fn new(base: Vec<ConfigLayer>) -> Self {
+        Self {
+            base,
+            ..Self::default()
+        }
+    }
+
+    /// Remember the roots of detected projects and look up the layers of new
+    /// ones. Projects stay known after their processes exit, so their services
+    /// can be started again. Returns whether the layers differ from before.
+    pub fn add_projects<'a>(
+        &mut self,
+        projects: impl IntoIterator<Item = &'a ProjectInfo>,
+    ) -> bool {
+        let before = self.project_dirs.len();
+        for project in projects {
+            let dir = (project.path.clone(), project.worktree.clone());
+            if !self.project_dirs.contains(&dir) {
+                self.project_dirs.push(dir);
+            }
+        }
+        if self.project_dirs.len() == before {
+            return false;
+        }
+        self.project_dirs.sort();
+        self.reload_projects()
+    }
+
+    /// Re-read the project layers of the known projects and of trusted
+    /// folders named without wildcards. Returns whether they changed.
+    pub fn reload_projects(&mut self) -> bool {
+        // Only the defaults, system and user files decide what is trusted
+        let projects_config = parse(&self.merge_base().0)
+            .map(|config| config.projects)
+            .unwrap_or_default();
+        let trusted_dirs = trusted_dirs(&projects_config)
+            .into_iter()
+            .map(|dir| (dir, None));
+        let mut layers: Vec<ConfigLayer> = Vec::new();
+        let mut untrusted: Vec<PathBuf> = Vec::new();
+        for (dir, worktree) in trusted_dirs.chain(self.project_dirs.iter().cloned()) {
+            let Some(layer) = find_project_layer(&dir, worktree.as_deref()) else {
+                continue;
+            };
+            let LayerSource::Project(file) = &layer.source else {
+                continue;
+            };
+            let trusted = file
+                .parent()
+                .is_some_and(|folder| is_trusted(folder, &projects_config));
+            if !trusted {
+                if !untrusted.contains(file) {
+                    log::info!("Ignoring untrusted project config {}", file.display());
+                    untrusted.push(file.clone());
+                }
+            } else if !layers.iter().any(|known| known.source == layer.source) {
+                layers.push(layer);
+            }
+        }
+        self.untrusted = untrusted;
+        let changed = layers != self.projects;
+        self.projects = layers;
+        changed
+    }
+
+    fn merge_base(&self) -> (Value, Origins) {
+        let mut value = Value::Object(Map::new());
+        let mut origins = Origins::default();
+        for layer in &self.base {
+            merge(&mut value, &layer.value, "", &layer.source, &mut origins);
+        }
+        (value, origins)
+    }
+
+    /// Merge the layers into a validated config. Errors in the base layers
+    /// fail the whole load; an invalid project layer is skipped.
+    pub fn resolve(&self) -> Result<EffectiveConfig> {
+        let (mut value, mut origins) = self.merge_base();
+        let mut config = parse(&value)?;
+
+        let mut skipped = Vec::new();
+        for layer in &self.projects {
+            let mut candidate = value.clone();
+            let mut candidate_origins = origins.clone();
+            let result = append_project(
+                &mut candidate,
+                &layer.value,
+                &layer.source,
+                &mut candidate_origins,
+            )
+            .and_then(|()| parse(&candidate));
+            match result {
+                Ok(merged) => {
+                    value = candidate;
+                    origins = candidate_origins;
+                    config = merged;
+                }
+                Err(err) => skipped.push(format!("{}: {:#}", layer.source, err)),
+            }
+        }
+        Ok(EffectiveConfig {
+            config,
+            origins,
+            skipped,
+        })
+    }
+}
+
+fn parse(value: &Value) -> Result<Config> {
+    let config: Config = serde_json::from_value(value.clone()).context("invalid config")?;
+    validate_config(&config)?;
+    Ok(config)
+}
+
+/// Where each setting of the effective config came from.
+#[derive(Clone, Debug, Default)]
+pub struct Origins(Vec<(String, LayerSource)>);
+
+impl Origins {
+    fn record(&mut self, path: String, source: &LayerSource) {
+        // A replaced value takes everything below it along
+        self.0.retain(|(existing, _)| !is_within(existing, &path));
+        self.0.push((path, source.clone()));
+    }
+
+    /// The layer that set `path` (`monitoring.port_ranges[2]`), or one of its parents.
+    pub fn source_of(&self, path: &str) -> &LayerSource {
+        self.0
+            .iter()
+            .filter(|(recorded, _)| is_within(path, recorded))
+            .max_by_key(|(recorded, _)| recorded.len())
+            .map_or(&LayerSource::Default, |(_, source)| source)
+    }
+
+    /// One `path = value  # source` line per setting, after the project
+    /// files that were left out as untrusted.
+    pub fn report(&self, config: &Config, untrusted: &[PathBuf]) -> String {
+        let mut lines = vec![
+            "# Effective PortKiller configuration".to_string(),
+            "# Each line shows the layer the setting comes from.".to_string(),
+        ];
+        if !untrusted.is_empty() {
+            lines.push("# Ignored, not under projects.trusted_roots:".to_string());
+            lines.extend(
+                untrusted
+                    .iter()
+                    .map(|file| format!("#   {}", file.display())),
+            );
+        }
+        lines.push(String::new());
+        let value = serde_json::to_value(config).unwrap_or(Value::Null);
+        let mut add = |path: &str, value: &Value| {
+            lines.push(format!("{} = {}  # {}", path, value, self.source_of(path)));
+        };
+        walk(&value, "", &mut add);
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Visit scalars, empty containers and list items, depth first.
+fn walk(value: &Value, path: &str, visit: &mut impl FnMut(&str, &Value)) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                walk(child, &join(path, key), visit);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, item) in items.iter().enumerate() {
+                visit(&format!("{}[{}]", path, index), item);
+            }
+        }
+        _ => visit(path, value),
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Whether `path` is `parent` or lies below it.
+fn is_within(path: &str, parent: &str) -> bool {
+    parent.is_empty()
+        || path
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+}
+
+fn merge(
+    target: &mut Value,
+    layer: &Value,
+    path: &str,
+    source: &LayerSource,
+    origins: &mut Origins,
+) {
+    match (target, layer) {
+        (Value::Object(target), Value::Object(layer)) => {
+            for (key, value) in layer {
+                let child = join(path, key);
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value, &child, source, origins),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                        origins.record(child, source);
+                    }
+                }
+            }
+        }
+        (target, layer) => {
+            *target = layer.clone();
+            origins.record(path.to_string(), source);
+        }
+    }
+}
+
+/// Append the lists of a project layer, rejecting any other setting.
+fn append_project(
+    target: &mut Value,
+    layer: &Value,
+    source: &LayerSource,
+    origins: &mut Origins,
+) -> Result<()> {
+    check_project_keys(layer, "")?;
+    for list in PROJECT_LISTS {
+        let Some(items) = list.split('.').try_fold(layer, |value, key| value.get(key)) else {
+            continue;
+        };
+        let items = items
+            .as_array()
+            .with_context(|| format!("{} must be a list", list))?;
+        let mut existing = &mut *target;
+        for key in list.split('.') {
+            existing = existing
+                .as_object_mut()
+                .context("config is not an object")?
+                .entry(key)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if !existing.is_array() {
+            *existing = Value::Array(Vec::new());
+        }
+        let existing = existing.as_array_mut().expect("just made an array");
+        for item in items {
+            let mut item = item.clone();
+            // Project services run in the project unless they say otherwise
+            if *list == "services"
+                && let LayerSource::Project(file) = source
+                && let (Some(service), Some(dir)) = (item.as_object_mut(), file.parent())
+            {
+                service
+                    .entry("cwd")
+                    .or_insert_with(|| Value::String(dir.to_string_lossy().into_owned()));
+            }
+            origins.record(format!("{}[{}]", list, existing.len()), source);
+            existing.push(item);
+        }
+    }
+    Ok(())
+}
+
+fn check_project_keys(value: &Value, path: &str) -> Result<()> {
+    let map = value.as_object().with_context(|| {
+        format!(
+            "{} must be an object",
+            if path.is_empty() { "the file" } else { path }
+        )
+    })?;
+    for (key, child) in map {
+        let child_path = join(path, key);
+        if PROJECT_LISTS.contains(&child_path.as_str()) {
+            continue;
+        }
+        if PROJECT_LISTS
+            .iter()
+            .any(|list| is_within(list, &child_path))
+        {
+            check_project_keys(child, &child_path)?;
+        } else {
+            anyhow::bail!(
+                "{} can't be set per project (only {})",
+                child_path,
+                PROJECT_LISTS.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Machine-wide defaults shared by every user; a `.toml` or `.yaml` file next
+/// to this path is used instead when present.
+pub fn system_config_path() -> PathBuf {
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support/PortKiller/portkiller.json")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let data = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+        PathBuf::from(data)
+            .join("PortKiller")
+            .join("portkiller.json")
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        PathBuf::from("/etc/portkiller/portkiller.json")
+    }
+}
+
+/// Defaults, the system-wide file when there is one, and the user's file,
+/// which is created on first run.
+pub fn load_base_layers() -> Result<Vec<ConfigLayer>> {
+    let mut layers = vec![ConfigLayer {
+        source: LayerSource::Default,
+        value: serde_json::to_value(Config::default()).context("failed to serialize defaults")?,
+    }];
+
+    let system = find_config_file(&system_config_path());
+    let has_system = system.is_some();
+    if let Some(system) = system {
+        layers.push(ConfigLayer {
+            value: read_config(&system)?,
+            source: LayerSource::System(system),
+        });
+    }
+
+    let user = get_config_path();
+    if !user.exists() {
+        // A full copy of the defaults would override every system-wide setting
+        let content = if has_system {
+            "{}\n".to_string()
+        } else {
+            ConfigFormat::of(&user)
+                .render(&Config::default())
+                .context("failed to serialize config")?
+        };
+        write_config_file(&user, &content)?;
+    }
+    #[cfg(unix)]
+    crate::config::ensure_secure_permissions(&user)?;
+    layers.push(ConfigLayer {
+        value: read_config(&user)?,
+        source: LayerSource::User(user),
+    });
+    Ok(layers)
+}
+
+fn read_config(path: &Path) -> Result<Value> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    ConfigFormat::of(path)
+        .parse(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// The project config above `dir`, looking no further up than `root` (the
+/// work tree) or, without one, the directory below the home directory.
+pub fn find_project_layer(dir: &Path, root: Option<&Path>) -> Option<ConfigLayer> {
+    let home = dirs::home_dir();
+    for dir in dir.ancestors() {
+        if home.as_deref() == Some(dir) {
+            break;
+        }
+        if let Some(file) = find_config_file(&dir.join(PROJECT_CONFIG_FILE)) {
+            return match read_config(&file) {
+                Ok(value) => Some(ConfigLayer {
+                    source: LayerSource::Project(file),
+                    value,
+                }),
+                Err(err) => {
+                    log::warn!("Ignoring project config: {:#}", err);
+                    None
+                }
+            };
+        }
+        let package = dir.join("package.json");
+        if let Ok(mut value) = read_config(&package)
+            && let Some(settings) = value.get_mut(PACKAGE_JSON_KEY)
+        {
+            return Some(ConfigLayer {
+                value: settings.take(),
+                source: LayerSource::Project(package),
+            });
+        }
+        if root == Some(dir) || (root.is_none() && home.as_deref() == dir.parent()) {
+            break;
+        }
+    }
+    None
+}
+
+/// Change one setting in the user's file, leaving the rest of it as written,
+/// comments included.
+pub fn set_user_setting(path: &[&str], value: Value) -> Result<()> {
+    let file = get_config_path();
+    let content = if file.exists() {
+        fs::read_to_string(&file).with_context(|| format!("failed to read {}", file.display()))?
+    } else {
+        String::new()
+    };
+    let format = ConfigFormat::of(&file);
+    // JSON needs a document to edit; the others start out empty
+    let content = if content.trim().is_empty() && format == ConfigFormat::Json {
+        "{}"
+    } else {
+        &content
+    };
+    let updated = format
+        .set(content, path, &value)
+        .with_context(|| format!("failed to update {}", file.display()))?;
+    write_config_file(&file, &updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(source: LayerSource, json: &str) -> ConfigLayer {
+        ConfigLayer {
+            source,
+            value: serde_json::from_str(json).unwrap(),
+        }
+    }
+
+    #[test]
+    fn merges_layers_and_tracks_origins() {
+        let system = LayerSource::System(PathBuf::from("/etc/pk.json"));
+        let user = LayerSource::User(PathBuf::from("/home/dev/.portkiller.json"));
+        let shop = LayerSource::Project(PathBuf::from("/src/shop/.portkiller.json"));
+        let layers = LayeredConfig {
+            base: vec![
+                layer(
+                    LayerSource::Default,
+                    &serde_json::to_string(&Config::default()).unwrap(),
+                ),
+                layer(
+                    system.clone(),
+                    r#"{"monitoring": {"poll_interval_secs": 5, "port_ranges": [[3000, 3001]]}}"#,
+                ),
+                layer(user.clone(), r#"{"monitoring": {"poll_interval_secs": 3}}"#),
+            ],
+            project_dirs: Vec::new(),
+            untrusted: Vec::new(),
+            projects: vec![
+                layer(
+                    shop.clone(),
+                    r#"{"monitoring": {"port_ranges": [{"name": "Shop", "ports": "4000", "protected": true}]}}"#,
+                ),
+                layer(
+                    LayerSource::Project(PathBuf::from("/src/bad/.portkiller.json")),
+                    r#"{"monitoring": {"poll_interval_secs": 1}}"#,
+                ),
+                layer(
+                    LayerSource::Project(PathBuf::from("/src/sneaky/.portkiller.json")),
+                    r#"{"monitoring": {"ignore": [{"command": "*"}]}}"#,
+                ),
+            ],
+        };
+        let effective = layers.resolve().unwrap();
+        let monitoring = &effective.config.monitoring;
+        assert_eq!(monitoring.poll_interval_secs, 3);
+        assert_eq!(monitoring.port_ranges.len(), 2);
+        assert!(monitoring.is_protected(4000));
+        assert_eq!(effective.skipped.len(), 2);
+        assert!(effective.skipped[0].contains("monitoring.poll_interval_secs"));
+        assert!(effective.skipped[1].contains("monitoring.ignore"));
+
+        let origins = &effective.origins;
+        assert_eq!(origins.source_of("monitoring.poll_interval_secs"), &user);
+        assert_eq!(origins.source_of("monitoring.port_ranges[0]"), &system);
+        assert_eq!(origins.source_of("monitoring.port_ranges[1]"), &shop);
+        assert_eq!(origins.source_of("health.enabled"), &LayerSource::Default);
+        assert!(
+            origins
+                .report(&effective.config, &[])
+                .contains("monitoring.poll_interval_secs = 3  # user /home/dev/.portkiller.json")
+        );
+    }
+}
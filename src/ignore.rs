@@ -0,0 +1,261 @@
+//! `monitoring.ignore` rules for noisy system listeners
+
+use std::collections::{HashMap, HashSet};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::config::IgnoreRule;
+use crate::model::{ProcessIdentity, ProcessInfo};
+use crate::port_set::PortSet;
+use crate::process::ports::{process_executable, process_user};
+
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    command: Option<Pattern>,
+    executable: Option<Pattern>,
+    user: Option<String>,
+    ports: Option<PortSet>,
+}
+
+/// Executable and owner of a process, read once per process lifetime and
+/// only when some rule looks at them.
+#[derive(Clone, Debug, Default)]
+struct Details {
+    executable: Option<String>,
+    user: Option<String>,
+}
+
+/// Processes whose executable or owner the rules need. Reading them runs `ps`
+/// on macOS, so it happens on a background thread.
+#[derive(Clone, Debug)]
+pub struct DetailsRequest {
+    generation: u64,
+    processes: Vec<(ProcessIdentity, i32)>,
+    executable: bool,
+    user: bool,
+}
+
+impl DetailsRequest {
+    pub fn read(self) -> ReadDetails {
+        let details = self
+            .processes
+            .into_iter()
+            .map(|(identity, pid)| {
+                let details = Details {
+                    executable: self
+                        .executable
+                        .then(|| process_executable(pid))
+                        .flatten()
+                        .map(|path| path.to_string_lossy().into_owned()),
+                    user: self.user.then(|| process_user(pid)).flatten(),
+                };
+                (identity, details)
+            })
+            .collect();
+        ReadDetails {
+            generation: self.generation,
+            details,
+        }
+    }
+}
+
+/// Result of a [`DetailsRequest`], handed back to [`IgnoreList::apply_details`].
+#[derive(Clone, Debug)]
+pub struct ReadDetails {
+    generation: u64,
+    details: Vec<(ProcessIdentity, Details)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreList {
+    rules: Vec<CompiledRule>,
+    details: HashMap<ProcessIdentity, Details>,
+    requested: HashSet<ProcessIdentity>,
+    // Bumped on reconfigure so details read for the old rules are dropped
+    generation: u64,
+}
+
+impl IgnoreList {
+    pub fn new(rules: &[IgnoreRule]) -> Self {
+        let mut list = Self::default();
+        list.reconfigure(rules);
+        list
+    }
+
+    /// Swap in new rules. Invalid patterns were already rejected by config validation.
+    pub fn reconfigure(&mut self, rules: &[IgnoreRule]) {
+        let pattern = |glob: &Option<String>| glob.as_deref().and_then(|g| Pattern::new(g).ok());
+        self.rules = rules
+            .iter()
+            .map(|rule| CompiledRule {
+                command: pattern(&rule.command),
+                executable: pattern(&rule.executable),
+                user: rule.user.clone(),
+                ports: rule.ports.clone(),
+            })
+            .collect();
+        // Details read for the old rules may be missing fields the new ones need
+        self.details.clear();
+        self.requested.clear();
+        self.generation += 1;
+    }
+
+    /// The executables and owners the rules need for processes not seen before,
+    /// or `None` when there's nothing to read.
+    pub fn request_missing(&mut self, processes: &[ProcessInfo]) -> Option<DetailsRequest> {
+        let executable = self.rules.iter().any(|rule| rule.executable.is_some());
+        let user = self.rules.iter().any(|rule| rule.user.is_some());
+        if !executable && !user {
+            return None;
+        }
+        let missing: Vec<(ProcessIdentity, i32)> = processes
+            .iter()
+            .map(|process| (process.identity(), process.pid))
+            .filter(|(identity, _)| {
+                !self.details.contains_key(identity) && self.requested.insert(*identity)
+            })
+            .collect();
+        (!missing.is_empty()).then_some(DetailsRequest {
+            generation: self.generation,
+            processes: missing,
+            executable,
+            user,
+        })
+    }
+
+    /// Store details read in the background. Returns whether anything was added.
+    pub fn apply_details(&mut self, read: ReadDetails) -> bool {
+        if read.generation != self.generation {
+            return false;
+        }
+        let mut added = false;
+        for (identity, details) in read.details {
+            // Skip processes that exited while their details were read
+            if self.requested.remove(&identity) {
+                self.details.insert(identity, details);
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// Drop details of processes that are gone.
+    pub fn retain_active(&mut self, processes: &[ProcessInfo]) {
+        let active: HashSet<ProcessIdentity> = processes.iter().map(|p| p.identity()).collect();
+        self.details.retain(|identity, _| active.contains(identity));
+        self.requested.retain(|identity| active.contains(identity));
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let details = self.details.get(&process.identity());
+        self.rules.iter().any(|rule| {
+            rule.command
+                .as_ref()
+                .is_none_or(|p| p.matches_with(&process.command, options))
+                && rule.executable.as_ref().is_none_or(|p| {
+                    details
+                        .and_then(|d| d.executable.as_deref())
+                        .is_some_and(|path| p.matches_with(path, options))
+                })
+                && rule.user.as_ref().is_none_or(|user| {
+                    details
+                        .and_then(|d| d.user.as_deref())
+                        .is_some_and(|owner| owner.eq_ignore_ascii_case(user))
+                })
+                && rule
+                    .ports
+                    .as_ref()
+                    .is_none_or(|ports| ports.contains(process.port))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(port: u16, command: &str) -> ProcessInfo {
+        ProcessInfo {
+            port,
+            pid: 7,
+            command: command.to_string(),
+            start_time: None,
+            address: None,
+        }
+    }
+
+    #[test]
+    fn matches_every_given_field() {
+        let mut list = IgnoreList::new(&[
+            IgnoreRule {
+                command: Some("controlce*".to_string()),
+                executable: None,
+                user: None,
+                ports: None,
+            },
+            IgnoreRule {
+                command: Some("node".to_string()),
+                executable: None,
+                user: Some("ROOT".to_string()),
+                ports: Some("9000-9010".parse().unwrap()),
+            },
+        ]);
+        assert!(list.matches(&process(5000, "ControlCenter")));
+        assert!(!list.matches(&process(3000, "Control")));
+
+        let root_node = process(9005, "node");
+        list.details.insert(
+            root_node.identity(),
+            Details {
+                executable: None,
+                user: Some("root".to_string()),
+            },
+        );
+        assert!(list.matches(&root_node));
+        assert!(!list.matches(&process(9011, "node")));
+        // The owner was never read for this one
+        assert!(!list.matches(&ProcessInfo {
+            pid: 8,
+            ..root_node
+        }));
+    }
+
+    #[test]
+    fn drops_details_read_for_old_rules() {
+        let rules = [IgnoreRule {
+            command: None,
+            executable: None,
+            user: Some("root".to_string()),
+            ports: None,
+        }];
+        let mut list = IgnoreList::new(&rules);
+        let listeners = [process(3000, "node")];
+        let node = &listeners[0];
+        let request = list.request_missing(&listeners).unwrap();
+        assert_eq!(request.processes, vec![(node.identity(), 7)]);
+        // Already being read
+        assert!(list.request_missing(&listeners).is_none());
+
+        let read = |generation| ReadDetails {
+            generation,
+            details: vec![(
+                node.identity(),
+                Details {
+                    executable: None,
+                    user: Some("root".to_string()),
+                },
+            )],
+        };
+        list.reconfigure(&rules);
+        assert!(!list.apply_details(read(request.generation)));
+        assert!(!list.matches(node));
+
+        let request = list.request_missing(&listeners).unwrap();
+        assert!(list.apply_details(read(request.generation)));
+        assert!(list.matches(node));
+    }
+}
@@ -1,30 +1,82 @@
 use std::collections::HashMap;
+use std::io::ErrorKind;
 use std::process::Command;
+use std::time::Duration;
 
-use anyhow::Result;
-use log::warn;
+use anyhow::{Result, anyhow};
+use log::debug;
 
-use crate::model::KillFeedback;
-use crate::utils::find_command;
+use crate::integrations::registry::Integration;
+use crate::model::{KillFeedback, ManagedUnit, ProcessInfo};
+use crate::utils::{find_command, output_with_timeout};
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Homebrew services, claimed by command name and the service's default port.
+pub struct BrewIntegration;
+
+impl Integration for BrewIntegration {
+    fn id(&self) -> &str {
+        "brew"
+    }
+
+    fn display_name(&self) -> &str {
+        "Brew"
+    }
+
+    fn section_title(&self) -> &str {
+        "Brew Services"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Stop All Services"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        Ok(query_brew_services_map()?
+            .into_iter()
+            .map(|(name, status)| ManagedUnit {
+                ports: get_default_port_for_service(&name).into_iter().collect(),
+                name,
+                status,
+                pids: Vec::new(),
+            })
+            .collect())
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        let services: HashMap<String, String> = units
+            .iter()
+            .map(|u| (u.name.clone(), u.status.clone()))
+            .collect();
+        get_brew_managed_service(&process.command, process.port, &services)
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        run_brew_stop(unit)
+    }
+}
 
 pub fn query_brew_services_map() -> Result<HashMap<String, String>> {
     let mut map = HashMap::new();
-    let out = Command::new(find_command("brew"))
-        .args(["services", "list"])
-        .output();
+    let out = output_with_timeout(
+        Command::new(find_command("brew")).args(["services", "list"]),
+        QUERY_TIMEOUT,
+    );
     let out = match out {
         Ok(o) => o,
-        Err(err) => {
-            warn!("Brew command failed (brew not installed?): {}", err);
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            debug!("Brew not installed: {}", err);
             return Ok(map);
         }
+        Err(err) => return Err(anyhow!("brew services list failed: {}", err)),
     };
     if !out.status.success() {
-        warn!(
-            "Brew services list command failed: {}",
-            String::from_utf8_lossy(&out.stderr)
-        );
-        return Ok(map);
+        return Err(anyhow!(
+            "brew services list failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
     }
     let stdout = String::from_utf8_lossy(&out.stdout);
     for (idx, line) in stdout.lines().enumerate() {
@@ -61,9 +113,10 @@ pub fn get_brew_managed_service(
 }
 
 pub fn run_brew_stop(service: &str) -> KillFeedback {
-    let res = Command::new(find_command("brew"))
-        .args(["services", "stop", service])
-        .output();
+    let res = output_with_timeout(
+        Command::new(find_command("brew")).args(["services", "stop", service]),
+        STOP_TIMEOUT,
+    );
     match res {
         Ok(out) if out.status.success() => {
             KillFeedback::info(format!("Stopped brew service {}.", service))
@@ -1,40 +1,104 @@
-use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::time::Duration;
 
-use anyhow::Result;
-use log::warn;
+use anyhow::{Result, anyhow};
+use log::debug;
 
-use crate::model::{DockerContainerInfo, KillFeedback};
-use crate::utils::{find_command, hidden_command};
+use crate::catalog::Catalog;
+use crate::integrations::registry::Integration;
+use crate::model::{KillFeedback, ManagedUnit, ProcessInfo};
+use crate::utils::{find_command, hidden_command, output_with_timeout};
 
-pub fn query_docker_port_map() -> Result<HashMap<u16, DockerContainerInfo>> {
-    let mut map = HashMap::new();
-    let out = hidden_command(find_command("docker"))
-        .args(["ps", "--format", "{{.ID}}\t{{.Names}}\t{{.Ports}}"])
-        .output();
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+// `docker stop` waits up to 10s for the container before SIGKILL
+const STOP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Running containers with published host ports, claimed by port.
+pub struct DockerIntegration {
+    catalog: Catalog,
+}
+
+impl DockerIntegration {
+    pub fn new(catalog: Catalog) -> Self {
+        Self { catalog }
+    }
+}
+
+impl Integration for DockerIntegration {
+    fn id(&self) -> &str {
+        "docker"
+    }
+
+    fn display_name(&self) -> &str {
+        "Docker"
+    }
+
+    fn section_title(&self) -> &str {
+        "Docker Containers"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Stop All Containers"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        query_docker_containers()
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.ports.contains(&process.port))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn unit_label(&self, unit: &str) -> String {
+        friendly_container_name(&self.catalog, unit)
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        run_docker_stop(unit)
+    }
+}
+
+pub fn query_docker_containers() -> Result<Vec<ManagedUnit>> {
+    let out = output_with_timeout(
+        hidden_command(find_command("docker")).args([
+            "ps",
+            "--format",
+            "{{.ID}}\t{{.Names}}\t{{.Ports}}",
+        ]),
+        QUERY_TIMEOUT,
+    );
     let out = match out {
         Ok(o) => o,
-        Err(err) => {
-            warn!("Docker command failed (docker not installed?): {}", err);
-            return Ok(map);
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            debug!("Docker not installed: {}", err);
+            return Ok(Vec::new());
         }
+        Err(err) => return Err(anyhow!("docker ps failed: {}", err)),
     };
     if !out.status.success() {
-        warn!(
-            "Docker ps command failed: {}",
-            String::from_utf8_lossy(&out.stderr)
-        );
-        return Ok(map);
+        return Err(anyhow!(
+            "docker ps failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
     }
-    let stdout = String::from_utf8_lossy(&out.stdout);
+    Ok(parse_docker_ps(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Parse `docker ps --format "{{.ID}}\t{{.Names}}\t{{.Ports}}"` into one unit per container
+/// that publishes at least one single (non-range) host port.
+fn parse_docker_ps(stdout: &str) -> Vec<ManagedUnit> {
+    let mut units = Vec::new();
     for line in stdout.lines() {
         let parts: Vec<&str> = line.split('\t').collect();
         if parts.len() < 3 {
             continue;
         }
-        let id = parts[0].to_string();
         let name = parts[1].to_string();
-        let ports = parts[2];
-        for seg in ports.split(',') {
+        let mut ports = Vec::new();
+        for seg in parts[2].split(',') {
             let seg = seg.trim();
             if seg.is_empty() {
                 continue;
@@ -45,25 +109,30 @@ pub fn query_docker_port_map() -> Result<HashMap<u16, DockerContainerInfo>> {
                 if host.contains('-') {
                     continue;
                 }
-                if let Ok(p) = host.parse::<u16>() {
-                    map.insert(
-                        p,
-                        DockerContainerInfo {
-                            name: name.clone(),
-                            id: id.clone(),
-                        },
-                    );
+                if let Ok(p) = host.parse::<u16>()
+                    && !ports.contains(&p)
+                {
+                    ports.push(p);
                 }
             }
         }
+        if !ports.is_empty() {
+            units.push(ManagedUnit {
+                name,
+                status: "running".to_string(),
+                ports,
+                pids: Vec::new(),
+            });
+        }
     }
-    Ok(map)
+    units
 }
 
 pub fn run_docker_stop(container: &str) -> KillFeedback {
-    let res = hidden_command(find_command("docker"))
-        .args(["stop", container])
-        .output();
+    let res = output_with_timeout(
+        hidden_command(find_command("docker")).args(["stop", container]),
+        STOP_TIMEOUT,
+    );
     match res {
         Ok(out) if out.status.success() => {
             KillFeedback::info(format!("Stopped container {}.", container))
@@ -76,3 +145,22 @@ pub fn run_docker_stop(container: &str) -> KillFeedback {
         Err(err) => KillFeedback::error(format!("docker stop error: {}", err)),
     }
 }
+
+/// Maps container names to friendly display names through the catalog
+fn friendly_container_name(catalog: &Catalog, raw_name: &str) -> String {
+    // Strip common prefixes
+    let name = raw_name
+        .trim_start_matches("portkiller-")
+        .trim_start_matches("test-")
+        .trim_start_matches("dev-");
+
+    if let Some(friendly) = catalog.describe_container(name) {
+        return friendly.to_string();
+    }
+    // Capitalize first letter of unknown containers
+    let mut chars = name.chars();
+    match chars.next() {
+        None => name.to_string(),
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+    }
+}
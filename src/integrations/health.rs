@@ -0,0 +1,166 @@
+//! Circuit breaker for integration refreshes
+//!
+//! External tools (docker, brew, sc) can hang or fail repeatedly, e.g. while
+//! the Docker daemon is starting. After a few consecutive failures the breaker
+//! opens and the integration is skipped with exponential backoff. Its last
+//! successful result is kept meanwhile, so managed units aren't suddenly
+//! treated as plain processes.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use log::{debug, warn};
+
+use crate::model::IntegrationHealth;
+
+const FAILURE_THRESHOLD: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_secs(15);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl CircuitBreaker {
+    /// Whether a refresh may run now (breaker closed or backoff elapsed).
+    pub fn allows(&self, now: Instant) -> bool {
+        self.open_until.is_none_or(|until| now >= until)
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.open_until = None;
+        self.last_error = None;
+    }
+
+    pub fn record_failure(&mut self, error: String, now: Instant) {
+        self.failures += 1;
+        self.last_error = Some(error);
+        if self.failures >= FAILURE_THRESHOLD {
+            let exponent = (self.failures - FAILURE_THRESHOLD).min(5);
+            let backoff = (BASE_BACKOFF * 2u32.pow(exponent)).min(MAX_BACKOFF);
+            self.open_until = Some(now + backoff);
+        }
+    }
+
+    pub fn health(&self, now: Instant) -> IntegrationHealth {
+        let Some(error) = self.last_error.clone() else {
+            return IntegrationHealth::Healthy;
+        };
+        match self.open_until {
+            Some(until) if until > now => IntegrationHealth::BackingOff {
+                error,
+                retry_in: until - now,
+            },
+            _ => IntegrationHealth::Failing {
+                error,
+                failures: self.failures,
+            },
+        }
+    }
+}
+
+/// A refresh guarded by a breaker, keeping the last successful result.
+#[derive(Debug, Default)]
+pub struct GuardedRefresh<T> {
+    breaker: CircuitBreaker,
+    last: T,
+}
+
+impl<T: Clone> GuardedRefresh<T> {
+    /// Run `query` unless the breaker is open. While it is open, or when the
+    /// query fails, the last successful result is returned instead.
+    pub fn run(&mut self, name: &str, now: Instant, query: impl FnOnce() -> Result<T>) -> T {
+        if !self.breaker.allows(now) {
+            debug!("{} refresh skipped while backing off", name);
+            return self.last.clone();
+        }
+        match query() {
+            Ok(result) => {
+                self.breaker.record_success();
+                self.last = result.clone();
+                result
+            }
+            Err(err) => {
+                warn!("{} refresh failed: {}", name, err);
+                self.breaker.record_failure(err.to_string(), now);
+                self.last.clone()
+            }
+        }
+    }
+
+    /// Anything but healthy means the last result is stale.
+    pub fn health(&self, now: Instant) -> IntegrationHealth {
+        self.breaker.health(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_and_recovers() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+        assert!(matches!(breaker.health(now), IntegrationHealth::Healthy));
+
+        breaker.record_failure("boom".into(), now);
+        breaker.record_failure("boom".into(), now);
+        assert!(breaker.allows(now));
+        assert!(matches!(
+            breaker.health(now),
+            IntegrationHealth::Failing { failures: 2, .. }
+        ));
+
+        breaker.record_failure("boom".into(), now);
+        assert!(!breaker.allows(now));
+        assert!(breaker.allows(now + BASE_BACKOFF));
+        assert!(matches!(
+            breaker.health(now),
+            IntegrationHealth::BackingOff { .. }
+        ));
+
+        breaker.record_success();
+        assert!(breaker.allows(now));
+        assert!(matches!(breaker.health(now), IntegrationHealth::Healthy));
+    }
+
+    #[test]
+    fn failures_keep_the_last_result() {
+        let now = Instant::now();
+        let mut refresh = GuardedRefresh::<Vec<&str>>::default();
+        assert_eq!(refresh.run("test", now, || Ok(vec!["web"])), vec!["web"]);
+        for _ in 0..FAILURE_THRESHOLD {
+            let units = refresh.run("test", now, || Err(anyhow::anyhow!("boom")));
+            assert_eq!(units, vec!["web"]);
+        }
+        assert!(matches!(
+            refresh.health(now),
+            IntegrationHealth::BackingOff { .. }
+        ));
+        // Skipped while open, still the earlier units
+        let units = refresh.run("test", now, || panic!("breaker is open"));
+        assert_eq!(units, vec!["web"]);
+
+        assert!(
+            refresh
+                .run("test", now + MAX_BACKOFF, || Ok(vec![]))
+                .is_empty()
+        );
+        assert!(matches!(refresh.health(now), IntegrationHealth::Healthy));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+        for _ in 0..20 {
+            breaker.record_failure("boom".into(), now);
+        }
+        assert!(breaker.allows(now + MAX_BACKOFF));
+    }
+}
@@ -0,0 +1,431 @@
+//! kubectl port-forward integration
+//!
+//! `kubectl port-forward` processes are long-lived listeners that all show up
+//! as `kubectl`. Their command lines are parsed for the context, namespace,
+//! resource and port mappings so they can be labelled `8080 → svc/api:80 (staging)`.
+//! Forwards that die stay listed so the same command can be re-run.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::integrations::registry::Integration;
+use crate::model::{FeedbackSender, KillFeedback, KillOutcome, ManagedUnit, ProcessInfo};
+use crate::process::kill::terminate_pid;
+use crate::process::ports::command_lines_for;
+use crate::utils::{detached_command, find_command};
+
+// A relaunched forward that survives this long is considered started
+const STARTUP_GRACE: Duration = Duration::from_millis(1500);
+const POLL_STEP: Duration = Duration::from_millis(100);
+
+// kubectl flags that take a separate value argument
+const VALUE_FLAGS: &[&str] = &[
+    "--address",
+    "--as",
+    "--as-group",
+    "--as-uid",
+    "--cache-dir",
+    "--certificate-authority",
+    "--client-certificate",
+    "--client-key",
+    "--cluster",
+    "--context",
+    "--kubeconfig",
+    "--log-file",
+    "--namespace",
+    "--pod-running-timeout",
+    "--request-timeout",
+    "--server",
+    "--tls-server-name",
+    "--token",
+    "--user",
+    "-n",
+    "-s",
+    "-v",
+];
+
+/// A parsed `kubectl port-forward` command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortForward {
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    /// `svc/api`, `deployment/web`, `pod/db-0` (bare pod names get a `pod/` prefix)
+    pub resource: String,
+    /// `(local, remote)`; local is `None` when kubectl picks a random port (`:80`)
+    pub mappings: Vec<(Option<u16>, u16)>,
+    pub argv: Vec<String>,
+}
+
+impl PortForward {
+    fn local_ports(&self) -> Vec<u16> {
+        self.mappings
+            .iter()
+            .filter_map(|(local, _)| *local)
+            .collect()
+    }
+
+    /// `8080 → svc/api:80, 8443 → svc/api:443 (staging)`
+    pub fn label(&self) -> String {
+        let mappings = self
+            .mappings
+            .iter()
+            .map(|(local, remote)| match local {
+                Some(local) => format!("{} → {}:{}", local, self.resource, remote),
+                None => format!("? → {}:{}", self.resource, remote),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        match &self.context {
+            Some(context) => format!("{} ({})", mappings, context),
+            None => mappings,
+        }
+    }
+}
+
+struct TrackedForward {
+    forward: PortForward,
+    /// `None` once the process has exited
+    pid: Option<i32>,
+}
+
+/// Running (and recently exited) port-forwards, claimed by PID.
+#[derive(Default)]
+pub struct KubectlIntegration {
+    forwards: Mutex<BTreeMap<String, TrackedForward>>,
+}
+
+impl Integration for KubectlIntegration {
+    fn id(&self) -> &str {
+        "kubectl"
+    }
+
+    fn display_name(&self) -> &str {
+        "kubectl"
+    }
+
+    fn section_title(&self) -> &str {
+        "Kubernetes Forwards"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Stop All Forwards"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let running: Vec<(i32, PortForward)> = command_lines_for("kubectl")?
+            .into_iter()
+            .filter_map(|(pid, argv)| parse_port_forward(&argv).map(|fwd| (pid, fwd)))
+            .collect();
+
+        let mut forwards = self.forwards.lock().unwrap_or_else(|e| e.into_inner());
+        for tracked in forwards.values_mut() {
+            tracked.pid = None;
+        }
+        for (pid, forward) in running {
+            forwards.insert(
+                unit_name(&forward, pid),
+                TrackedForward {
+                    forward,
+                    pid: Some(pid),
+                },
+            );
+        }
+        Ok(forwards
+            .iter()
+            .map(|(name, tracked)| ManagedUnit {
+                name: name.clone(),
+                status: if tracked.pid.is_some() {
+                    "running"
+                } else {
+                    "exited"
+                }
+                .to_string(),
+                ports: tracked.forward.local_ports(),
+                pids: tracked.pid.into_iter().collect(),
+            })
+            .collect())
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.pids.contains(&process.pid))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn unit_label(&self, unit: &str) -> String {
+        let forwards = self.forwards.lock().unwrap_or_else(|e| e.into_inner());
+        match forwards.get(unit) {
+            Some(tracked) if tracked.pid.is_some() => tracked.forward.label(),
+            Some(tracked) => format!("{} (exited)", tracked.forward.label()),
+            None => unit.to_string(),
+        }
+    }
+
+    fn unit_menu_label(&self, unit: &str, _ports: &[u16]) -> String {
+        // The label already leads with the local ports
+        self.unit_label(unit)
+    }
+
+    fn lists_idle_units(&self) -> bool {
+        true
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        let Some(tracked) = self.take(unit) else {
+            return KillFeedback::warning(format!("Port-forward {} is no longer tracked.", unit));
+        };
+        let label = tracked.forward.label();
+        let Some(pid) = tracked.pid else {
+            return KillFeedback::info(format!("Removed exited forward {}.", label));
+        };
+        match terminate_pid(pid) {
+            KillOutcome::Success | KillOutcome::AlreadyExited => {
+                KillFeedback::info(format!("Stopped forward {}.", label))
+            }
+            outcome => {
+                // Keep it listed so the user can retry
+                self.put_back(unit, tracked);
+                KillFeedback::error(format!("Failed to stop forward {}: {:?}.", label, outcome))
+            }
+        }
+    }
+
+    fn unit_actions(&self, _unit: &str) -> &[(&'static str, &'static str)] {
+        &[("restart", "Restart forward")]
+    }
+
+    fn run_action(&self, action: &str, unit: &str, _later: &FeedbackSender) -> KillFeedback {
+        if action != "restart" {
+            return KillFeedback::error(format!("Unknown kubectl action '{}'.", action));
+        }
+        let Some(mut tracked) = self.take(unit) else {
+            return KillFeedback::warning(format!("Port-forward {} is no longer tracked.", unit));
+        };
+        let label = tracked.forward.label();
+
+        if let Some(pid) = tracked.pid.take() {
+            match terminate_pid(pid) {
+                KillOutcome::Success | KillOutcome::AlreadyExited => {}
+                outcome => {
+                    tracked.pid = Some(pid);
+                    self.put_back(unit, tracked);
+                    return KillFeedback::error(format!(
+                        "Failed to stop forward {} before restarting: {:?}.",
+                        label, outcome
+                    ));
+                }
+            }
+        }
+
+        let feedback = match relaunch(&tracked.forward.argv) {
+            Ok(pid) => {
+                tracked.pid = Some(pid);
+                KillFeedback::info(format!("Restarted forward {} (PID {}).", label, pid))
+            }
+            Err(err) => KillFeedback::error(format!("Failed to restart {}: {}", label, err)),
+        };
+        self.put_back(unit, tracked);
+        feedback
+    }
+}
+
+impl KubectlIntegration {
+    fn take(&self, unit: &str) -> Option<TrackedForward> {
+        self.forwards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(unit)
+    }
+
+    fn put_back(&self, unit: &str, tracked: TrackedForward) {
+        self.forwards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(unit.to_string(), tracked);
+    }
+}
+
+/// Stable unit name built from the local ports, which only one forward can hold at a time.
+fn unit_name(forward: &PortForward, pid: i32) -> String {
+    let ports = forward.local_ports();
+    if ports.is_empty() {
+        format!("pid-{}", pid)
+    } else {
+        ports
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// Re-run a forward's command line detached, returning the new PID once it has
+/// survived the startup grace period.
+fn relaunch(argv: &[String]) -> Result<i32> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("empty command line"))?;
+    // GUI apps on macOS don't inherit the shell PATH, so resolve bare names
+    let program = if program.contains(['/', '\\']) {
+        program.as_str()
+    } else {
+        find_command("kubectl")
+    };
+    let mut child = detached_command(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let pid = child.id() as i32;
+
+    let deadline = Instant::now() + STARTUP_GRACE;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            anyhow::bail!("kubectl exited with {}: {}", status, stderr.trim());
+        }
+        thread::sleep(POLL_STEP);
+    }
+
+    // Drain stderr and reap the process when it eventually exits
+    thread::spawn(move || {
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = std::io::copy(&mut pipe, &mut std::io::sink());
+        }
+        let _ = child.wait();
+    });
+    Ok(pid)
+}
+
+/// Parse `kubectl [flags] port-forward [flags] TYPE/NAME [LOCAL:]REMOTE...`.
+pub fn parse_port_forward(argv: &[String]) -> Option<PortForward> {
+    let program = argv.first()?;
+    let program = program.rsplit(['/', '\\']).next()?;
+    if program != "kubectl" && program != "kubectl.exe" {
+        return None;
+    }
+
+    let mut context = None;
+    let mut namespace = None;
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut args = argv[1..].iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positionals.extend(args.by_ref().map(String::as_str));
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            positionals.push(arg);
+            continue;
+        }
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            // pflag also accepts `-nstaging`
+            None if arg.len() > 2 && !arg.starts_with("--") => {
+                (&arg[..2], Some(arg[2..].to_string()))
+            }
+            None => (arg.as_str(), None),
+        };
+        if !VALUE_FLAGS.contains(&flag) {
+            continue;
+        }
+        let value = match inline_value {
+            Some(value) => value,
+            None => args.next()?.clone(),
+        };
+        match flag {
+            "--context" => context = Some(value),
+            "-n" | "--namespace" => namespace = Some(value),
+            _ => {}
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
+    // Global flags may precede the subcommand
+    positionals.find(|p| *p == "port-forward")?;
+    let resource = positionals.next()?;
+    let resource = if resource.contains('/') {
+        resource.to_string()
+    } else {
+        format!("pod/{}", resource)
+    };
+
+    let mappings: Vec<(Option<u16>, u16)> = positionals
+        .filter_map(|spec| match spec.split_once(':') {
+            Some(("", remote)) => Some((None, remote.parse().ok()?)),
+            Some((local, remote)) => Some((Some(local.parse().ok()?), remote.parse().ok()?)),
+            None => {
+                let port = spec.parse().ok()?;
+                Some((Some(port), port))
+            }
+        })
+        .collect();
+    if mappings.is_empty() {
+        return None;
+    }
+
+    Some(PortForward {
+        context,
+        namespace,
+        resource,
+        mappings,
+        argv: argv.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_context_namespace_and_ports() {
+        let fwd = parse_port_forward(&argv(
+            "/usr/local/bin/kubectl --context staging port-forward -n shop svc/api 8080:80 9090",
+        ))
+        .unwrap();
+        assert_eq!(fwd.context.as_deref(), Some("staging"));
+        assert_eq!(fwd.namespace.as_deref(), Some("shop"));
+        assert_eq!(fwd.resource, "svc/api");
+        assert_eq!(fwd.mappings, vec![(Some(8080), 80), (Some(9090), 9090)]);
+        assert_eq!(
+            fwd.label(),
+            "8080 → svc/api:80, 9090 → svc/api:9090 (staging)"
+        );
+        assert_eq!(unit_name(&fwd, 1), "8080-9090");
+    }
+
+    #[test]
+    fn handles_inline_flags_and_bare_pods() {
+        let fwd = parse_port_forward(&argv(
+            "kubectl.exe port-forward --namespace=db -nshop --address 0.0.0.0 postgres-0 :5432",
+        ))
+        .unwrap();
+        assert_eq!(fwd.context, None);
+        assert_eq!(fwd.namespace.as_deref(), Some("shop"));
+        assert_eq!(fwd.resource, "pod/postgres-0");
+        assert_eq!(fwd.mappings, vec![(None, 5432)]);
+        assert_eq!(unit_name(&fwd, 77), "pid-77");
+    }
+
+    #[test]
+    fn ignores_other_kubectl_commands() {
+        assert!(parse_port_forward(&argv("kubectl get pods -w")).is_none());
+        assert!(parse_port_forward(&argv("kubectl port-forward svc/api")).is_none());
+        assert!(parse_port_forward(&argv("kubectx port-forward svc/api 80")).is_none());
+    }
+}
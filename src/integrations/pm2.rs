@@ -0,0 +1,219 @@
+//! PM2 process manager integration
+//!
+//! PM2 respawns apps the moment their PID is killed, so listeners owned by a
+//! PM2 app are stopped (or restarted/deleted) through `pm2` instead.
+
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use log::{debug, warn};
+use serde::Deserialize;
+
+use crate::integrations::registry::Integration;
+use crate::model::{FeedbackSender, KillFeedback, ManagedUnit, ProcessInfo};
+use crate::utils::{find_command, hidden_command, output_with_timeout};
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+const ACTION_TIMEOUT: Duration = Duration::from_secs(20);
+
+// npm installs pm2 as a .cmd shim on Windows, which Command won't resolve from a bare name
+#[cfg(target_os = "windows")]
+const PM2: &str = "pm2.cmd";
+#[cfg(not(target_os = "windows"))]
+const PM2: &str = "pm2";
+
+/// PM2 apps, claimed by the PIDs reported in `pm2 jlist`.
+pub struct Pm2Integration;
+
+#[derive(Deserialize)]
+struct Pm2App {
+    name: String,
+    #[serde(default)]
+    pid: i32,
+    #[serde(default)]
+    pm2_env: Pm2Env,
+}
+
+#[derive(Deserialize, Default)]
+struct Pm2Env {
+    #[serde(default)]
+    status: String,
+}
+
+impl Integration for Pm2Integration {
+    fn id(&self) -> &str {
+        "pm2"
+    }
+
+    fn display_name(&self) -> &str {
+        "PM2"
+    }
+
+    fn section_title(&self) -> &str {
+        "PM2 Apps"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Stop All Apps"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let out = output_with_timeout(
+            hidden_command(find_command(PM2)).arg("jlist"),
+            QUERY_TIMEOUT,
+        );
+        let out = match out {
+            Ok(o) => o,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                debug!("PM2 not installed: {}", err);
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(anyhow!("pm2 jlist failed: {}", err)),
+        };
+        if !out.status.success() {
+            return Err(anyhow!(
+                "pm2 jlist failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+        }
+        parse_jlist(&String::from_utf8_lossy(&out.stdout))
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.pids.contains(&process.pid))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        run_pm2("stop", unit)
+    }
+
+    fn unit_actions(&self, _unit: &str) -> &[(&'static str, &'static str)] {
+        &[("restart", "Restart"), ("delete", "Delete from PM2")]
+    }
+
+    fn run_action(&self, action: &str, unit: &str, _later: &FeedbackSender) -> KillFeedback {
+        match action {
+            "restart" | "delete" => run_pm2(action, unit),
+            _ => KillFeedback::error(format!("Unknown PM2 action '{}'.", action)),
+        }
+    }
+}
+
+fn run_pm2(verb: &str, app: &str) -> KillFeedback {
+    let res = output_with_timeout(
+        hidden_command(find_command(PM2)).args([verb, app]),
+        ACTION_TIMEOUT,
+    );
+    match res {
+        Ok(out) if out.status.success() => {
+            KillFeedback::info(format!("pm2 {} {} succeeded.", verb, app))
+        }
+        Ok(out) => KillFeedback::error(format!(
+            "pm2 {} {} failed: {}",
+            verb,
+            app,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )),
+        Err(err) => KillFeedback::error(format!("pm2 {} error: {}", verb, err)),
+    }
+}
+
+/// Parse `pm2 jlist` into one unit per app name; cluster-mode instances share a unit.
+/// PM2 may print `[PM2] ...` notices before the JSON, so parsing starts at the first `[`
+/// that opens an array of objects.
+fn parse_jlist(stdout: &str) -> Result<Vec<ManagedUnit>> {
+    let start = stdout
+        .match_indices('[')
+        .map(|(idx, _)| idx)
+        .find(|&idx| {
+            matches!(
+                stdout[idx + 1..].trim_start().chars().next(),
+                Some('{' | ']')
+            )
+        })
+        .ok_or_else(|| anyhow!("pm2 jlist printed no JSON"))?;
+    let apps: Vec<Pm2App> =
+        serde_json::from_str(stdout[start..].trim_end()).context("invalid pm2 jlist JSON")?;
+
+    let mut units: Vec<ManagedUnit> = Vec::new();
+    for app in apps {
+        if !app
+            .name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            warn!("Skipping PM2 app with unsupported name {:?}", app.name);
+            continue;
+        }
+        let index = match units.iter().position(|u| u.name == app.name) {
+            Some(index) => index,
+            None => {
+                units.push(ManagedUnit {
+                    name: app.name.clone(),
+                    status: app.pm2_env.status.clone(),
+                    ports: Vec::new(),
+                    pids: Vec::new(),
+                });
+                units.len() - 1
+            }
+        };
+        if app.pid > 0 {
+            units[index].pids.push(app.pid);
+        }
+    }
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JLIST: &str = include_str!("testdata/pm2_jlist.json");
+
+    fn process(pid: i32, port: u16) -> ProcessInfo {
+        ProcessInfo {
+            port,
+            pid,
+            command: "node".into(),
+            start_time: None,
+            address: None,
+        }
+    }
+
+    #[test]
+    fn parses_fixture_and_merges_cluster_instances() {
+        let units = parse_jlist(JLIST).unwrap();
+        assert_eq!(units.len(), 3);
+
+        let web = units.iter().find(|u| u.name == "web").unwrap();
+        assert_eq!(web.pids, vec![48230, 48231]);
+        assert_eq!(web.status, "online");
+
+        let worker = units.iter().find(|u| u.name == "worker").unwrap();
+        assert!(worker.pids.is_empty());
+        assert_eq!(worker.status, "stopped");
+    }
+
+    #[test]
+    fn claims_listeners_by_pid() {
+        let units = parse_jlist(JLIST).unwrap();
+        let pm2 = Pm2Integration;
+        assert_eq!(pm2.claim(&process(48211, 4001), &units), Some("api".into()));
+        assert_eq!(pm2.claim(&process(48231, 3000), &units), Some("web".into()));
+        assert_eq!(pm2.claim(&process(999, 3000), &units), None);
+    }
+
+    #[test]
+    fn tolerates_banner_before_json() {
+        let output = format!(
+            "[PM2] Spawning PM2 daemon with pm2_home=/tmp/.pm2\n{}",
+            JLIST
+        );
+        assert_eq!(parse_jlist(&output).unwrap().len(), 3);
+        assert!(parse_jlist("").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Integration trait and registry
+//!
+//! An integration knows how to list the units it manages (containers,
+//! services, ...), claim listeners that belong to one of those units, and stop
+//! them through the owning tool instead of killing the PID. The menu, worker
+//! and refresher only talk to integrations through this trait.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::model::{FeedbackSender, FeedbackSeverity, KillFeedback, ManagedUnit, ProcessInfo};
+
+pub trait Integration: Send + Sync {
+    /// Stable identifier used in menu ids, e.g. `docker`. Must not contain `_`.
+    fn id(&self) -> &str;
+
+    /// Short name for status lines and feedback, e.g. `Docker`.
+    fn display_name(&self) -> &str;
+
+    /// Menu section header, e.g. `Docker Containers`.
+    fn section_title(&self) -> &str;
+
+    /// Label for the section's bulk action.
+    fn stop_all_label(&self) -> &str {
+        "Stop All"
+    }
+
+    /// Query the external tool. Runs on the refresher thread, never on the UI thread.
+    fn refresh(&self) -> Result<Vec<ManagedUnit>>;
+
+    /// Name of the unit that owns this listener, if any.
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String>;
+
+    /// Menu label for a unit (defaults to its name).
+    fn unit_label(&self, unit: &str) -> String {
+        unit.to_string()
+    }
+
+    /// Full menu label for a unit and the listener ports it owns: "ports · label".
+    fn unit_menu_label(&self, unit: &str, ports: &[u16]) -> String {
+        if ports.is_empty() {
+            return self.unit_label(unit);
+        }
+        let ports_str = ports
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} · {}", ports_str, self.unit_label(unit))
+    }
+
+    /// List units even when none of their listeners were scanned, so actions stay
+    /// reachable for units that have exited or listen outside the port ranges.
+    fn lists_idle_units(&self) -> bool {
+        false
+    }
+
+    /// Stop a single unit. Runs on the worker thread.
+    fn stop(&self, unit: &str) -> KillFeedback;
+
+    /// Extra actions offered next to Stop for `unit`, as `(action id, label)`.
+    /// When non-empty the unit is rendered as a submenu. Action ids must not contain `_`.
+    fn unit_actions(&self, _unit: &str) -> &[(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Run one of `unit_actions` on a unit. Runs on the worker thread, so
+    /// anything slow should continue elsewhere and report through `later`.
+    fn run_action(&self, action: &str, unit: &str, _later: &FeedbackSender) -> KillFeedback {
+        KillFeedback::error(format!(
+            "{} does not support '{}' for {}.",
+            self.display_name(),
+            action,
+            unit
+        ))
+    }
+
+    /// Stop several units, summarising the outcome in one feedback message.
+    fn stop_all(&self, units: &[String]) -> KillFeedback {
+        let results: Vec<KillFeedback> = units.iter().map(|unit| self.stop(unit)).collect();
+        let failures: Vec<&KillFeedback> = results
+            .iter()
+            .filter(|f| !matches!(f.severity, FeedbackSeverity::Info))
+            .collect();
+        match failures.first() {
+            None => KillFeedback::info(format!(
+                "Stopped {} {} unit(s).",
+                results.len(),
+                self.display_name()
+            )),
+            Some(first) if failures.len() == results.len() => {
+                KillFeedback::error(format!("{}: {}", self.display_name(), first.message))
+            }
+            Some(first) => KillFeedback::warning(format!(
+                "{}: stopped {}, {} failed. First failure: {}",
+                self.display_name(),
+                results.len() - failures.len(),
+                failures.len(),
+                first.message
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for dyn Integration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Integration({})", self.id())
+    }
+}
+
+/// The integrations enabled by the current config, in claim priority order.
+#[derive(Clone, Debug, Default)]
+pub struct IntegrationRegistry {
+    integrations: Vec<Arc<dyn Integration>>,
+    detect_tunnels: bool,
+    proxy_configs: Vec<PathBuf>,
+}
+
+impl IntegrationRegistry {
+    pub fn from_config(config: &Config) -> Self {
+        let mut integrations: Vec<Arc<dyn Integration>> = Vec::new();
+        // Configured services first: their ports are the most specific claim
+        if !config.services.is_empty() {
+            integrations.push(Arc::new(
+                crate::integrations::services::ServicesIntegration::new(config.services.clone()),
+            ));
+        }
+        if config.integrations.docker_enabled {
+            integrations.push(Arc::new(
+                crate::integrations::docker::DockerIntegration::new(crate::catalog::Catalog::new(
+                    &config.catalog,
+                )),
+            ));
+        }
+        if config.integrations.pm2_enabled {
+            integrations.push(Arc::new(crate::integrations::pm2::Pm2Integration));
+        }
+        if config.integrations.kubectl_enabled {
+            integrations.push(Arc::new(
+                crate::integrations::kubectl::KubectlIntegration::default(),
+            ));
+        }
+        if config.integrations.ssh_enabled {
+            integrations.push(Arc::new(crate::integrations::ssh::SshIntegration::default()));
+        }
+        if config.integrations.vm_enabled {
+            integrations.push(Arc::new(crate::integrations::vm::VmIntegration::default()));
+        }
+        #[cfg(target_os = "macos")]
+        if config.integrations.brew_enabled {
+            integrations.push(Arc::new(crate::integrations::brew::BrewIntegration));
+        }
+        #[cfg(target_os = "windows")]
+        if config.integrations.windows_services_enabled {
+            integrations.push(Arc::new(
+                crate::integrations::windows_services::WindowsServicesIntegration,
+            ));
+        }
+        if let Some(url) = &config.integrations.supervisord_url {
+            match crate::integrations::supervisord::SupervisordIntegration::new(url) {
+                Ok(supervisord) => integrations.push(Arc::new(supervisord)),
+                Err(err) => log::warn!("supervisord integration disabled: {}", err),
+            }
+        }
+        for script in &config.integrations.scripts {
+            integrations.push(Arc::new(
+                crate::integrations::script::ScriptIntegration::new(script.clone()),
+            ));
+        }
+        Self {
+            integrations,
+            detect_tunnels: config.integrations.tunnel_detection_enabled,
+            proxy_configs: config
+                .integrations
+                .proxy_configs
+                .iter()
+                .map(|path| crate::utils::expand_home(path))
+                .collect(),
+        }
+    }
+
+    /// Whether the refresher should also look for public tunnels.
+    pub fn detects_tunnels(&self) -> bool {
+        self.detect_tunnels
+    }
+
+    /// Reverse-proxy configs the refresher reads routes from.
+    pub fn proxy_configs(&self) -> &[PathBuf] {
+        &self.proxy_configs
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Integration>> {
+        self.integrations.iter()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Arc<dyn Integration>> {
+        self.integrations.iter().find(|i| i.id() == id)
+    }
+}
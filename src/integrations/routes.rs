@@ -0,0 +1,533 @@
+//! Reverse-proxy route mapping (Caddy, nginx, Traefik)
+//!
+//! Local dev proxies put friendly hostnames (`api.localhost`) in front of dev
+//! servers. Reading their configs maps each upstream port back to the hostname
+//! and URL users actually open, without talking to the proxy itself.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde_json::Value;
+
+use crate::config_format::ConfigFormat;
+use crate::model::ProxyRoute;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConfigKind {
+    Caddy,
+    Nginx,
+    Traefik,
+}
+
+/// Every route to a local port found in the given files or directories.
+pub fn load_routes(paths: &[PathBuf]) -> Vec<ProxyRoute> {
+    let mut routes = Vec::new();
+    let mut nginx = NginxConfig::default();
+    for file in paths.iter().flat_map(|path| config_files(path)) {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(err) => {
+                debug!("skipping proxy config {}: {}", file.display(), err);
+                continue;
+            }
+        };
+        match config_kind(&file) {
+            ConfigKind::Caddy => routes.extend(parse_caddyfile(&text)),
+            ConfigKind::Traefik => routes.extend(parse_traefik(&text, ConfigFormat::of(&file))),
+            // Upstream blocks are often defined in a different file than the servers using them
+            ConfigKind::Nginx => nginx.parse(&text),
+        }
+    }
+    routes.extend(nginx.routes());
+    routes.sort_by(|a, b| (a.port, &a.hostname).cmp(&(b.port, &b.hostname)));
+    routes.dedup();
+    routes
+}
+
+/// A file, or the files directly inside a directory (e.g. nginx `servers/`).
+fn config_files(path: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file())
+                .collect();
+            files.sort();
+            files
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(_) => vec![path.to_path_buf()],
+    }
+}
+
+fn config_kind(path: &Path) -> ConfigKind {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if name.starts_with("caddyfile") || name.ends_with(".caddy") {
+        ConfigKind::Caddy
+    } else if name.ends_with(".yml") || name.ends_with(".yaml") || name.ends_with(".toml") {
+        ConfigKind::Traefik
+    } else {
+        ConfigKind::Nginx
+    }
+}
+
+/// Port of an upstream address on this machine: `:4001`, `localhost:4001`,
+/// `http://127.0.0.1:4001/api`, `[::1]:4001`.
+fn local_upstream_port(target: &str) -> Option<u16> {
+    let (scheme, rest) = match target.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, target),
+    };
+    let authority = rest.split('/').next()?;
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, after) = bracketed.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let is_local =
+        matches!(host, "" | "localhost" | "0.0.0.0" | "::1" | "::") || host.starts_with("127.");
+    if !is_local {
+        return None;
+    }
+    match (port, scheme) {
+        (Some(port), _) => port.parse().ok(),
+        (None, Some("https")) => Some(443),
+        (None, _) => Some(80),
+    }
+}
+
+fn route_url(https: bool, hostname: &str, port: Option<u16>) -> String {
+    let scheme = if https { "https" } else { "http" };
+    match port {
+        Some(port) if port != if https { 443 } else { 80 } => {
+            format!("{}://{}:{}", scheme, hostname, port)
+        }
+        _ => format!("{}://{}", scheme, hostname),
+    }
+}
+
+fn is_routable_hostname(hostname: &str) -> bool {
+    !hostname.is_empty()
+        && hostname != "_"
+        && !hostname.contains(['*', '~', '{', '$'])
+        && !hostname.starts_with('.')
+}
+
+// === Caddy ===
+
+/// Sites and their `reverse_proxy` upstreams:
+///
+/// ```text
+/// api.localhost, http://api.test {
+///     reverse_proxy /v1/* localhost:4001
+/// }
+/// ```
+fn parse_caddyfile(text: &str) -> Vec<ProxyRoute> {
+    let mut routes = Vec::new();
+    let mut depth = 0usize;
+    let mut sites: Vec<String> = Vec::new();
+    let mut seen_first_line = false;
+
+    for line in text.lines() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let opens = line.ends_with('{');
+        let closes = line.starts_with('}');
+        let first_line = !seen_first_line;
+        seen_first_line = true;
+
+        if closes {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                sites.clear();
+            }
+            continue;
+        }
+        if depth == 0 && opens {
+            let addresses = line.trim_end_matches('{').trim();
+            // Global options (`{`) and snippets (`(name) {`) aren't sites
+            sites = if addresses.is_empty() || addresses.starts_with('(') {
+                Vec::new()
+            } else {
+                split_caddy_addresses(addresses)
+            };
+        } else if depth == 0 && first_line && !line.contains(' ') {
+            // A single-site Caddyfile may omit the braces
+            sites = split_caddy_addresses(line);
+        } else if let Some(args) = line.strip_prefix("reverse_proxy ") {
+            let upstreams = args
+                .split_whitespace()
+                .take_while(|arg| *arg != "{")
+                .skip_while(|arg| arg.starts_with(['/', '@', '*']));
+            for port in upstreams.filter_map(local_upstream_port) {
+                for site in &sites {
+                    if let Some(route) = caddy_route(site, port) {
+                        routes.push(route);
+                    }
+                }
+            }
+        }
+        if opens {
+            depth += 1;
+        }
+    }
+    routes
+}
+
+fn split_caddy_addresses(addresses: &str) -> Vec<String> {
+    addresses
+        .split([',', ' ', '\t'])
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Caddy serves bare hostnames over HTTPS unless the address says otherwise.
+fn caddy_route(site: &str, port: u16) -> Option<ProxyRoute> {
+    let (scheme, rest) = match site.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, site),
+    };
+    let authority = rest.split('/').next()?;
+    let (hostname, site_port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port.parse::<u16>().ok()?)),
+        None => (authority, None),
+    };
+    if !is_routable_hostname(hostname) {
+        return None;
+    }
+    let https = match scheme {
+        Some(scheme) => scheme == "https",
+        None => site_port != Some(80),
+    };
+    Some(ProxyRoute {
+        hostname: hostname.to_string(),
+        port,
+        url: route_url(https, hostname, site_port),
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(0) => "",
+        Some(i) if line[..i].ends_with(char::is_whitespace) => &line[..i],
+        _ => line,
+    }
+}
+
+// === nginx ===
+
+#[derive(Default)]
+struct NginxConfig {
+    servers: Vec<NginxServer>,
+    upstreams: HashMap<String, Vec<u16>>,
+}
+
+#[derive(Default)]
+struct NginxServer {
+    names: Vec<String>,
+    https: bool,
+    listen_port: Option<u16>,
+    proxy_passes: Vec<String>,
+}
+
+impl NginxConfig {
+    /// `server { server_name …; listen …; location / { proxy_pass …; } }` and
+    /// `upstream name { server 127.0.0.1:4001; }` blocks.
+    fn parse(&mut self, text: &str) {
+        let mut blocks: Vec<String> = Vec::new();
+        let mut server: Option<NginxServer> = None;
+        let mut upstream: Option<String> = None;
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+
+        let source: String = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n");
+        for c in source.chars() {
+            match c {
+                ';' | '{' | '}' => {
+                    if !word.is_empty() {
+                        words.push(std::mem::take(&mut word));
+                    }
+                    let statement = std::mem::take(&mut words);
+                    match c {
+                        ';' => self.statement(&statement, &blocks, &mut server, &upstream),
+                        '{' => {
+                            let name = statement.first().cloned().unwrap_or_default();
+                            if name == "server" && blocks.last().is_none_or(|b| b == "http") {
+                                server = Some(NginxServer::default());
+                            } else if name == "upstream" {
+                                upstream = statement.get(1).cloned();
+                            }
+                            blocks.push(name);
+                        }
+                        _ => match blocks.pop().as_deref() {
+                            Some("server") if !blocks.iter().any(|b| b == "server") => {
+                                self.servers.extend(server.take());
+                            }
+                            Some("upstream") => upstream = None,
+                            _ => {}
+                        },
+                    }
+                }
+                c if c.is_whitespace() => {
+                    if !word.is_empty() {
+                        words.push(std::mem::take(&mut word));
+                    }
+                }
+                '"' | '\'' => {}
+                c => word.push(c),
+            }
+        }
+    }
+
+    fn statement(
+        &mut self,
+        words: &[String],
+        blocks: &[String],
+        server: &mut Option<NginxServer>,
+        upstream: &Option<String>,
+    ) {
+        let Some((directive, args)) = words.split_first() else {
+            return;
+        };
+        if blocks.last().map(String::as_str) == Some("upstream") {
+            if directive == "server"
+                && let Some(name) = upstream
+                && let Some(port) = args.first().and_then(|a| local_upstream_port(a))
+            {
+                self.upstreams.entry(name.clone()).or_default().push(port);
+            }
+            return;
+        }
+        let Some(server) = server else {
+            return;
+        };
+        match directive.as_str() {
+            "server_name" => server.names.extend(args.iter().cloned()),
+            "listen" => {
+                let Some(address) = args.first() else {
+                    return;
+                };
+                let port = address
+                    .rsplit(':')
+                    .next()
+                    .and_then(|p| p.parse::<u16>().ok());
+                let ssl = args.iter().any(|a| a == "ssl") || port == Some(443);
+                // Prefer the TLS listener when a server has both
+                if server.listen_port.is_none() || (ssl && !server.https) {
+                    server.listen_port = port;
+                    server.https = ssl;
+                }
+            }
+            "proxy_pass" => server.proxy_passes.extend(args.first().cloned()),
+            _ => {}
+        }
+    }
+
+    fn routes(&self) -> Vec<ProxyRoute> {
+        let mut routes = Vec::new();
+        for server in &self.servers {
+            let ports =
+                server
+                    .proxy_passes
+                    .iter()
+                    .flat_map(|target| match local_upstream_port(target) {
+                        Some(port) => vec![port],
+                        None => self.upstream_ports(target),
+                    });
+            for port in ports {
+                for name in server.names.iter().filter(|n| is_routable_hostname(n)) {
+                    routes.push(ProxyRoute {
+                        hostname: name.clone(),
+                        port,
+                        url: route_url(server.https, name, server.listen_port),
+                    });
+                }
+            }
+        }
+        routes
+    }
+
+    /// Ports of the `upstream` block named by `http://backend/path`.
+    fn upstream_ports(&self, target: &str) -> Vec<u16> {
+        let rest = target.split_once("://").map_or(target, |(_, rest)| rest);
+        let name = rest.split(['/', ':']).next().unwrap_or(rest);
+        self.upstreams.get(name).cloned().unwrap_or_default()
+    }
+}
+
+// === Traefik ===
+
+/// Routers and load-balanced services from a Traefik dynamic config file,
+/// in either YAML or TOML form.
+fn parse_traefik(text: &str, format: ConfigFormat) -> Vec<ProxyRoute> {
+    let config = match format.parse(text) {
+        Ok(config) => config,
+        Err(err) => {
+            debug!("skipping Traefik config: {}", err);
+            return Vec::new();
+        }
+    };
+    let http = &config["http"];
+
+    let services: HashMap<&str, Vec<u16>> = http["services"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, service)| {
+            let ports = service["loadBalancer"]["servers"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|server| local_upstream_port(server["url"].as_str()?))
+                .collect();
+            (name.as_str(), ports)
+        })
+        .collect();
+
+    let mut routes = Vec::new();
+    for router in http["routers"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(_, r)| r)
+    {
+        let Some(service) = router["service"].as_str() else {
+            continue;
+        };
+        let Some(ports) = services.get(service.split('@').next().unwrap_or(service)) else {
+            continue;
+        };
+        // `tls: {}` is enough to serve the router over HTTPS
+        let https = !matches!(router["tls"], Value::Null | Value::Bool(false));
+        let hosts = router["rule"].as_str().map(rule_hosts).unwrap_or_default();
+        for port in ports {
+            for host in hosts.iter().filter(|h| is_routable_hostname(h)) {
+                routes.push(ProxyRoute {
+                    hostname: host.clone(),
+                    port: *port,
+                    url: route_url(https, host, None),
+                });
+            }
+        }
+    }
+    routes
+}
+
+/// Hostnames in a rule like ``Host(`api.localhost`) || Host(`a.test`, `b.test`)``.
+fn rule_hosts(rule: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    let mut rest = rule;
+    while let Some(start) = rest.find("Host(") {
+        let args = &rest[start + "Host(".len()..];
+        let end = args.find(')').unwrap_or(args.len());
+        hosts.extend(
+            args[..end]
+                .split(',')
+                .map(|h| h.trim().trim_matches(['`', '"', '\'']).to_string())
+                .filter(|h| !h.is_empty()),
+        );
+        rest = &args[end..];
+    }
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(routes: &[ProxyRoute]) -> Vec<(u16, &str, &str)> {
+        let mut pairs: Vec<_> = routes
+            .iter()
+            .map(|r| (r.port, r.hostname.as_str(), r.url.as_str()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn parses_caddyfile_sites() {
+        let routes = parse_caddyfile(
+            "{\n    local_certs\n}\n\n\
+             api.localhost, http://api.test {\n    reverse_proxy /v1/* localhost:4001 # api\n}\n\
+             web.localhost:8443 {\n    handle {\n        reverse_proxy :3000\n    }\n}\n\
+             docs.localhost {\n    reverse_proxy https://example.com:443\n}\n",
+        );
+        assert_eq!(
+            pairs(&routes),
+            vec![
+                (3000, "web.localhost", "https://web.localhost:8443"),
+                (4001, "api.localhost", "https://api.localhost"),
+                (4001, "api.test", "http://api.test"),
+            ]
+        );
+        let bare = parse_caddyfile("shop.localhost\nreverse_proxy 127.0.0.1:5173\n");
+        assert_eq!(
+            pairs(&bare),
+            vec![(5173, "shop.localhost", "https://shop.localhost")]
+        );
+    }
+
+    #[test]
+    fn parses_nginx_servers_and_upstreams() {
+        let mut config = NginxConfig::default();
+        config.parse(
+            "upstream backend {\n    server 127.0.0.1:4001;\n}\n\
+             server {\n    listen 80;\n    server_name api.localhost _;\n\
+                 location / { proxy_pass http://backend; }\n}\n",
+        );
+        config.parse(
+            "server {\n    listen 8443 ssl;\n    server_name app.localhost;\n\
+                 location /api/ { proxy_pass http://localhost:3000/; } # dev\n}\n",
+        );
+        assert_eq!(
+            pairs(&config.routes()),
+            vec![
+                (3000, "app.localhost", "https://app.localhost:8443"),
+                (4001, "api.localhost", "http://api.localhost"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_traefik_yaml_and_toml() {
+        let yaml = parse_traefik(
+            "http:\n  routers:\n    api:\n      rule: \"Host(`api.localhost`) || Host(`a.test`)\"\n\
+             \x20     service: api@file\n      tls: {}\n  services:\n    api:\n      loadBalancer:\n\
+             \x20       servers:\n          - url: \"http://127.0.0.1:4001\"\n",
+            ConfigFormat::Yaml,
+        );
+        assert_eq!(
+            pairs(&yaml),
+            vec![
+                (4001, "a.test", "https://a.test"),
+                (4001, "api.localhost", "https://api.localhost"),
+            ]
+        );
+        let toml = parse_traefik(
+            "[http.routers.web]\n  rule = \"Host(`web.localhost`)\"\n  service = \"web\"\n\n\
+             [[http.services.web.loadBalancer.servers]]\n  url = \"http://localhost:3000/\"\n",
+            ConfigFormat::Toml,
+        );
+        assert_eq!(
+            pairs(&toml),
+            vec![(3000, "web.localhost", "http://web.localhost")]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! User-defined integrations backed by external commands
+//!
+//! The `list` command prints a JSON array such as
+//! `[{"name": "web", "ports": [8080], "status": "running"}]`; listeners on those
+//! ports are grouped under the integration's own menu section. The `stop`
+//! command receives the unit name as its last argument, or in place of `{name}`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use log::warn;
+use serde::Deserialize;
+
+use crate::config::ScriptIntegrationConfig;
+use crate::integrations::registry::Integration;
+use crate::model::{KillFeedback, ManagedUnit, ProcessInfo};
+use crate::utils::{find_command, hidden_command, output_with_timeout};
+
+const NAME_PLACEHOLDER: &str = "{name}";
+
+pub struct ScriptIntegration {
+    config: ScriptIntegrationConfig,
+}
+
+#[derive(Deserialize)]
+struct ScriptUnit {
+    name: String,
+    #[serde(default)]
+    ports: Vec<u16>,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+impl ScriptIntegration {
+    pub fn new(config: ScriptIntegrationConfig) -> Self {
+        Self { config }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_secs)
+    }
+}
+
+impl Integration for ScriptIntegration {
+    fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    fn display_name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn section_title(&self) -> &str {
+        &self.config.name
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let (program, args) = self
+            .config
+            .list
+            .split_first()
+            .ok_or_else(|| anyhow!("empty list command"))?;
+        let out = output_with_timeout(hidden_command(resolve(program)).args(args), self.timeout())
+            .with_context(|| format!("{} failed", program))?;
+        if !out.status.success() {
+            return Err(anyhow!(
+                "{} exited with {}: {}",
+                program,
+                out.status,
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+        }
+        parse_script_list(&String::from_utf8_lossy(&out.stdout))
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.ports.contains(&process.port))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        let Some((program, args)) = self.config.stop.split_first() else {
+            return KillFeedback::error(format!("{}: empty stop command", self.config.name));
+        };
+        let mut command = hidden_command(resolve(program));
+        if args.iter().any(|a| a.contains(NAME_PLACEHOLDER)) {
+            command.args(args.iter().map(|a| a.replace(NAME_PLACEHOLDER, unit)));
+        } else {
+            command.args(args).arg(unit);
+        }
+        match output_with_timeout(&mut command, self.timeout()) {
+            Ok(out) if out.status.success() => {
+                KillFeedback::info(format!("Stopped {} {}.", self.config.name, unit))
+            }
+            Ok(out) => KillFeedback::error(format!(
+                "Failed to stop {} {}: {}",
+                self.config.name,
+                unit,
+                String::from_utf8_lossy(&out.stderr).trim()
+            )),
+            Err(err) => KillFeedback::error(format!("{} stop error: {}", self.config.name, err)),
+        }
+    }
+}
+
+/// Resolve a bare program name like the built-in integrations do, since a
+/// session launched from the GUI has a minimal PATH.
+fn resolve(program: &str) -> &str {
+    if program.contains(['/', '\\']) {
+        program
+    } else {
+        find_command(program)
+    }
+}
+
+/// Parse the JSON printed by a `list` command. Units whose names can't round-trip
+/// through a menu id are skipped so a stop can never target the wrong unit.
+fn parse_script_list(stdout: &str) -> Result<Vec<ManagedUnit>> {
+    let units: Vec<ScriptUnit> =
+        serde_json::from_str(stdout).context("list command did not print a JSON array")?;
+    Ok(units
+        .into_iter()
+        .filter(|unit| {
+            let valid = !unit.name.is_empty()
+                && unit
+                    .name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                warn!("Skipping script unit with unsupported name {:?}", unit.name);
+            }
+            valid
+        })
+        .map(|unit| ManagedUnit {
+            name: unit.name,
+            status: unit.status.unwrap_or_else(|| "running".to_string()),
+            ports: unit.ports,
+            pids: Vec::new(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units_and_skips_bad_names() {
+        let units = parse_script_list(
+            r#"[
+                {"name": "web", "ports": [8080, 8443], "status": "running"},
+                {"name": "db"},
+                {"name": "bad name", "ports": [9000]}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].name, "web");
+        assert_eq!(units[0].ports, vec![8080, 8443]);
+        assert_eq!(units[1].status, "running");
+        assert!(units[1].ports.is_empty());
+    }
+
+    #[test]
+    fn rejects_non_array_output() {
+        assert!(parse_script_list("not json").is_err());
+        assert!(parse_script_list(r#"{"name": "web"}"#).is_err());
+    }
+}
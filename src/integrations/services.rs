@@ -0,0 +1,432 @@
+//! Dev servers declared in the config and launched by PortKiller
+//!
+//! Each `services` entry is listed under "Services" whether or not it is
+//! running. Children started from the menu run detached with their output in
+//! `<log dir>/<name>.log`; listeners on a service's ports (or owned by
+//! the child it started) are grouped under it. The launched children are kept
+//! in a process-wide table so they survive config reloads rebuilding the registry.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::config::{ReadinessProbe, ServiceConfig, ensure_log_dir, log_dir};
+use crate::integrations::registry::Integration;
+use crate::model::{FeedbackSender, KillFeedback, KillOutcome, ManagedUnit, ProcessInfo};
+use crate::port_set::PortSet;
+use crate::probe::{http_get, parse_status_code};
+use crate::process::kill::{ProcessTree, terminate_pid};
+use crate::process::ports::scan_ports;
+use crate::restart::{PORT_RELEASE_TIMEOUT, port_in_use, wait_for_ports_free};
+use crate::utils::{detached_command, expand_home, find_command};
+
+pub const SERVICES_ID: &str = "services";
+
+const POLL_STEP: Duration = Duration::from_millis(200);
+/// How long a stopped child gets to exit on its own once its listeners are gone.
+const CHILD_EXIT_GRACE: Duration = Duration::from_secs(3);
+
+const ACTIONS: &[(&str, &str)] = &[("start", "Start"), ("restart", "Restart")];
+
+/// A child PortKiller launched, and how it ended once it has.
+struct Supervised {
+    child: Child,
+    /// The child and whatever it spawned, such as the server behind `npm run dev`
+    tree: ProcessTree,
+    exited: Option<String>,
+}
+
+fn supervised() -> MutexGuard<'static, HashMap<String, Supervised>> {
+    static CHILDREN: OnceLock<Mutex<HashMap<String, Supervised>>> = OnceLock::new();
+    CHILDREN
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub struct ServicesIntegration {
+    services: Vec<ServiceConfig>,
+}
+
+impl ServicesIntegration {
+    pub fn new(services: Vec<ServiceConfig>) -> Self {
+        Self { services }
+    }
+
+    fn service(&self, name: &str) -> Option<&ServiceConfig> {
+        self.services.iter().find(|s| s.name == name)
+    }
+
+    /// PID of the child we launched for `name`, if it is still running.
+    fn running_child(name: &str) -> Option<i32> {
+        let mut children = supervised();
+        let entry = children.get_mut(name)?;
+        reap(entry);
+        entry.exited.is_none().then_some(entry.child.id() as i32)
+    }
+
+    /// Launch the service and return; readiness is awaited on its own thread
+    /// and reported through `later`.
+    fn start(&self, service: &ServiceConfig, later: &FeedbackSender) -> Result<String> {
+        if let Some(pid) = Self::running_child(&service.name) {
+            bail!("already running (PID {})", pid);
+        }
+        if let Some(&port) = service.ports.iter().find(|&&port| port_in_use(port)) {
+            bail!("port {} is already in use", port);
+        }
+
+        ensure_log_dir()?;
+        let log_path = log_path(&service.name);
+        let log = File::create(&log_path)
+            .with_context(|| format!("failed to create {}", log_path.display()))?;
+
+        let (program, args) = service
+            .command
+            .split_first()
+            .ok_or_else(|| anyhow!("empty command"))?;
+        // GUI apps on macOS don't inherit the shell PATH, so resolve bare names
+        let program = if program.contains(['/', '\\']) {
+            program.as_str()
+        } else {
+            find_command(program)
+        };
+        let mut cmd = detached_command(program);
+        cmd.args(args)
+            .envs(&service.env)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        if let Some(cwd) = &service.cwd {
+            cmd.current_dir(expand_home(cwd));
+        }
+        let child = cmd
+            .spawn()
+            .with_context(|| format!("failed to start {}", program))?;
+        let pid = child.id() as i32;
+        supervised().insert(
+            service.name.clone(),
+            Supervised {
+                tree: ProcessTree::of(&child),
+                child,
+                exited: None,
+            },
+        );
+
+        let probe = service.ready.clone().or_else(|| {
+            service
+                .ports
+                .first()
+                .map(|&port| ReadinessProbe::Tcp { port })
+        });
+        let Some(probe) = probe else {
+            return Ok(format!("Started {} (PID {}).", service.name, pid));
+        };
+        let name = service.name.clone();
+        let timeout = Duration::from_secs(service.ready_timeout_secs);
+        let later = later.clone();
+        thread::spawn(move || {
+            later.send(match wait_until_ready(&name, &probe, &log_path, timeout) {
+                Ok(()) => KillFeedback::info(format!("{} is ready (PID {}).", name, pid)),
+                Err(err) => KillFeedback::error(format!("{}: {}", name, err)),
+            });
+        });
+        Ok(format!("Starting {} (PID {})…", service.name, pid))
+    }
+
+    fn restart(&self, service: &ServiceConfig, later: &FeedbackSender) -> Result<String> {
+        if Self::running_child(&service.name).is_some()
+            || service.ports.iter().any(|&port| port_in_use(port))
+        {
+            self.stop_service(service)?;
+            wait_for_ports_free(&service.ports, PORT_RELEASE_TIMEOUT)?;
+        }
+        self.start(service, later)
+    }
+
+    fn stop_service(&self, service: &ServiceConfig) -> Result<String> {
+        let child = Self::running_child(&service.name);
+
+        // Stop the listeners first so servers get a graceful shutdown, then the
+        // child we launched (often a wrapper such as `npm run dev`)
+        let ranges: Vec<(u16, u16)> = service.ports.iter().map(|&p| (p, p)).collect();
+        let mut listeners: Vec<i32> = if ranges.is_empty() {
+            Vec::new()
+        } else {
+            scan_ports(&PortSet::from_ranges(&ranges))?
+                .into_iter()
+                .map(|p| p.pid)
+                .collect()
+        };
+        listeners.sort();
+        listeners.dedup();
+        if child.is_none() && listeners.is_empty() {
+            bail!("not running");
+        }
+        for pid in &listeners {
+            match terminate_pid(*pid) {
+                KillOutcome::Success | KillOutcome::AlreadyExited => {}
+                KillOutcome::PermissionDenied => bail!("permission denied stopping PID {}", pid),
+                KillOutcome::TimedOut => bail!("timed out stopping PID {}", pid),
+                KillOutcome::Failed(err) => bail!("could not stop PID {}: {}", pid, err),
+            }
+        }
+
+        if child.is_some() {
+            if let Some(entry) = supervised().get(&service.name) {
+                entry.tree.terminate();
+            }
+            let deadline = Instant::now() + CHILD_EXIT_GRACE;
+            while Self::running_child(&service.name).is_some() && Instant::now() < deadline {
+                thread::sleep(POLL_STEP);
+            }
+            // Forget the child once it's gone so the menu shows "stopped"
+            // rather than the exit status of the kill
+            let mut children = supervised();
+            if let Some(mut entry) = children.remove(&service.name) {
+                // Also catches children that outlived the wrapper
+                entry.tree.kill();
+                let _ = entry.child.wait();
+            }
+        }
+        Ok(format!("Stopped {}.", service.name))
+    }
+}
+
+impl Integration for ServicesIntegration {
+    fn id(&self) -> &str {
+        SERVICES_ID
+    }
+
+    fn display_name(&self) -> &str {
+        "Services"
+    }
+
+    fn section_title(&self) -> &str {
+        "Services"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let mut children = supervised();
+        Ok(self
+            .services
+            .iter()
+            .map(|service| {
+                let entry = children.get_mut(&service.name);
+                let (status, pids) = match entry {
+                    Some(entry) => {
+                        reap(entry);
+                        match &entry.exited {
+                            None => ("running".to_string(), vec![entry.child.id() as i32]),
+                            Some(exit) => (exit.clone(), Vec::new()),
+                        }
+                    }
+                    None => ("stopped".to_string(), Vec::new()),
+                };
+                ManagedUnit {
+                    name: service.name.clone(),
+                    status,
+                    ports: service.ports.clone(),
+                    pids,
+                }
+            })
+            .collect())
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.ports.contains(&process.port) || unit.pids.contains(&process.pid))
+            .map(|unit| unit.name.clone())
+    }
+
+    /// "ports · name" while listening, otherwise "name (running|exited 1|stopped)".
+    fn unit_menu_label(&self, unit: &str, ports: &[u16]) -> String {
+        if !ports.is_empty() {
+            let ports_str = ports
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return format!("{} · {}", ports_str, unit);
+        }
+        let mut children = supervised();
+        let state = match children.get_mut(unit) {
+            Some(entry) => {
+                reap(entry);
+                entry
+                    .exited
+                    .clone()
+                    .unwrap_or_else(|| "running".to_string())
+            }
+            None => "stopped".to_string(),
+        };
+        format!("{} ({})", unit, state)
+    }
+
+    fn lists_idle_units(&self) -> bool {
+        true
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        let Some(service) = self.service(unit) else {
+            return KillFeedback::error(format!("Unknown service {}.", unit));
+        };
+        match self.stop_service(service) {
+            Ok(message) => KillFeedback::info(message),
+            Err(err) => KillFeedback::error(format!("Failed to stop {}: {}", unit, err)),
+        }
+    }
+
+    fn unit_actions(&self, _unit: &str) -> &[(&'static str, &'static str)] {
+        ACTIONS
+    }
+
+    fn run_action(&self, action: &str, unit: &str, later: &FeedbackSender) -> KillFeedback {
+        let Some(service) = self.service(unit) else {
+            return KillFeedback::error(format!("Unknown service {}.", unit));
+        };
+        let result = match action {
+            "start" => self.start(service, later),
+            "restart" => self.restart(service, later),
+            _ => Err(anyhow!("unsupported action '{}'", action)),
+        };
+        match result {
+            Ok(message) => KillFeedback::info(message),
+            Err(err) => KillFeedback::error(format!("Failed to {} {}: {}", action, unit, err)),
+        }
+    }
+}
+
+/// Poll `probe` until it passes, the child exits or `timeout` runs out.
+fn wait_until_ready(
+    name: &str,
+    probe: &ReadinessProbe,
+    log_path: &Path,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if ServicesIntegration::running_child(name).is_none() {
+            bail!("exited during startup: {}", last_log_line(log_path));
+        }
+        if probe_ready(probe, log_path) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "started but not ready after {}s; see {}",
+                timeout.as_secs(),
+                log_path.display()
+            );
+        }
+        thread::sleep(POLL_STEP);
+    }
+}
+
+/// Record the exit status of a child that has finished.
+fn reap(entry: &mut Supervised) {
+    if entry.exited.is_none()
+        && let Ok(Some(status)) = entry.child.try_wait()
+    {
+        entry.exited = Some(match status.code() {
+            Some(0) => "exited".to_string(),
+            Some(code) => format!("exited {}", code),
+            None => "killed".to_string(),
+        });
+    }
+}
+
+/// Where a service's output goes: `<log dir>/<name>.log`.
+pub fn log_path(name: &str) -> PathBuf {
+    log_dir().join(format!("{}.log", name))
+}
+
+fn last_log_line(path: &Path) -> String {
+    let mut output = String::new();
+    if let Ok(mut file) = File::open(path) {
+        let _ = file.read_to_string(&mut output);
+    }
+    output
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("no output")
+        .to_string()
+}
+
+fn probe_ready(probe: &ReadinessProbe, log_path: &Path) -> bool {
+    match probe {
+        ReadinessProbe::Tcp { port } => port_in_use(*port),
+        ReadinessProbe::Http { url } => http_responds(url),
+        ReadinessProbe::Log { pattern } => std::fs::read(log_path)
+            .is_ok_and(|log| String::from_utf8_lossy(&log).contains(pattern.as_str())),
+    }
+}
+
+/// Ready once the server answers a plain HTTP `GET` with a non-5xx status.
+fn http_responds(url: &str) -> bool {
+    let Some((host, path)) = split_http_url(url) else {
+        return false;
+    };
+    let timeout = Duration::from_secs(1);
+    let Some(addr) = host
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+    else {
+        return false;
+    };
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, timeout) else {
+        return false;
+    };
+    http_get(&mut stream, &host, &path, timeout).is_some_and(|response| {
+        parse_status_code(&String::from_utf8_lossy(&response.bytes)).is_some_and(|code| code < 500)
+    })
+}
+
+/// `http://host[:port][/path]` → (`host:port`, `/path`).
+fn split_http_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    let host = if authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+    {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    Some((host, path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_readiness_urls() {
+        assert_eq!(
+            split_http_url("http://localhost:3000/health"),
+            Some(("localhost:3000".to_string(), "/health".to_string()))
+        );
+        assert_eq!(
+            split_http_url("http://api.localhost"),
+            Some(("api.localhost:80".to_string(), "/".to_string()))
+        );
+        assert_eq!(split_http_url("https://localhost"), None);
+    }
+}
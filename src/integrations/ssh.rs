@@ -0,0 +1,497 @@
+//! SSH tunnel integration
+//!
+//! `ssh -L 5432:db.internal:5432 bastion` holds a dev port, but killing the
+//! client can also end an interactive session. Listeners owned by an ssh client
+//! are grouped per forward and labelled with their destination; a single forward
+//! can be cancelled through the ControlMaster socket with `ssh -O cancel`.
+//! ControlMaster clients are picked up without forwards on their command line,
+//! since `ssh -O forward` can add them later.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::integrations::registry::Integration;
+use crate::model::{FeedbackSender, KillFeedback, KillOutcome, ManagedUnit, ProcessInfo};
+use crate::process::kill::terminate_pid;
+use crate::process::ports::command_lines_for;
+use crate::utils::{hidden_command, output_with_timeout};
+
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Options from OpenSSH's getopt string that take an argument
+const VALUE_OPTIONS: &str = "bceilmopBDEFIJLOPQRSwW";
+// Options worth passing along to `ssh -O` so it finds the same control socket
+const CONTROL_OPTIONS: &[char] = &['F', 'S', 'o', 'p', 'l', 'J'];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForwardKind {
+    Local,
+    Dynamic,
+    Remote,
+}
+
+impl ForwardKind {
+    fn flag(self) -> &'static str {
+        match self {
+            ForwardKind::Local => "-L",
+            ForwardKind::Dynamic => "-D",
+            ForwardKind::Remote => "-R",
+        }
+    }
+}
+
+/// One `-L`, `-D` or `-R` spec.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SshForward {
+    pub kind: ForwardKind,
+    /// The spec exactly as given, needed verbatim by `ssh -O cancel`
+    pub spec: String,
+    /// Port listened on (locally for -L/-D, on the server for -R)
+    pub listen_port: Option<u16>,
+    /// `host:port` (or socket path) the forward connects to; `None` for -D
+    pub target: Option<String>,
+}
+
+/// A parsed ssh client command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SshSession {
+    pub destination: String,
+    pub forwards: Vec<SshForward>,
+    /// No -N/-f/-W and no remote command: killing it ends someone's shell
+    pub interactive: bool,
+    /// Options (`-F`, `-S`, `-o`, ...) that locate the ControlMaster socket
+    control_args: Vec<String>,
+    program: String,
+}
+
+impl SshSession {
+    fn local_forwards(&self) -> impl Iterator<Item = &SshForward> {
+        self.forwards
+            .iter()
+            .filter(|f| f.kind != ForwardKind::Remote && f.listen_port.is_some())
+    }
+}
+
+struct TunnelUnit {
+    pid: i32,
+    session: SshSession,
+    /// `None` for listeners of this client that no spec on its command line explains,
+    /// e.g. forwards added later with `ssh -O forward`
+    forward: Option<SshForward>,
+}
+
+impl TunnelUnit {
+    fn label(&self) -> String {
+        let via = format!("via {}", self.session.destination);
+        match &self.forward {
+            Some(SshForward {
+                kind: ForwardKind::Dynamic,
+                listen_port: Some(port),
+                ..
+            }) => format!("{} SOCKS {}", port, via),
+            Some(SshForward {
+                listen_port: Some(port),
+                target: Some(target),
+                ..
+            }) => format!("{} → {} {}", port, target, via),
+            _ => format!("ssh tunnel {} (multiplexed)", via),
+        }
+    }
+}
+
+/// ssh clients with forwards or a ControlMaster, one unit per local forward plus
+/// one for the client's other listeners, claimed by PID and port.
+#[derive(Default)]
+pub struct SshIntegration {
+    tunnels: Mutex<BTreeMap<String, TunnelUnit>>,
+}
+
+impl Integration for SshIntegration {
+    fn id(&self) -> &str {
+        "ssh"
+    }
+
+    fn display_name(&self) -> &str {
+        "SSH"
+    }
+
+    fn section_title(&self) -> &str {
+        "SSH Tunnels"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Close All Tunnels"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let mut tunnels = BTreeMap::new();
+        for (pid, argv) in command_lines_for("ssh")? {
+            let Some(session) = parse_ssh_command(&argv) else {
+                continue;
+            };
+            for forward in session.local_forwards() {
+                let port = forward.listen_port.unwrap_or_default();
+                tunnels.insert(
+                    format!("{}-{}", pid, port),
+                    TunnelUnit {
+                        pid,
+                        session: session.clone(),
+                        forward: Some(forward.clone()),
+                    },
+                );
+            }
+            // Catch-all for this client's other listeners
+            tunnels.insert(
+                pid.to_string(),
+                TunnelUnit {
+                    pid,
+                    session,
+                    forward: None,
+                },
+            );
+        }
+
+        let units = tunnels
+            .iter()
+            .map(|(name, tunnel)| ManagedUnit {
+                name: name.clone(),
+                status: "running".to_string(),
+                ports: tunnel
+                    .forward
+                    .as_ref()
+                    .and_then(|f| f.listen_port)
+                    .into_iter()
+                    .collect(),
+                pids: vec![tunnel.pid],
+            })
+            .collect();
+        *self.tunnels.lock().unwrap_or_else(|e| e.into_inner()) = tunnels;
+        Ok(units)
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        let mine = || units.iter().filter(|unit| unit.pids.contains(&process.pid));
+        mine()
+            .find(|unit| unit.ports.contains(&process.port))
+            .or_else(|| mine().find(|unit| unit.ports.is_empty()))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn unit_label(&self, unit: &str) -> String {
+        let tunnels = self.tunnels.lock().unwrap_or_else(|e| e.into_inner());
+        tunnels
+            .get(unit)
+            .map(TunnelUnit::label)
+            .unwrap_or_else(|| unit.to_string())
+    }
+
+    fn unit_menu_label(&self, unit: &str, ports: &[u16]) -> String {
+        let label = self.unit_label(unit);
+        if label.starts_with(|c: char| c.is_ascii_digit()) || ports.is_empty() {
+            return label;
+        }
+        let ports_str = ports
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} · {}", ports_str, label)
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        let Some((pid, destination, interactive)) = self.with_tunnel(unit, |t| {
+            (t.pid, t.session.destination.clone(), t.session.interactive)
+        }) else {
+            return KillFeedback::warning(format!("SSH tunnel {} is no longer running.", unit));
+        };
+        if interactive {
+            return KillFeedback::warning(format!(
+                "ssh {} (PID {}) is an interactive session; close it from its terminal or cancel the forward instead.",
+                destination, pid
+            ));
+        }
+        match terminate_pid(pid) {
+            KillOutcome::Success | KillOutcome::AlreadyExited => KillFeedback::info(format!(
+                "Closed ssh {} (PID {}) and all its forwards.",
+                destination, pid
+            )),
+            outcome => KillFeedback::error(format!(
+                "Failed to close ssh {} (PID {}): {:?}.",
+                destination, pid, outcome
+            )),
+        }
+    }
+
+    fn unit_actions(&self, unit: &str) -> &[(&'static str, &'static str)] {
+        // Only a forward spelled out on the command line can be named to `ssh -O cancel`
+        if self.with_tunnel(unit, |t| t.forward.is_some()) == Some(true) {
+            &[("cancel", "Cancel forward")]
+        } else {
+            &[]
+        }
+    }
+
+    fn run_action(&self, action: &str, unit: &str, _later: &FeedbackSender) -> KillFeedback {
+        if action != "cancel" {
+            return KillFeedback::error(format!("Unknown SSH action '{}'.", action));
+        }
+        let Some((session, forward)) =
+            self.with_tunnel(unit, |t| (t.session.clone(), t.forward.clone()))
+        else {
+            return KillFeedback::warning(format!("SSH tunnel {} is no longer running.", unit));
+        };
+        let Some(forward) = forward else {
+            return KillFeedback::error(format!(
+                "The forwards of ssh {} aren't on its command line; cancel them with ssh -O cancel.",
+                session.destination
+            ));
+        };
+
+        let mut command = hidden_command(&session.program);
+        command
+            .args(&session.control_args)
+            .args(["-O", "cancel", forward.kind.flag(), &forward.spec])
+            .arg(&session.destination);
+        match output_with_timeout(&mut command, CANCEL_TIMEOUT) {
+            Ok(out) if out.status.success() => KillFeedback::info(format!(
+                "Cancelled forward {} {} via {}.",
+                forward.kind.flag(),
+                forward.spec,
+                session.destination
+            )),
+            // Without a ControlMaster ssh reports "Control socket connect(...)" or
+            // "No ControlPath specified"
+            Ok(out) => KillFeedback::error(format!(
+                "Cannot cancel {} {}: {}",
+                forward.kind.flag(),
+                forward.spec,
+                String::from_utf8_lossy(&out.stderr).trim()
+            )),
+            Err(err) => KillFeedback::error(format!("ssh -O cancel error: {}", err)),
+        }
+    }
+}
+
+impl SshIntegration {
+    fn with_tunnel<T>(&self, unit: &str, f: impl FnOnce(&TunnelUnit) -> T) -> Option<T> {
+        let tunnels = self.tunnels.lock().unwrap_or_else(|e| e.into_inner());
+        tunnels.get(unit).map(f)
+    }
+}
+
+/// Parse an ssh client command line. Returns `None` for clients that have no
+/// forwards and aren't a ControlMaster.
+pub fn parse_ssh_command(argv: &[String]) -> Option<SshSession> {
+    let program = argv.first()?;
+    let name = program.rsplit(['/', '\\']).next()?;
+    if name != "ssh" && name != "ssh.exe" {
+        return None;
+    }
+
+    let mut forwards = Vec::new();
+    let mut control_args = Vec::new();
+    let mut destination: Option<String> = None;
+    let mut has_remote_command = false;
+    let mut backgrounded = false;
+    let mut control_master = false;
+    let mut args = argv[1..].iter();
+    while let Some(arg) = args.next() {
+        // ssh keeps parsing options after the destination; the first other word
+        // starts the remote command
+        if arg == "--" {
+            if destination.is_none() {
+                destination = args.next().cloned();
+            }
+            has_remote_command = args.len() > 0;
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            if destination.is_none() {
+                destination = Some(arg.clone());
+                continue;
+            }
+            has_remote_command = true;
+            break;
+        }
+        // Clustered options: `-fNL 8080:localhost:80` or `-L8080:localhost:80`
+        let cluster = &arg[1..];
+        for (idx, opt) in cluster.char_indices() {
+            if !VALUE_OPTIONS.contains(opt) {
+                match opt {
+                    'N' | 'f' => backgrounded = true,
+                    'M' => control_master = true,
+                    _ => {}
+                }
+                continue;
+            }
+            let rest = &cluster[idx + opt.len_utf8()..];
+            let value = if rest.is_empty() {
+                args.next()?.clone()
+            } else {
+                rest.to_string()
+            };
+            match opt {
+                'L' => forwards.extend(parse_forward(ForwardKind::Local, &value)),
+                'D' => forwards.extend(parse_forward(ForwardKind::Dynamic, &value)),
+                'R' => forwards.extend(parse_forward(ForwardKind::Remote, &value)),
+                'W' => backgrounded = true,
+                'S' => control_master |= !value.eq_ignore_ascii_case("none"),
+                'o' => control_master |= is_control_option(&value),
+                _ => {}
+            }
+            if CONTROL_OPTIONS.contains(&opt) {
+                control_args.push(format!("-{}", opt));
+                control_args.push(value);
+            }
+            break;
+        }
+    }
+
+    if forwards.is_empty() && !control_master {
+        return None;
+    }
+    Some(SshSession {
+        destination: destination?,
+        forwards,
+        interactive: !backgrounded && !has_remote_command,
+        control_args,
+        program: program.clone(),
+    })
+}
+
+/// `-o ControlMaster=auto` or `-o ControlPath=~/.ssh/cm-%C`; `no`/`none` turn them off.
+fn is_control_option(option: &str) -> bool {
+    let Some((key, value)) = option
+        .split_once('=')
+        .or_else(|| option.split_once(char::is_whitespace))
+    else {
+        return false;
+    };
+    let value = value.trim();
+    match key.trim().to_ascii_lowercase().as_str() {
+        "controlmaster" => !value.eq_ignore_ascii_case("no"),
+        "controlpath" => !value.eq_ignore_ascii_case("none"),
+        _ => false,
+    }
+}
+
+/// Parse a forward spec such as `5432:db.internal:5432`, `127.0.0.1:1080`,
+/// or `[::1]:8080:[fd00::2]:80`. Unix-socket listeners yield no port.
+fn parse_forward(kind: ForwardKind, spec: &str) -> Option<SshForward> {
+    let parts = split_spec(spec);
+    let port = |s: &str| s.parse::<u16>().ok();
+    let (listen_port, target) = match (kind, parts.as_slice()) {
+        (ForwardKind::Dynamic, [port_str]) | (ForwardKind::Dynamic, [_, port_str]) => {
+            (port(port_str), None)
+        }
+        (ForwardKind::Dynamic, _) => return None,
+        // [bind:]port:host:hostport
+        (_, [_, listen, host, host_port]) | (_, [listen, host, host_port]) => {
+            let host = if host.contains(':') {
+                format!("[{}]", host)
+            } else {
+                host.to_string()
+            };
+            (port(listen), Some(format!("{}:{}", host, host_port)))
+        }
+        // port:/remote/socket, or -R [bind:]port for a remote SOCKS proxy
+        (_, [listen, target]) => (
+            port(listen).or_else(|| port(target)),
+            port(listen).map(|_| target.to_string()),
+        ),
+        (ForwardKind::Remote, [listen]) => (port(listen), None),
+        _ => return None,
+    };
+    Some(SshForward {
+        kind,
+        spec: spec.to_string(),
+        listen_port,
+        target,
+    })
+}
+
+/// Split on `:` outside of `[...]`, stripping the brackets.
+fn split_spec(spec: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_brackets = false;
+    for ch in spec.chars() {
+        match ch {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ':' if !in_brackets => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_clustered_background_tunnel() {
+        let session = parse_ssh_command(&argv(
+            "/usr/bin/ssh -fNL 5432:db.internal:5432 -D1080 -S /tmp/cm-bastion bastion",
+        ))
+        .unwrap();
+        assert_eq!(session.destination, "bastion");
+        assert!(!session.interactive);
+        assert_eq!(session.control_args, vec!["-S", "/tmp/cm-bastion"]);
+        assert_eq!(session.forwards.len(), 2);
+        assert_eq!(session.forwards[0].listen_port, Some(5432));
+        assert_eq!(
+            session.forwards[0].target.as_deref(),
+            Some("db.internal:5432")
+        );
+        assert_eq!(session.forwards[1].kind, ForwardKind::Dynamic);
+        assert_eq!(session.forwards[1].listen_port, Some(1080));
+    }
+
+    #[test]
+    fn detects_interactive_sessions_and_remote_forwards() {
+        let session = parse_ssh_command(&argv(
+            "ssh -L [::1]:8080:[fd00::2]:80 dev@box -R 9000:localhost:3000",
+        ))
+        .unwrap();
+        assert!(session.interactive);
+        assert_eq!(session.forwards[0].listen_port, Some(8080));
+        assert_eq!(session.forwards[0].target.as_deref(), Some("[fd00::2]:80"));
+        assert_eq!(session.forwards[1].kind, ForwardKind::Remote);
+        assert_eq!(session.local_forwards().count(), 1);
+
+        let with_command =
+            parse_ssh_command(&argv("ssh -L 8080:localhost:80 box tail -f log")).unwrap();
+        assert!(!with_command.interactive);
+    }
+
+    #[test]
+    fn ignores_clients_without_forwards() {
+        assert!(parse_ssh_command(&argv("ssh -p 2222 box")).is_none());
+        assert!(parse_ssh_command(&argv("sshd -D")).is_none());
+        assert!(parse_ssh_command(&argv("ssh -o ControlMaster=no box")).is_none());
+    }
+
+    #[test]
+    fn keeps_control_masters_without_forwards() {
+        let session = parse_ssh_command(&argv("ssh -fNM -S /tmp/cm-box box")).unwrap();
+        assert!(session.forwards.is_empty());
+        assert!(!session.interactive);
+        assert_eq!(session.control_args, vec!["-S", "/tmp/cm-box"]);
+        assert!(
+            parse_ssh_command(&argv(
+                "ssh -N -o ControlMaster=auto -o ControlPath=~/.ssh/cm-%C box"
+            ))
+            .is_some()
+        );
+    }
+}
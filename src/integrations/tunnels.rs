@@ -0,0 +1,370 @@
+//! Public tunnel detection (ngrok, cloudflared, tailscale funnel, localtunnel)
+//!
+//! Tunnels don't own the ports they expose, so instead of claiming listeners
+//! this reports which local ports are reachable from the internet and, where the
+//! client makes it available, under which public URL.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use log::debug;
+use serde_json::Value;
+
+use crate::model::PublicTunnel;
+use crate::process::ports::command_lines_for_each;
+use crate::utils::{find_command, hidden_command, output_with_timeout};
+
+/// Name used for this detector's health status line.
+pub const DISPLAY_NAME: &str = "Tunnel detection";
+
+const API_TIMEOUT: Duration = Duration::from_millis(500);
+const TAILSCALE_TIMEOUT: Duration = Duration::from_secs(3);
+const NGROK_API: &str = "127.0.0.1:4040";
+// cloudflared quick tunnels serve metrics on the first free port in this range
+const CLOUDFLARED_METRICS_PORTS: std::ops::RangeInclusive<u16> = 20241..=20245;
+
+/// Every local port currently exposed by a known tunnel client.
+pub fn detect_public_tunnels() -> Result<Vec<PublicTunnel>> {
+    // One process listing serves every detector
+    let processes = command_lines_for_each(&["ngrok", "cloudflared", "node", "lt"])?;
+    let mut tunnels = Vec::new();
+    tunnels.extend(detect_ngrok(&named(&processes, &["ngrok"])));
+    tunnels.extend(detect_cloudflared(&named(&processes, &["cloudflared"])));
+    tunnels.extend(detect_localtunnel(&named(&processes, &["node", "lt"])));
+    tunnels.extend(detect_tailscale_funnel());
+    tunnels.sort_by(|a, b| (a.port, &a.provider).cmp(&(b.port, &b.provider)));
+    tunnels.dedup();
+    Ok(tunnels)
+}
+
+/// Argv of the processes that matched one of `names`.
+fn named<'a>(processes: &'a [(&str, i32, Vec<String>)], names: &[&str]) -> Vec<&'a [String]> {
+    processes
+        .iter()
+        .filter(|(name, _, _)| names.contains(name))
+        .map(|(_, _, argv)| argv.as_slice())
+        .collect()
+}
+
+fn detect_ngrok(processes: &[&[String]]) -> Vec<PublicTunnel> {
+    if processes.is_empty() {
+        return Vec::new();
+    }
+    // The inspection API knows the real public URLs, including `ngrok start` tunnels
+    match http_get_json(NGROK_API, "/api/tunnels") {
+        Ok(api) => return parse_ngrok_api(&api),
+        Err(err) => debug!("ngrok API unavailable: {}", err),
+    }
+    processes
+        .iter()
+        .filter_map(|argv| parse_ngrok_args(argv))
+        .collect()
+}
+
+/// `{"tunnels": [{"public_url": "https://…", "config": {"addr": "http://localhost:3000"}}]}`
+fn parse_ngrok_api(api: &Value) -> Vec<PublicTunnel> {
+    api["tunnels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tunnel| {
+            Some(PublicTunnel {
+                provider: "ngrok".to_string(),
+                port: addr_port(tunnel["config"]["addr"].as_str()?)?,
+                url: tunnel["public_url"].as_str().map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// `ngrok http [--url=host] 3000` / `ngrok tcp 22`
+fn parse_ngrok_args(argv: &[String]) -> Option<PublicTunnel> {
+    let mut args = argv.iter().skip(1);
+    args.by_ref()
+        .find(|arg| matches!(arg.as_str(), "http" | "tcp" | "tls"))?;
+    let mut domain = None;
+    let mut port = None;
+    while let Some(arg) = args.next() {
+        if let Some(flag) = arg.strip_prefix("--") {
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            if matches!(name, "url" | "domain" | "hostname" | "subdomain") {
+                let value = value.or_else(|| args.next().cloned())?;
+                domain = Some(if name == "subdomain" {
+                    format!("{}.ngrok.io", value)
+                } else {
+                    value
+                });
+            }
+        } else if port.is_none() && !arg.starts_with('-') {
+            port = addr_port(arg);
+        }
+    }
+    Some(PublicTunnel {
+        provider: "ngrok".to_string(),
+        port: port?,
+        url: domain.map(|d| {
+            if d.contains("://") {
+                d
+            } else {
+                format!("https://{}", d)
+            }
+        }),
+    })
+}
+
+fn detect_cloudflared(processes: &[&[String]]) -> Vec<PublicTunnel> {
+    let mut tunnels = Vec::new();
+    for argv in processes {
+        let Some((port, metrics, hostname)) = parse_cloudflared_args(argv) else {
+            continue;
+        };
+        // Quick tunnels report their random trycloudflare.com hostname on /quicktunnel
+        let hostname = hostname.or_else(|| {
+            let candidates: Vec<String> = match metrics {
+                Some(addr) => vec![addr],
+                None => CLOUDFLARED_METRICS_PORTS
+                    .map(|p| format!("127.0.0.1:{}", p))
+                    .collect(),
+            };
+            candidates.iter().find_map(|addr| {
+                let info = http_get_json(addr, "/quicktunnel").ok()?;
+                info["hostname"]
+                    .as_str()
+                    .filter(|h| !h.is_empty())
+                    .map(str::to_string)
+            })
+        });
+        tunnels.push(PublicTunnel {
+            provider: "cloudflared".to_string(),
+            port,
+            url: hostname.map(|h| format!("https://{}", h)),
+        });
+    }
+    tunnels
+}
+
+/// `cloudflared tunnel --url http://localhost:8000 [--metrics addr] [--hostname host]`
+/// as `(port, metrics address, hostname)`. Named tunnels routed through a config
+/// file don't name their local port and are skipped.
+fn parse_cloudflared_args(argv: &[String]) -> Option<(u16, Option<String>, Option<String>)> {
+    let mut port = None;
+    let mut metrics = None;
+    let mut hostname = None;
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let slot = match flag.trim_start_matches('-') {
+            "url" => {
+                port = addr_port(&inline.or_else(|| args.next().cloned())?);
+                continue;
+            }
+            "metrics" => &mut metrics,
+            "hostname" => &mut hostname,
+            _ => continue,
+        };
+        *slot = inline.or_else(|| args.next().cloned());
+    }
+    Some((port?, metrics, hostname))
+}
+
+fn detect_localtunnel(processes: &[&[String]]) -> Vec<PublicTunnel> {
+    // The `lt` CLI is a node script, so it usually shows up as `node …/lt --port 8000`
+    processes
+        .iter()
+        .filter(|argv| {
+            argv.iter().take(2).any(|arg| {
+                let name = arg.rsplit(['/', '\\']).next().unwrap_or(arg);
+                name == "lt" || name == "lt.js" || arg.contains("localtunnel")
+            })
+        })
+        .filter_map(|argv| parse_localtunnel_args(argv))
+        .collect()
+}
+
+/// `lt --port 8000 [--subdomain name] [--host https://localtunnel.me]`
+fn parse_localtunnel_args(argv: &[String]) -> Option<PublicTunnel> {
+    let mut port = None;
+    let mut subdomain = None;
+    let mut args = argv.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        match flag {
+            "--port" | "-p" => port = inline.or_else(|| args.next().cloned())?.parse().ok(),
+            "--subdomain" | "-s" => subdomain = inline.or_else(|| args.next().cloned()),
+            _ => {}
+        }
+    }
+    Some(PublicTunnel {
+        provider: "localtunnel".to_string(),
+        port: port?,
+        url: subdomain.map(|s| format!("https://{}.loca.lt", s)),
+    })
+}
+
+/// Funnel config lives in tailscaled, both for `tailscale funnel 3000` and `--bg`.
+fn detect_tailscale_funnel() -> Vec<PublicTunnel> {
+    let out = output_with_timeout(
+        hidden_command(find_command("tailscale")).args(["funnel", "status", "--json"]),
+        TAILSCALE_TIMEOUT,
+    );
+    match out {
+        Ok(out) if out.status.success() => match serde_json::from_slice::<Value>(&out.stdout) {
+            Ok(status) => parse_funnel_status(&status),
+            Err(err) => {
+                debug!("Unexpected tailscale funnel status: {}", err);
+                Vec::new()
+            }
+        },
+        Ok(_) => Vec::new(),
+        Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(err) => {
+            debug!("tailscale funnel status failed: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// `{"Web": {"host.ts.net:443": {"Handlers": {"/": {"Proxy": "http://127.0.0.1:3000"}}}},
+///   "AllowFunnel": {"host.ts.net:443": true}}`
+fn parse_funnel_status(status: &Value) -> Vec<PublicTunnel> {
+    let Some(allowed) = status["AllowFunnel"].as_object() else {
+        return Vec::new();
+    };
+    let Some(web) = status["Web"].as_object() else {
+        return Vec::new();
+    };
+    let mut tunnels = Vec::new();
+    for (host_port, config) in web {
+        if allowed.get(host_port).and_then(Value::as_bool) != Some(true) {
+            continue;
+        }
+        let url = match host_port.strip_suffix(":443") {
+            Some(host) => format!("https://{}", host),
+            None => format!("https://{}", host_port),
+        };
+        for handler in config["Handlers"].as_object().into_iter().flatten() {
+            if let Some(port) = handler.1["Proxy"].as_str().and_then(addr_port) {
+                tunnels.push(PublicTunnel {
+                    provider: "tailscale funnel".to_string(),
+                    port,
+                    url: Some(format!("{}{}", url, handler.0.trim_end_matches('/'))),
+                });
+            }
+        }
+    }
+    tunnels
+}
+
+/// Local port from `3000`, `localhost:3000` or `http://127.0.0.1:3000/path`.
+fn addr_port(addr: &str) -> Option<u16> {
+    let (scheme, rest) = match addr.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, addr),
+    };
+    let authority = rest.split('/').next()?;
+    match authority.rsplit_once(':') {
+        Some((_, port)) => port.parse().ok(),
+        None => match (scheme, authority.parse()) {
+            (_, Ok(port)) => Some(port),
+            (Some("http"), _) => Some(80),
+            (Some("https"), _) => Some(443),
+            _ => None,
+        },
+    }
+}
+
+/// Minimal HTTP/1.0 GET against a loopback API, parsed as JSON.
+fn http_get_json(addr: &str, path: &str) -> Result<Value> {
+    let addr: SocketAddr = addr
+        .parse()
+        .with_context(|| format!("bad address {}", addr))?;
+    let mut stream = TcpStream::connect_timeout(&addr, API_TIMEOUT)?;
+    stream.set_read_timeout(Some(API_TIMEOUT))?;
+    stream.set_write_timeout(Some(API_TIMEOUT))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n",
+        path, addr
+    )?;
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw)?;
+    let raw = String::from_utf8_lossy(&raw);
+    let (head, body) = raw
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed HTTP response"))?;
+    if head.split_whitespace().nth(1) != Some("200") {
+        bail!("{}", head.lines().next().unwrap_or(""));
+    }
+    Ok(serde_json::from_str(body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_tunnel_client_arguments() {
+        let ngrok =
+            parse_ngrok_args(&argv("ngrok http --url=demo.ngrok.app localhost:3000")).unwrap();
+        assert_eq!(ngrok.port, 3000);
+        assert_eq!(ngrok.url.as_deref(), Some("https://demo.ngrok.app"));
+        assert_eq!(parse_ngrok_args(&argv("ngrok tcp 22")).unwrap().url, None);
+        assert!(parse_ngrok_args(&argv("ngrok config check")).is_none());
+
+        let (port, metrics, _) = parse_cloudflared_args(&argv(
+            "cloudflared tunnel --url http://localhost:8000 --metrics=127.0.0.1:40000",
+        ))
+        .unwrap();
+        assert_eq!(port, 8000);
+        assert_eq!(metrics.as_deref(), Some("127.0.0.1:40000"));
+        assert!(parse_cloudflared_args(&argv("cloudflared tunnel run office")).is_none());
+
+        let lt =
+            parse_localtunnel_args(&argv("node /usr/local/bin/lt --port 8080 -s admin")).unwrap();
+        assert_eq!(lt.port, 8080);
+        assert_eq!(lt.url.as_deref(), Some("https://admin.loca.lt"));
+    }
+
+    #[test]
+    fn parses_ngrok_api_and_funnel_status() {
+        let api: Value = serde_json::from_str(
+            r#"{"tunnels": [{"public_url": "https://a1b2.ngrok-free.app",
+                             "config": {"addr": "http://localhost:5173"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_ngrok_api(&api),
+            vec![PublicTunnel {
+                provider: "ngrok".into(),
+                port: 5173,
+                url: Some("https://a1b2.ngrok-free.app".into()),
+            }]
+        );
+
+        let status: Value = serde_json::from_str(
+            r#"{"Web": {"box.tail1.ts.net:443": {"Handlers": {"/": {"Proxy": "http://127.0.0.1:3000"}}},
+                        "box.tail1.ts.net:8443": {"Handlers": {"/": {"Proxy": "http://127.0.0.1:4000"}}}},
+                "AllowFunnel": {"box.tail1.ts.net:443": true}}"#,
+        )
+        .unwrap();
+        let funnels = parse_funnel_status(&status);
+        assert_eq!(funnels.len(), 1);
+        assert_eq!(funnels[0].port, 3000);
+        assert_eq!(funnels[0].url.as_deref(), Some("https://box.tail1.ts.net"));
+    }
+}
@@ -0,0 +1,746 @@
+//! Virtual machine port-forward integration (QEMU, VirtualBox, Vagrant)
+//!
+//! A `qemu-system-*` or `VBoxHeadless` listener on 2222 or 8080 is a VM port
+//! forward, not a dev server. Forwards come from QEMU's `hostfwd=` arguments or
+//! VirtualBox's NAT rules, and Vagrant machines are found through the `.vagrant`
+//! directory. VMs are shut down gracefully (`vagrant halt`, the QEMU monitor's
+//! `system_powerdown`, or an ACPI power button) instead of being killed.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use log::debug;
+use serde::Deserialize;
+
+use crate::integrations::registry::Integration;
+use crate::model::{KillFeedback, ManagedUnit, ProcessInfo};
+use crate::process::ports::{command_lines_for, process_cwd};
+use crate::utils::{find_command, hidden_command, output_with_timeout};
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+const MONITOR_TIMEOUT: Duration = Duration::from_secs(5);
+// `vagrant halt` waits for the guest to shut down before forcing it
+const HALT_TIMEOUT: Duration = Duration::from_secs(120);
+
+const VBOX_PROGRAMS: &[&str] = &["VBoxHeadless", "VirtualBoxVM"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostForward {
+    pub host_port: u16,
+    pub guest_port: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonitorAddr {
+    #[cfg(unix)]
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+/// A QEMU monitor socket: human (`-monitor`) or QMP (`-qmp`, `mode=control`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monitor {
+    pub addr: MonitorAddr,
+    pub qmp: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VmKind {
+    Qemu {
+        monitor: Option<Monitor>,
+        uuid: Option<String>,
+    },
+    VirtualBox {
+        uuid: String,
+    },
+}
+
+impl VmKind {
+    /// Vagrant provider directories that can hold this kind of VM.
+    fn vagrant_providers(&self) -> &'static [&'static str] {
+        match self {
+            VmKind::Qemu { .. } => &["qemu", "libvirt"],
+            VmKind::VirtualBox { .. } => &["virtualbox"],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VagrantMachine {
+    pub project_dir: PathBuf,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VmInfo {
+    pub pid: i32,
+    pub name: String,
+    pub kind: VmKind,
+    pub forwards: Vec<HostForward>,
+    pub vagrant: Option<VagrantMachine>,
+}
+
+impl VmInfo {
+    fn guest_port(&self, host_port: u16) -> Option<u16> {
+        self.forwards
+            .iter()
+            .find(|f| f.host_port == host_port)
+            .map(|f| f.guest_port)
+    }
+
+    /// Identifiers a Vagrant provider may record as the machine's `id`: the
+    /// VirtualBox UUID, or QEMU's `-uuid` (vagrant-libvirt) and `-name`.
+    fn provider_ids(&self) -> Vec<&str> {
+        match &self.kind {
+            VmKind::Qemu { uuid, .. } => uuid
+                .iter()
+                .map(String::as_str)
+                .chain([self.name.as_str()])
+                .collect(),
+            VmKind::VirtualBox { uuid } => vec![uuid],
+        }
+    }
+
+    /// The Vagrant machine this VM belongs to, from the process's working directory.
+    fn vagrant_machine(&self) -> Option<VagrantMachine> {
+        let cwd = process_cwd(self.pid)?;
+        find_vagrant_machine(&cwd, self.kind.vagrant_providers(), &self.provider_ids())
+    }
+
+    fn display_name(&self) -> String {
+        match (&self.vagrant, &self.kind) {
+            (Some(machine), _) => format!("{} (Vagrant)", machine.name),
+            (None, VmKind::Qemu { .. }) => format!("{} (QEMU)", self.name),
+            (None, VmKind::VirtualBox { .. }) => format!("{} (VirtualBox)", self.name),
+        }
+    }
+}
+
+/// Running VMs, claimed by PID, one unit per VM process.
+#[derive(Default)]
+pub struct VmIntegration {
+    vms: Mutex<BTreeMap<String, VmInfo>>,
+}
+
+impl Integration for VmIntegration {
+    fn id(&self) -> &str {
+        "vm"
+    }
+
+    fn display_name(&self) -> &str {
+        "VMs"
+    }
+
+    fn section_title(&self) -> &str {
+        "Virtual Machines"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Shut Down All VMs"
+    }
+
+    fn refresh(&self) -> Result<Vec<ManagedUnit>> {
+        let mut vms = BTreeMap::new();
+        for (pid, argv) in command_lines_for("qemu-system-*")? {
+            let mut vm = parse_qemu_command(pid, &argv);
+            vm.vagrant = vm.vagrant_machine();
+            vms.insert(pid.to_string(), vm);
+        }
+        for program in VBOX_PROGRAMS {
+            for (pid, argv) in command_lines_for(program)? {
+                let Some(mut vm) = parse_vbox_command(pid, &argv) else {
+                    continue;
+                };
+                if let VmKind::VirtualBox { uuid } = &vm.kind {
+                    vm.forwards = query_vbox_forwards(uuid).unwrap_or_else(|err| {
+                        debug!("VBoxManage showvminfo {} failed: {}", uuid, err);
+                        Vec::new()
+                    });
+                    vm.vagrant = vm
+                        .vagrant_machine()
+                        .or_else(|| find_indexed_vagrant_machine(uuid));
+                }
+                vms.insert(pid.to_string(), vm);
+            }
+        }
+
+        let units = vms
+            .iter()
+            .map(|(name, vm)| ManagedUnit {
+                name: name.clone(),
+                status: "running".to_string(),
+                ports: vm.forwards.iter().map(|f| f.host_port).collect(),
+                pids: vec![vm.pid],
+            })
+            .collect();
+        *self.vms.lock().unwrap_or_else(|e| e.into_inner()) = vms;
+        Ok(units)
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        units
+            .iter()
+            .find(|unit| unit.pids.contains(&process.pid))
+            .map(|unit| unit.name.clone())
+    }
+
+    fn unit_label(&self, unit: &str) -> String {
+        self.with_vm(unit, VmInfo::display_name)
+            .unwrap_or_else(|| unit.to_string())
+    }
+
+    /// `2222 → guest:22, 8080 → guest:80 · web (Vagrant)`
+    fn unit_menu_label(&self, unit: &str, ports: &[u16]) -> String {
+        let Some(vm) = self.with_vm(unit, VmInfo::clone) else {
+            return unit.to_string();
+        };
+        let ports_str = ports
+            .iter()
+            .map(|port| match vm.guest_port(*port) {
+                Some(guest) => format!("{} → guest:{}", port, guest),
+                None => port.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} · {}", ports_str, vm.display_name())
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        let Some(vm) = self.with_vm(unit, VmInfo::clone) else {
+            return KillFeedback::warning(format!("VM {} is no longer running.", unit));
+        };
+        let result = match (&vm.vagrant, &vm.kind) {
+            (Some(machine), _) => vagrant_halt(machine),
+            (
+                None,
+                VmKind::Qemu {
+                    monitor: Some(monitor),
+                    ..
+                },
+            ) => qemu_powerdown(monitor),
+            (None, VmKind::Qemu { monitor: None, .. }) => Err(anyhow!(
+                "no QEMU monitor to shut it down gracefully; start QEMU with -qmp unix:<path>,server,nowait"
+            )),
+            (None, VmKind::VirtualBox { uuid }) => vbox_acpi_shutdown(uuid),
+        };
+        match result {
+            Ok(how) => KillFeedback::info(format!("{} {}.", how, vm.display_name())),
+            Err(err) => KillFeedback::error(format!(
+                "Failed to shut down {}: {}",
+                vm.display_name(),
+                err
+            )),
+        }
+    }
+}
+
+impl VmIntegration {
+    fn with_vm<T>(&self, unit: &str, f: impl FnOnce(&VmInfo) -> T) -> Option<T> {
+        let vms = self.vms.lock().unwrap_or_else(|e| e.into_inner());
+        vms.get(unit).map(f)
+    }
+}
+
+/// Parse a `qemu-system-*` command line for its name, `hostfwd=` rules and monitor.
+pub fn parse_qemu_command(pid: i32, argv: &[String]) -> VmInfo {
+    let mut name = None;
+    let mut uuid = None;
+    let mut forwards = Vec::new();
+    let mut monitor = None;
+    // -chardev socket,id=X,path=... referenced later by -mon chardev=X
+    let mut chardevs: BTreeMap<String, MonitorAddr> = BTreeMap::new();
+
+    let mut args = argv.iter().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        // QEMU accepts both -opt and --opt
+        let opt = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-'));
+        let Some(opt) = opt else {
+            continue;
+        };
+        // Boolean switches such as -nographic take no value
+        let Some(value) = args.next_if(|next| !next.starts_with('-')) else {
+            continue;
+        };
+        match opt {
+            "name" => {
+                let first = value.split(',').next().unwrap_or(value);
+                name = Some(first.strip_prefix("guest=").unwrap_or(first).to_string());
+            }
+            "uuid" => uuid = Some(value.clone()),
+            "netdev" | "nic" | "net" => {
+                forwards.extend(
+                    value
+                        .split(',')
+                        .filter_map(|part| parse_hostfwd(part.strip_prefix("hostfwd=")?)),
+                );
+            }
+            "monitor" | "qmp" | "qmp-pretty" => {
+                if let Some(addr) = parse_monitor_addr(value) {
+                    monitor = Some(Monitor {
+                        addr,
+                        qmp: opt != "monitor",
+                    });
+                }
+            }
+            "chardev" => {
+                let options = parse_options(value);
+                if value.starts_with("socket")
+                    && let Some(id) = options.get("id")
+                {
+                    let addr = match (
+                        options.get("path"),
+                        options.get("host"),
+                        options.get("port"),
+                    ) {
+                        #[cfg(unix)]
+                        (Some(path), _, _) => Some(MonitorAddr::Unix(PathBuf::from(path))),
+                        (_, host, Some(port)) => Some(MonitorAddr::Tcp(format!(
+                            "{}:{}",
+                            host.map(String::as_str).unwrap_or("127.0.0.1"),
+                            port
+                        ))),
+                        _ => None,
+                    };
+                    if let Some(addr) = addr {
+                        chardevs.insert(id.clone(), addr);
+                    }
+                }
+            }
+            "mon" => {
+                let options = parse_options(value);
+                if let Some(addr) = options.get("chardev").and_then(|id| chardevs.get(id)) {
+                    monitor = Some(Monitor {
+                        addr: addr.clone(),
+                        qmp: options.get("mode").is_some_and(|m| m == "control"),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    VmInfo {
+        pid,
+        name: name.unwrap_or_else(|| "QEMU VM".to_string()),
+        kind: VmKind::Qemu { monitor, uuid },
+        forwards,
+        vagrant: None,
+    }
+}
+
+/// `key=value` pairs of a QEMU option list (bare flags are ignored).
+fn parse_options(value: &str) -> BTreeMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|part| part.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// `[tcp]:[hostaddr]:hostport-[guestaddr]:guestport`; UDP rules are skipped.
+fn parse_hostfwd(rule: &str) -> Option<HostForward> {
+    if rule.starts_with("udp:") {
+        return None;
+    }
+    let (host, guest) = rule.split_once('-')?;
+    Some(HostForward {
+        host_port: host.rsplit(':').next()?.parse().ok()?,
+        guest_port: guest.rsplit(':').next()?.parse().ok()?,
+    })
+}
+
+/// `unix:/path,server,nowait`, `tcp:host:port,server` or `telnet:host:port,...`.
+fn parse_monitor_addr(value: &str) -> Option<MonitorAddr> {
+    let target = value.split(',').next()?;
+    if let Some(path) = target.strip_prefix("unix:") {
+        #[cfg(unix)]
+        return Some(MonitorAddr::Unix(PathBuf::from(path)));
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            return None;
+        }
+    }
+    let hostport = target
+        .strip_prefix("tcp:")
+        .or_else(|| target.strip_prefix("telnet:"))?;
+    let (host, port) = hostport.rsplit_once(':')?;
+    let host = if host.is_empty() { "127.0.0.1" } else { host };
+    Some(MonitorAddr::Tcp(format!("{}:{}", host, port)))
+}
+
+/// Parse a `VBoxHeadless`/`VirtualBoxVM` command line for the VM name and UUID.
+pub fn parse_vbox_command(pid: i32, argv: &[String]) -> Option<VmInfo> {
+    let mut name = None;
+    let mut uuid = None;
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let target = match flag {
+            "--comment" => &mut name,
+            "--startvm" | "-startvm" | "-s" => &mut uuid,
+            _ => continue,
+        };
+        *target = inline.or_else(|| args.next().cloned());
+    }
+    let uuid = uuid?;
+    Some(VmInfo {
+        pid,
+        name: name.unwrap_or_else(|| uuid.clone()),
+        kind: VmKind::VirtualBox { uuid },
+        forwards: Vec::new(),
+        vagrant: None,
+    })
+}
+
+fn vboxmanage() -> String {
+    // The Windows installer doesn't add VirtualBox to PATH but records its location
+    #[cfg(target_os = "windows")]
+    if let Ok(dir) = std::env::var("VBOX_MSI_INSTALL_PATH") {
+        return Path::new(&dir)
+            .join("VBoxManage.exe")
+            .to_string_lossy()
+            .into_owned();
+    }
+    find_command("VBoxManage").to_string()
+}
+
+fn query_vbox_forwards(uuid: &str) -> Result<Vec<HostForward>> {
+    let out = output_with_timeout(
+        hidden_command(&vboxmanage()).args(["showvminfo", uuid, "--machinereadable"]),
+        QUERY_TIMEOUT,
+    )?;
+    if !out.status.success() {
+        bail!("{}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(parse_vbox_forwards(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// NAT rules look like `Forwarding(0)="ssh,tcp,127.0.0.1,2222,,22"`.
+fn parse_vbox_forwards(showvminfo: &str) -> Vec<HostForward> {
+    showvminfo
+        .lines()
+        .filter(|line| line.starts_with("Forwarding("))
+        .filter_map(|line| {
+            let (_, rule) = line.split_once('=')?;
+            let fields: Vec<&str> = rule.trim_matches('"').split(',').collect();
+            match fields.as_slice() {
+                [_, "tcp", _, host_port, _, guest_port] => Some(HostForward {
+                    host_port: host_port.parse().ok()?,
+                    guest_port: guest_port.parse().ok()?,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Find the Vagrant machine a VM process belongs to from its working directory:
+/// either inside `<project>/.vagrant/machines/<name>/...`, or a project directory
+/// whose `.vagrant/machines/<name>/<provider>/id` is one of `ids` for one of
+/// `providers`.
+pub fn find_vagrant_machine(
+    cwd: &Path,
+    providers: &[&str],
+    ids: &[&str],
+) -> Option<VagrantMachine> {
+    for dir in cwd.ancestors() {
+        let Some(parent) = dir.parent() else {
+            break;
+        };
+        if parent.file_name().is_some_and(|n| n == "machines")
+            && let Some(dot_vagrant) = parent.parent()
+            && dot_vagrant.file_name().is_some_and(|n| n == ".vagrant")
+        {
+            return Some(VagrantMachine {
+                project_dir: dot_vagrant.parent()?.to_path_buf(),
+                name: dir.file_name()?.to_string_lossy().into_owned(),
+            });
+        }
+    }
+    cwd.ancestors().find_map(|dir| {
+        let machine = machines_with_ids(&dir.join(".vagrant"))
+            .into_iter()
+            .find(|m| providers.contains(&m.provider.as_str()) && ids.contains(&m.id.as_str()))?;
+        Some(VagrantMachine {
+            project_dir: dir.to_path_buf(),
+            name: machine.name,
+        })
+    })
+}
+
+/// A `.vagrant/machines/<name>/<provider>/id` file.
+struct ProviderMachine {
+    name: String,
+    provider: String,
+    id: String,
+}
+
+/// Every machine with a provider id under a `.vagrant` directory.
+fn machines_with_ids(dot_vagrant: &Path) -> Vec<ProviderMachine> {
+    let Ok(machines) = std::fs::read_dir(dot_vagrant.join("machines")) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for machine in machines.flatten() {
+        let Ok(providers) = std::fs::read_dir(machine.path()) else {
+            continue;
+        };
+        for provider in providers.flatten() {
+            if let Ok(id) = std::fs::read_to_string(provider.path().join("id")) {
+                found.push(ProviderMachine {
+                    name: machine.file_name().to_string_lossy().into_owned(),
+                    provider: provider.file_name().to_string_lossy().into_owned(),
+                    id: id.trim().to_string(),
+                });
+            }
+        }
+    }
+    found
+}
+
+#[derive(Deserialize)]
+struct MachineIndex {
+    #[serde(default)]
+    machines: BTreeMap<String, IndexedMachine>,
+}
+
+#[derive(Deserialize)]
+struct IndexedMachine {
+    local_data_path: PathBuf,
+    vagrantfile_path: PathBuf,
+    name: String,
+}
+
+/// Fall back to Vagrant's global machine index when the VM's cwd says nothing
+/// (VirtualBox starts VMs from its own service process).
+fn find_indexed_vagrant_machine(uuid: &str) -> Option<VagrantMachine> {
+    let home = dirs::home_dir()?;
+    let vagrant_home = std::env::var_os("VAGRANT_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".vagrant.d"));
+    let index = std::fs::read_to_string(vagrant_home.join("data/machine-index/index")).ok()?;
+    let index: MachineIndex = serde_json::from_str(&index).ok()?;
+    index.machines.into_values().find_map(|machine| {
+        machines_with_ids(&machine.local_data_path)
+            .into_iter()
+            .any(|m| m.name == machine.name && m.id == uuid)
+            .then_some(VagrantMachine {
+                project_dir: machine.vagrantfile_path,
+                name: machine.name,
+            })
+    })
+}
+
+fn vagrant_halt(machine: &VagrantMachine) -> Result<&'static str> {
+    let out = output_with_timeout(
+        hidden_command(find_command("vagrant"))
+            .args(["halt", &machine.name])
+            .current_dir(&machine.project_dir),
+        HALT_TIMEOUT,
+    )
+    .context("vagrant halt failed")?;
+    if !out.status.success() {
+        bail!("{}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok("Halted")
+}
+
+fn vbox_acpi_shutdown(uuid: &str) -> Result<&'static str> {
+    let out = output_with_timeout(
+        hidden_command(&vboxmanage()).args(["controlvm", uuid, "acpipowerbutton"]),
+        QUERY_TIMEOUT,
+    )?;
+    if !out.status.success() {
+        bail!("{}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok("Sent ACPI shutdown to")
+}
+
+/// Ask the guest to power down through the QEMU monitor (ACPI power button).
+fn qemu_powerdown(monitor: &Monitor) -> Result<&'static str> {
+    match &monitor.addr {
+        #[cfg(unix)]
+        MonitorAddr::Unix(path) => {
+            let stream = std::os::unix::net::UnixStream::connect(path)
+                .with_context(|| format!("cannot connect to {}", path.display()))?;
+            stream.set_read_timeout(Some(MONITOR_TIMEOUT))?;
+            stream.set_write_timeout(Some(MONITOR_TIMEOUT))?;
+            monitor_powerdown(stream, monitor.qmp)
+        }
+        MonitorAddr::Tcp(addr) => {
+            let socket_addr = addr
+                .to_socket_addrs()
+                .with_context(|| format!("cannot resolve {}", addr))?
+                .next()
+                .ok_or_else(|| anyhow!("cannot resolve {}", addr))?;
+            let stream = TcpStream::connect_timeout(&socket_addr, MONITOR_TIMEOUT)
+                .with_context(|| format!("cannot connect to {}", addr))?;
+            stream.set_read_timeout(Some(MONITOR_TIMEOUT))?;
+            stream.set_write_timeout(Some(MONITOR_TIMEOUT))?;
+            monitor_powerdown(stream, monitor.qmp)
+        }
+    }
+}
+
+fn monitor_powerdown<S: std::io::Read + Write>(stream: S, qmp: bool) -> Result<&'static str> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if qmp {
+        // Greeting, then capabilities negotiation before any command is accepted
+        reader.read_line(&mut line)?;
+        for command in ["qmp_capabilities", "system_powerdown"] {
+            writeln!(reader.get_mut(), "{{\"execute\": \"{}\"}}", command)?;
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    bail!("QMP connection closed");
+                }
+                // Skip asynchronous events until the command's reply
+                if line.contains("\"return\"") {
+                    break;
+                }
+                if line.contains("\"error\"") {
+                    bail!("QMP {} failed: {}", command, line.trim());
+                }
+            }
+        }
+    } else {
+        writeln!(reader.get_mut(), "system_powerdown")?;
+        reader.get_mut().flush()?;
+    }
+    Ok("Sent power-down to")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_qemu_hostfwd_and_monitor() {
+        let vm = parse_qemu_command(
+            42,
+            &argv(
+                "qemu-system-aarch64 -name guest=devbox,debug-threads=on -nographic -m 2048 \
+                 -netdev user,id=n0,hostfwd=tcp::2222-:22,hostfwd=tcp:127.0.0.1:8080-10.0.2.15:80,hostfwd=udp::5353-:53 \
+                 -uuid 9f0a-7c -qmp tcp:localhost:4444,server,nowait",
+            ),
+        );
+        assert_eq!(vm.name, "devbox");
+        assert_eq!(
+            vm.forwards,
+            vec![
+                HostForward {
+                    host_port: 2222,
+                    guest_port: 22
+                },
+                HostForward {
+                    host_port: 8080,
+                    guest_port: 80
+                },
+            ]
+        );
+        assert_eq!(
+            vm.kind,
+            VmKind::Qemu {
+                monitor: Some(Monitor {
+                    addr: MonitorAddr::Tcp("localhost:4444".into()),
+                    qmp: true
+                }),
+                uuid: Some("9f0a-7c".into())
+            }
+        );
+    }
+
+    #[test]
+    fn parses_vbox_command_and_nat_rules() {
+        let vm = parse_vbox_command(
+            7,
+            &argv("VBoxHeadless --comment web_default_1700000000 --startvm 5e1c-42 --vrde config"),
+        )
+        .unwrap();
+        assert_eq!(vm.name, "web_default_1700000000");
+        assert_eq!(
+            vm.kind,
+            VmKind::VirtualBox {
+                uuid: "5e1c-42".into()
+            }
+        );
+        let forwards = parse_vbox_forwards(
+            "name=\"web\"\nForwarding(0)=\"ssh,tcp,127.0.0.1,2222,,22\"\nForwarding(1)=\"dns,udp,,5353,,53\"\n",
+        );
+        assert_eq!(
+            forwards,
+            vec![HostForward {
+                host_port: 2222,
+                guest_port: 22
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_vagrant_machine_by_id() {
+        let project =
+            std::env::temp_dir().join(format!("portkiller-vagrant-{}", std::process::id()));
+        let provider = project.join(".vagrant/machines/web/virtualbox");
+        std::fs::create_dir_all(&provider).unwrap();
+        std::fs::write(provider.join("id"), "5e1c-42").unwrap();
+
+        let machine = find_vagrant_machine(&project, &["virtualbox"], &["5e1c-42"]).unwrap();
+        assert_eq!(machine.name, "web");
+        assert_eq!(machine.project_dir, project);
+        assert!(find_vagrant_machine(&project, &["virtualbox"], &["other"]).is_none());
+
+        // QEMU providers run from inside the machine directory
+        let inside = find_vagrant_machine(&provider, &["qemu", "libvirt"], &[]).unwrap();
+        assert_eq!(inside.name, "web");
+
+        // A hand-started QEMU VM next to a lone VirtualBox machine isn't it
+        let qemu_cwd = project.join("vms");
+        std::fs::create_dir_all(&qemu_cwd).unwrap();
+        assert!(find_vagrant_machine(&qemu_cwd, &["qemu", "libvirt"], &["QEMU VM"]).is_none());
+        assert!(find_vagrant_machine(&qemu_cwd, &["qemu", "libvirt"], &["5e1c-42"]).is_none());
+
+        let libvirt = project.join(".vagrant/machines/db/libvirt");
+        std::fs::create_dir_all(&libvirt).unwrap();
+        std::fs::write(libvirt.join("id"), "9f0a-7c").unwrap();
+        let machine = find_vagrant_machine(&qemu_cwd, &["qemu", "libvirt"], &["9f0a-7c"]).unwrap();
+        assert_eq!(machine.name, "db");
+
+        let _ = std::fs::remove_dir_all(&project);
+    }
+
+    #[test]
+    fn hmp_powerdown_writes_command() {
+        struct Capture(Vec<u8>);
+        impl std::io::Read for Capture {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Ok(0)
+            }
+        }
+        impl Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut capture = Capture(Vec::new());
+        monitor_powerdown(&mut capture, false).unwrap();
+        assert_eq!(capture.0, b"system_powerdown\n");
+    }
+}
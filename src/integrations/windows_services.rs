@@ -4,63 +4,137 @@
 //! Windows services like PostgreSQL, MySQL, SQL Server, Redis, etc.
 
 use std::collections::HashMap;
+use std::time::Duration;
 
-use crate::utils::hidden_command;
+use anyhow::anyhow;
 
-use crate::model::KillFeedback;
+use crate::utils::{hidden_command, output_with_timeout};
 
-/// Query Windows services that commonly use dev ports
-pub fn query_windows_services_map() -> anyhow::Result<HashMap<String, String>> {
-    let mut map = HashMap::new();
-
-    // Check common dev services
-    let services = [
-        // PostgreSQL
-        "postgresql-x64-16",
-        "postgresql-x64-15",
-        "postgresql-x64-14",
-        "postgresql-x64-13",
-        "postgresql",
-        // MySQL
-        "MySQL80",
-        "MySQL57",
-        "MySQL",
-        // SQL Server
-        "MSSQLSERVER",
-        "MSSQL$SQLEXPRESS",
-        "SQLAgent$SQLEXPRESS",
-        // Redis
-        "Redis",
-        // MongoDB
-        "MongoDB",
-    ];
-
-    for service in services {
-        if let Some(status) = get_service_status(service) {
-            map.insert(service.to_string(), status);
-        }
+use crate::integrations::registry::Integration;
+use crate::model::{KillFeedback, ManagedUnit, ProcessInfo};
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Installed Windows services for common dev databases, claimed by command and default port.
+pub struct WindowsServicesIntegration;
+
+impl Integration for WindowsServicesIntegration {
+    // Kept as "service" so menu ids stay `service_stop_<name>`
+    fn id(&self) -> &str {
+        "service"
+    }
+
+    fn display_name(&self) -> &str {
+        "Windows Services"
+    }
+
+    fn section_title(&self) -> &str {
+        "Windows Services"
+    }
+
+    fn stop_all_label(&self) -> &str {
+        "Stop All Services"
+    }
+
+    fn refresh(&self) -> anyhow::Result<Vec<ManagedUnit>> {
+        Ok(query_windows_services_map()?
+            .into_iter()
+            .map(|(name, status)| ManagedUnit {
+                ports: get_default_port_for_service(&name).into_iter().collect(),
+                name,
+                status,
+                pids: Vec::new(),
+            })
+            .collect())
+    }
+
+    fn claim(&self, process: &ProcessInfo, units: &[ManagedUnit]) -> Option<String> {
+        let services: HashMap<String, String> = units
+            .iter()
+            .map(|u| (u.name.clone(), u.status.clone()))
+            .collect();
+        get_windows_managed_service(&process.command, process.port, &services)
     }
 
-    Ok(map)
+    fn unit_label(&self, unit: &str) -> String {
+        friendly_service_name(unit)
+    }
+
+    fn stop(&self, unit: &str) -> KillFeedback {
+        run_service_stop(unit)
+    }
 }
 
-fn get_service_status(service: &str) -> Option<String> {
-    let output = hidden_command("sc")
-        .args(["query", service])
-        .output()
-        .ok()?;
+/// Service name prefixes (lowercase) of common dev services, e.g. `postgresql-x64-16`.
+const DEV_SERVICE_PREFIXES: &[&str] = &[
+    "postgresql",
+    "mysql",
+    "mssqlserver",
+    "mssql$",
+    "sqlagent$",
+    "redis",
+    "mongodb",
+];
+
+/// Query Windows services that commonly use dev ports, with a single `sc queryex`
+pub fn query_windows_services_map() -> anyhow::Result<HashMap<String, String>> {
+    // The default 4 KiB buffer only holds a few dozen services
+    let output = output_with_timeout(
+        hidden_command("sc").args([
+            "queryex", "type=", "service", "state=", "all", "bufsize=", "262144",
+        ]),
+        QUERY_TIMEOUT,
+    )
+    .map_err(|e| anyhow!("sc queryex failed: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "sc queryex failed: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        ));
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_queryex(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|(name, _)| {
+            let lc = name.to_lowercase();
+            DEV_SERVICE_PREFIXES
+                .iter()
+                .any(|prefix| lc.starts_with(prefix))
+        })
+        .collect())
+}
 
-    if stdout.contains("RUNNING") {
-        Some("running".to_string())
-    } else if stdout.contains("STOPPED") {
-        Some("stopped".to_string())
-    } else if stdout.contains("PENDING") {
-        Some("pending".to_string())
-    } else {
-        None
+/// `SERVICE_NAME: MySQL80` blocks with a `STATE : 4  RUNNING` line, as
+/// `(name, status)`. Services in a state other than running, stopped or
+/// pending are skipped.
+fn parse_queryex(output: &str) -> Vec<(String, String)> {
+    let mut services = Vec::new();
+    let mut name: Option<&str> = None;
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "SERVICE_NAME" => name = Some(value.trim()),
+            "STATE" => {
+                let status = if value.contains("RUNNING") {
+                    "running"
+                } else if value.contains("STOPPED") {
+                    "stopped"
+                } else if value.contains("PENDING") {
+                    "pending"
+                } else {
+                    continue;
+                };
+                if let Some(name) = name.take() {
+                    services.push((name.to_string(), status.to_string()));
+                }
+            }
+            _ => {}
+        }
     }
+    services
 }
 
 /// Check if a process is managed by a Windows service
@@ -128,7 +202,7 @@ fn get_default_port_for_service(service: &str) -> Option<u16> {
 
 /// Stop a Windows service
 pub fn run_service_stop(service: &str) -> KillFeedback {
-    let result = hidden_command("sc").args(["stop", service]).output();
+    let result = output_with_timeout(hidden_command("sc").args(["stop", service]), STOP_TIMEOUT);
 
     match result {
         Ok(out) if out.status.success() => {
@@ -176,3 +250,34 @@ pub fn friendly_service_name(service: &str) -> String {
         service.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sc_queryex() {
+        let output = "\r
+SERVICE_NAME: MySQL80\r
+DISPLAY_NAME: MySQL80\r
+        TYPE               : 10  WIN32_OWN_PROCESS  \r
+        STATE              : 4  RUNNING \r
+                                (STOPPABLE, PAUSABLE, ACCEPTS_SHUTDOWN)\r
+        PID                : 4120\r
+\r
+SERVICE_NAME: postgresql-x64-17\r
+DISPLAY_NAME: postgresql-x64-17 - PostgreSQL Server 17\r
+        STATE              : 1  STOPPED \r
+\r
+SERVICE_NAME: Spooler\r
+        STATE              : 7  PAUSED \r
+";
+        assert_eq!(
+            parse_queryex(output),
+            vec![
+                ("MySQL80".to_string(), "running".to_string()),
+                ("postgresql-x64-17".to_string(), "stopped".to_string()),
+            ]
+        );
+    }
+}
@@ -1,8 +1,16 @@
 pub mod app;
 pub use app::run;
 
+pub mod catalog;
 pub mod config;
+pub mod config_format;
+pub mod config_layers;
+pub mod ignore;
 pub mod model;
+pub mod port_set;
+pub mod probe;
+pub mod project;
+pub mod restart;
 pub mod utils;
 
 // Platform abstraction layer
@@ -20,6 +28,17 @@ pub mod integrations {
     pub mod brew;
 
     pub mod docker;
+    pub mod health;
+    pub mod kubectl;
+    pub mod pm2;
+    pub mod registry;
+    pub mod routes;
+    pub mod script;
+    pub mod services;
+    pub mod ssh;
+    pub mod supervisord;
+    pub mod tunnels;
+    pub mod vm;
 
     #[cfg(target_os = "windows")]
     pub mod windows_services;
@@ -1,20 +1,63 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::integrations::registry::{Integration, IntegrationRegistry};
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ProcessInfo {
     pub port: u16,
     pub pid: i32,
     pub command: String,
+    pub start_time: Option<u64>,
+    /// Address the socket is bound to; `None` for `*` or when unknown
+    pub address: Option<std::net::IpAddr>,
+}
+
+impl ProcessInfo {
+    pub fn identity(&self) -> ProcessIdentity {
+        ProcessIdentity {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// A specific process rather than just a PID: the start time tells a PID the OS
+/// has since handed to a newer process apart from the one that was scanned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ProcessIdentity {
+    pub pid: i32,
+    /// Platform-specific start stamp, only compared for equality; `None` if unreadable
+    pub start_time: Option<u64>,
+}
+
+impl ProcessIdentity {
+    /// Whether the PID still belongs to this process. Assumed true when the
+    /// start time couldn't be read at scan time.
+    pub fn is_current(&self) -> bool {
+        match self.start_time {
+            Some(start) => crate::process::ports::process_start_time(self.pid) == Some(start),
+            None => true,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum UserEvent {
     ProcessesUpdated(Vec<ProcessInfo>),
+    IntegrationsUpdated(IntegrationSnapshot),
+    HealthUpdated(HashMap<u16, crate::probe::ListenerHealth>),
+    /// Branch and dirty state of each project work tree
+    GitStatusUpdated(HashMap<std::path::PathBuf, crate::project::GitStatus>),
+    /// Executables and owners of listeners, for ignore rules
+    IgnoreDetailsRead(crate::ignore::ReadDetails),
     MenuAction(MenuAction),
     KillFeedback(KillFeedback),
     MonitorError(String),
-    ConfigReloaded(crate::config::Config),
+    /// New defaults, system-wide and user layers
+    ConfigReloaded(Vec<crate::config_layers::ConfigLayer>),
     ConfigReloadFailed(String),
 }
 
@@ -23,49 +66,85 @@ pub enum MenuAction {
     KillPid {
         pid: i32,
     },
+    ForceKillPid {
+        pid: i32,
+    },
+    RestartPid {
+        pid: i32,
+    },
     KillAll,
-    DockerStop {
-        container: String,
-    },
-    DockerStopAll,
-    #[cfg(target_os = "macos")]
-    BrewStop {
-        service: String,
-    },
-    #[cfg(target_os = "macos")]
-    BrewStopAll,
-    #[cfg(target_os = "windows")]
-    WindowsServiceStop {
-        service: String,
-    },
-    #[cfg(target_os = "windows")]
-    WindowsServiceStopAll,
+    IntegrationStop {
+        integration: String,
+        unit: String,
+    },
+    IntegrationStopAll {
+        integration: String,
+    },
+    IntegrationAction {
+        integration: String,
+        action: String,
+        unit: String,
+    },
+    OpenTunnelUrl {
+        port: u16,
+        index: usize,
+    },
+    OpenRoute {
+        port: u16,
+        index: usize,
+    },
+    OpenLocalhost {
+        port: u16,
+    },
+    OpenProjectFolder {
+        pid: i32,
+    },
+    OpenInEditor {
+        pid: i32,
+    },
+    CopyPid {
+        pid: i32,
+    },
+    CopyUrl {
+        port: u16,
+    },
     EditConfig,
     ReloadConfig,
+    ShowEffectiveConfig,
     LaunchAtLogin,
     Quit,
 }
 
 #[derive(Clone, Debug)]
 pub enum WorkerCommand {
-    KillPid(KillTarget),
+    KillPid {
+        target: KillTarget,
+        /// Skip the graceful shutdown window
+        force: bool,
+    },
+    RestartPid {
+        target: KillTarget,
+        ports: Vec<u16>,
+    },
     KillAll(Vec<KillTarget>),
-    DockerStop {
-        container: String,
+    IntegrationStop {
+        integration: Arc<dyn Integration>,
+        unit: String,
     },
-    #[cfg(target_os = "macos")]
-    BrewStop {
-        service: String,
+    IntegrationStopAll {
+        integration: Arc<dyn Integration>,
+        units: Vec<String>,
     },
-    #[cfg(target_os = "windows")]
-    WindowsServiceStop {
-        service: String,
+    IntegrationAction {
+        integration: Arc<dyn Integration>,
+        action: String,
+        unit: String,
     },
 }
 
 #[derive(Clone, Debug)]
 pub struct KillTarget {
-    pub pid: i32,
+    pub process: ProcessIdentity,
     pub label: String,
 }
 
@@ -93,6 +172,21 @@ impl KillFeedback {
     }
 }
 
+/// Delivers feedback that arrives after a worker command returned, such as a
+/// service reporting ready, to the event loop.
+#[derive(Clone)]
+pub struct FeedbackSender(winit::event_loop::EventLoopProxy<UserEvent>);
+
+impl FeedbackSender {
+    pub fn new(proxy: winit::event_loop::EventLoopProxy<UserEvent>) -> Self {
+        Self(proxy)
+    }
+
+    pub fn send(&self, feedback: KillFeedback) {
+        let _ = self.0.send_event(UserEvent::KillFeedback(feedback));
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum FeedbackSeverity {
     Info,
@@ -105,12 +199,108 @@ pub struct AppState {
     pub processes: Vec<ProcessInfo>,
     pub last_feedback: Option<KillFeedback>,
     pub config: crate::config::Config,
-    pub project_cache: HashMap<i32, ProjectInfo>,
-    pub docker_port_map: HashMap<u16, DockerContainerInfo>,
-    #[cfg(target_os = "macos")]
-    pub brew_services_map: HashMap<String, String>, // service_name -> status
-    #[cfg(target_os = "windows")]
-    pub windows_services_map: HashMap<String, String>, // service_name -> status
+    pub config_layers: crate::config_layers::LayeredConfig,
+    pub config_origins: crate::config_layers::Origins,
+    pub project_cache: crate::project::ProjectCache,
+    pub catalog: crate::catalog::Catalog,
+    pub ignore: crate::ignore::IgnoreList,
+    pub integrations: IntegrationRegistry,
+    pub integration_units: HashMap<String, Vec<ManagedUnit>>, // integration id -> units
+    pub integration_health: Vec<(String, IntegrationHealth)>, // display name -> health
+    pub public_tunnels: Vec<PublicTunnel>,
+    pub proxy_routes: Vec<ProxyRoute>,
+    pub listener_health: HashMap<u16, crate::probe::ListenerHealth>, // port -> last probe
+}
+
+impl AppState {
+    /// "Next.js (node)" when the catalog recognises the process, otherwise its command.
+    pub fn describe(&self, process: &ProcessInfo) -> String {
+        match self
+            .catalog
+            .describe_process(&process.identity(), &[process.port])
+        {
+            Some(name) => format!("{} ({})", name, process.command),
+            None => process.command.clone(),
+        }
+    }
+
+    /// Whether an `ignore` rule hides this listener. Integration-managed
+    /// listeners stay in their section regardless.
+    pub fn is_hidden(&self, process: &ProcessInfo) -> bool {
+        self.ignore.matches(process) && self.integration_for(process).is_none()
+    }
+
+    /// Listeners that aren't hidden.
+    pub fn visible_processes(&self) -> Vec<ProcessInfo> {
+        self.processes
+            .iter()
+            .filter(|p| !self.is_hidden(p))
+            .cloned()
+            .collect()
+    }
+
+    /// Browser URL for a local port, using the scheme the last probe detected.
+    pub fn local_url(&self, port: u16) -> String {
+        let scheme = self
+            .listener_health
+            .get(&port)
+            .map_or("http", |health| health.scheme());
+        format!("{}://localhost:{}", scheme, port)
+    }
+
+    /// The first integration (in registry order) that claims this listener, with the unit name.
+    pub fn integration_for(
+        &self,
+        process: &ProcessInfo,
+    ) -> Option<(&Arc<dyn Integration>, String)> {
+        self.integrations.iter().find_map(|integration| {
+            let units = self.integration_units.get(integration.id())?;
+            integration
+                .claim(process, units)
+                .map(|unit| (integration, unit))
+        })
+    }
+}
+
+/// Something an integration manages: a container, a service, a supervised program.
+#[derive(Clone, Debug, Default)]
+pub struct ManagedUnit {
+    pub name: String,
+    pub status: String,
+    pub ports: Vec<u16>,
+    pub pids: Vec<i32>,
+}
+
+/// Result of one background integration refresh, published to the UI thread.
+#[derive(Clone, Debug, Default)]
+pub struct IntegrationSnapshot {
+    pub units: HashMap<String, Vec<ManagedUnit>>, // integration id -> units
+    pub health: Vec<(String, IntegrationHealth)>,
+    pub tunnels: Vec<PublicTunnel>,
+    pub routes: Vec<ProxyRoute>,
+}
+
+/// A local port exposed to the internet by a tunnel client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicTunnel {
+    pub provider: String, // e.g. "ngrok"
+    pub port: u16,
+    pub url: Option<String>,
+}
+
+/// A hostname a local reverse proxy (Caddy, nginx, Traefik) routes to a port.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProxyRoute {
+    pub hostname: String, // e.g. "api.localhost"
+    pub port: u16,
+    pub url: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum IntegrationHealth {
+    Healthy,
+    Failing { error: String, failures: u32 },
+    BackingOff { error: String, retry_in: Duration },
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -125,13 +315,24 @@ pub enum KillOutcome {
 #[derive(Clone, Debug)]
 pub struct ProjectInfo {
     pub name: String,
-    #[allow(dead_code)]
     pub path: PathBuf,
+    pub branch: Option<String>,
+    /// Root of the git work tree, which differs between worktrees of one repository
+    pub worktree: Option<PathBuf>,
+    pub dirty: bool,
 }
 
-#[derive(Clone, Debug)]
-pub struct DockerContainerInfo {
-    pub name: String,
-    #[allow(dead_code)]
-    pub id: String,
+impl ProjectInfo {
+    /// `shop (feature/cart)`, with `*` after the branch when the work tree has changes.
+    pub fn label(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!(
+                "{} ({}{})",
+                self.name,
+                branch,
+                if self.dirty { "*" } else { "" }
+            ),
+            None => self.name.clone(),
+        }
+    }
 }
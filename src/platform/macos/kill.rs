@@ -4,7 +4,7 @@ use std::thread;
 use std::time::Duration;
 
 use nix::errno::Errno;
-use nix::sys::signal::{Signal, kill};
+use nix::sys::signal::{Signal, kill, killpg};
 use nix::unistd::Pid;
 
 use crate::model::KillOutcome;
@@ -15,6 +15,15 @@ const SIGKILL_GRACE: Duration = Duration::from_secs(1);
 const POLL_STEP: Duration = Duration::from_millis(200);
 
 pub fn terminate_pid(pid_raw: i32) -> KillOutcome {
+    terminate(pid_raw, true)
+}
+
+/// Send SIGKILL straight away, without giving the process a chance to shut down.
+pub fn force_kill_pid(pid_raw: i32) -> KillOutcome {
+    terminate(pid_raw, false)
+}
+
+fn terminate(pid_raw: i32, graceful: bool) -> KillOutcome {
     let pid = Pid::from_raw(pid_raw);
 
     // Check if process exists
@@ -36,19 +45,21 @@ pub fn terminate_pid(pid_raw: i32) -> KillOutcome {
 
     let mut last_perm_denied = false;
 
-    // Send SIGTERM to the specific PID only (not process group)
-    match kill(pid, Signal::SIGTERM) {
-        Ok(()) => {}
-        Err(Errno::ESRCH) => return KillOutcome::AlreadyExited,
-        Err(Errno::EPERM) => last_perm_denied = true,
-        Err(err) => return KillOutcome::Failed(err as i32),
-    }
+    if graceful {
+        // Send SIGTERM to the specific PID only (not process group)
+        match kill(pid, Signal::SIGTERM) {
+            Ok(()) => {}
+            Err(Errno::ESRCH) => return KillOutcome::AlreadyExited,
+            Err(Errno::EPERM) => last_perm_denied = true,
+            Err(err) => return KillOutcome::Failed(err as i32),
+        }
 
-    // Wait for graceful shutdown
-    match wait_for_exit(pid, SIGTERM_GRACE) {
-        Ok(true) => return KillOutcome::Success,
-        Ok(false) => {}
-        Err(err) => return KillOutcome::Failed(err as i32),
+        // Wait for graceful shutdown
+        match wait_for_exit(pid, SIGTERM_GRACE) {
+            Ok(true) => return KillOutcome::Success,
+            Ok(false) => {}
+            Err(err) => return KillOutcome::Failed(err as i32),
+        }
     }
 
     // Force kill if still running
@@ -72,6 +83,30 @@ pub fn terminate_pid(pid_raw: i32) -> KillOutcome {
     }
 }
 
+/// A child launched with `detached_command` and everything it spawned: the
+/// process group it leads.
+pub struct ProcessTree {
+    group: Pid,
+}
+
+impl ProcessTree {
+    pub fn of(child: &std::process::Child) -> Self {
+        Self {
+            group: Pid::from_raw(child.id() as i32),
+        }
+    }
+
+    /// Ask every process in the tree to shut down (SIGTERM).
+    pub fn terminate(&self) {
+        let _ = killpg(self.group, Signal::SIGTERM);
+    }
+
+    /// Kill whatever is left of the tree (SIGKILL).
+    pub fn kill(&self) {
+        let _ = killpg(self.group, Signal::SIGKILL);
+    }
+}
+
 fn wait_for_exit(pid: Pid, timeout: Duration) -> Result<bool, Errno> {
     let deadline = std::time::Instant::now() + timeout;
     loop {
@@ -3,7 +3,7 @@
 use std::collections::HashSet;
 use std::process::Command;
 
-use crate::model::{AppState, ProcessInfo};
+use crate::model::{AppState, ProcessInfo, PublicTunnel};
 use crate::utils::find_command;
 
 const BUNDLE_ID: &str = "com.samarthgupta.portkiller";
@@ -20,41 +20,88 @@ pub fn maybe_notify_changes(state: &AppState, prev: &[ProcessInfo]) {
     let prev_ports: HashSet<u16> = prev.iter().map(|p| p.port).collect();
     let curr_ports: HashSet<u16> = state.processes.iter().map(|p| p.port).collect();
 
+    // Ranges with `notify: false` stay quiet
+    let wants_notification = |port: &u16| {
+        state
+            .config
+            .monitoring
+            .range_for(*port)
+            .is_none_or(|range| range.notify)
+    };
+
     // Notify for added ports
-    let added: Vec<u16> = curr_ports.difference(&prev_ports).copied().collect();
+    let added: Vec<u16> = curr_ports
+        .difference(&prev_ports)
+        .copied()
+        .filter(wants_notification)
+        .collect();
     for port in added {
-        if let Some(process) = state.processes.iter().find(|p| p.port == port) {
+        if let Some(process) = state
+            .processes
+            .iter()
+            .find(|p| p.port == port && !state.is_hidden(p))
+        {
             let (title, body) = format_notification(port, process, state, true);
             notify(&title, &body);
         }
     }
 
     // Notify for removed ports
-    let removed: Vec<u16> = prev_ports.difference(&curr_ports).copied().collect();
+    let removed: Vec<u16> = prev_ports
+        .difference(&curr_ports)
+        .copied()
+        .filter(wants_notification)
+        .collect();
     for port in removed {
-        if let Some(process) = prev.iter().find(|p| p.port == port) {
+        if let Some(process) = prev.iter().find(|p| p.port == port && !state.is_hidden(p)) {
             let (title, body) = format_notification(port, process, state, false);
             notify(&title, &body);
         }
     }
 }
 
+/// Warn when a tunnel starts exposing a local port to the internet.
+pub fn maybe_notify_tunnels(state: &AppState, prev: &[PublicTunnel]) {
+    if !state.config.notifications.enabled {
+        return;
+    }
+    for tunnel in &state.public_tunnels {
+        if prev
+            .iter()
+            .any(|p| p.port == tunnel.port && p.provider == tunnel.provider)
+        {
+            continue;
+        }
+        let title = format!("Port {} Is Public", tunnel.port);
+        let body = match &tunnel.url {
+            Some(url) => format!("Exposed by {} at {}", tunnel.provider, url),
+            None => format!("Exposed by {}", tunnel.provider),
+        };
+        notify(&title, &body);
+    }
+}
+
 fn format_notification(
     port: u16,
     process: &ProcessInfo,
     state: &AppState,
     is_start: bool,
 ) -> (String, String) {
-    let title = if is_start {
-        format!("Port {} Started", port)
-    } else {
-        format!("Port {} Stopped", port)
+    let event = if is_start { "Started" } else { "Stopped" };
+    let title = match state
+        .config
+        .monitoring
+        .range_for(port)
+        .and_then(|range| range.label())
+    {
+        Some(name) => format!("{} Port {} {}", name, port, event),
+        None => format!("Port {} {}", port, event),
     };
 
-    let command = truncate_command(&process.command, 40);
+    let command = truncate_command(&state.describe(process), 40);
 
-    let body = if let Some(project) = state.project_cache.get(&process.pid) {
-        format!("{} ({}) • {}", command, process.pid, project.name)
+    let body = if let Some(project) = state.project_cache.get(&process.identity()) {
+        format!("{} ({}) • {}", command, process.pid, project.label())
     } else {
         format!("{} ({})", command, process.pid)
     };
@@ -1,17 +1,19 @@
 //! macOS port scanning implementation using lsof
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use std::process::Command;
+use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow};
 
 use crate::model::ProcessInfo;
+use crate::port_set::PortSet;
+use crate::utils::output_with_timeout;
 
-pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
-    fn in_ranges(port: u16, ranges: &[(u16, u16)]) -> bool {
-        ranges.iter().any(|(s, e)| port >= *s && port <= *e)
-    }
+const PS_TIMEOUT: Duration = Duration::from_secs(5);
 
+pub fn scan_ports(ports: &PortSet) -> Result<Vec<ProcessInfo>> {
     let output = Command::new("lsof")
         .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-FpcnPT"])
         .output()
@@ -46,13 +48,15 @@ pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
             "n" => {
                 if let (Some(pid), Some(cmd)) = (current_pid, current_cmd.as_ref())
                     && let Some(port) = parse_port_from_lsof(val.trim())
-                    && in_ranges(port, port_ranges)
+                    && ports.contains(port)
                     && seen.insert((port, pid))
                 {
                     results.push(ProcessInfo {
                         port,
                         pid,
                         command: cmd.clone(),
+                        start_time: None,
+                        address: parse_address_from_lsof(val.trim()),
                     });
                 }
             }
@@ -60,6 +64,12 @@ pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
         }
     }
 
+    let pids: Vec<i32> = results.iter().map(|p| p.pid).collect();
+    let start_times = process_start_times(&pids);
+    for process in &mut results {
+        process.start_time = start_times.get(&process.pid).copied();
+    }
+
     results.sort();
     Ok(results)
 }
@@ -89,6 +99,273 @@ pub fn verify_pid_is_listener(pid: i32) -> bool {
     }
 }
 
+/// Working directory of a process, if it can be read.
+pub fn process_cwd(pid: i32) -> Option<std::path::PathBuf> {
+    let out = Command::new("lsof")
+        .args(["-a", "-p", &pid.to_string(), "-d", "cwd", "-Fn"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(std::path::PathBuf::from)
+}
+
+/// PID of the process that started `pid`.
+pub fn parent_pid(pid: i32) -> Option<i32> {
+    ps_field(pid, "ppid=")?.parse().ok()
+}
+
+/// Full path of a process's executable.
+pub fn process_executable(pid: i32) -> Option<std::path::PathBuf> {
+    ps_field(pid, "comm=").map(std::path::PathBuf::from)
+}
+
+/// Account that owns a process.
+pub fn process_user(pid: i32) -> Option<String> {
+    ps_field(pid, "user=")
+}
+
+/// Argv of a process, read with `sysctl(KERN_PROCARGS2)` so arguments that
+/// contain spaces stay whole. Only readable for our own user's processes.
+pub fn process_argv(pid: i32) -> Option<Vec<String>> {
+    let argv = parse_procargs(&procargs(pid)?)?;
+    (!argv.is_empty()).then_some(argv)
+}
+
+/// The raw `KERN_PROCARGS2` buffer of a process.
+fn procargs(pid: i32) -> Option<Vec<u8>> {
+    use nix::libc;
+
+    let mut argmax: libc::c_int = 0;
+    let mut size = std::mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            &mut argmax as *mut libc::c_int as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 || argmax <= 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u8; argmax as usize];
+    let mut size = buffer.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+    buffer.truncate(size);
+    Some(buffer)
+}
+
+/// `argc`, the executable path and its NUL padding, then `argc` NUL-terminated
+/// arguments (followed by the environment, which isn't needed here).
+fn parse_procargs(buffer: &[u8]) -> Option<Vec<String>> {
+    let argc = i32::from_ne_bytes(buffer.get(..4)?.try_into().ok()?);
+    let rest = buffer.get(4..)?;
+    let exec_end = rest.iter().position(|&byte| byte == 0)?;
+    let mut strings = rest[exec_end..]
+        .split(|&byte| byte == 0)
+        .skip_while(|part| part.is_empty());
+    Some(
+        (0..argc)
+            .map_while(|_| strings.next())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+/// Environment of a process, from `ps -E` (only readable for our own user's processes).
+pub fn process_environment(pid: i32) -> Option<Vec<(String, String)>> {
+    let args = ps_output(pid, &["-ww", "-o", "args="])?;
+    let with_env = ps_output(pid, &["-ww", "-E", "-o", "args="])?;
+    let env = with_env.strip_prefix(args.as_str())?;
+    Some(parse_env_words(env))
+}
+
+/// Split `KEY=value KEY=value with spaces` back into pairs: a word that doesn't
+/// look like `KEY=` continues the previous value.
+fn parse_env_words(env: &str) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for word in env.split(' ') {
+        match word.split_once('=') {
+            Some((key, value))
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                vars.push((key.to_string(), value.to_string()));
+            }
+            _ => {
+                if let Some((_, value)) = vars.last_mut() {
+                    value.push(' ');
+                    value.push_str(word);
+                }
+            }
+        }
+    }
+    vars
+}
+
+fn ps_output(pid: i32, args: &[&str]) -> Option<String> {
+    let output = output_with_timeout(
+        Command::new("ps").args(args).args(["-p", &pid.to_string()]),
+        PS_TIMEOUT,
+    )
+    .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+fn ps_field(pid: i32, field: &str) -> Option<String> {
+    let value = ps_output(pid, &["-o", field])?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Parent PID of every running process, from a single `ps` run.
+pub fn process_parents() -> Result<HashMap<i32, i32>> {
+    let output = output_with_timeout(
+        Command::new("ps").args(["-ax", "-o", "pid=,ppid="]),
+        PS_TIMEOUT,
+    )
+    .context("failed to execute ps")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ps failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+        })
+        .collect())
+}
+
+/// Start time of a process, used to tell a reused PID apart.
+pub fn process_start_time(pid: i32) -> Option<u64> {
+    process_start_times(&[pid]).get(&pid).copied()
+}
+
+/// Start times of several processes from one `ps` call, as `YYYYMMDDhhmmss`.
+fn process_start_times(pids: &[i32]) -> HashMap<i32, u64> {
+    if pids.is_empty() {
+        return HashMap::new();
+    }
+    let pid_list = pids
+        .iter()
+        .map(|pid| pid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let output = output_with_timeout(
+        Command::new("ps")
+            .env("LC_ALL", "C")
+            .args(["-o", "pid=,lstart=", "-p", &pid_list]),
+        PS_TIMEOUT,
+    );
+    // ps exits non-zero when some of the PIDs are gone; the rest are still listed
+    let Ok(output) = output else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, lstart) = line.trim().split_once(' ')?;
+            Some((pid.parse().ok()?, parse_lstart(lstart)?))
+        })
+        .collect()
+}
+
+/// `Sat Oct 18 10:02:03 2026` → `20261018100203`
+fn parse_lstart(lstart: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let fields: Vec<&str> = lstart.split_whitespace().collect();
+    let [_, month, day, time, year] = fields.as_slice() else {
+        return None;
+    };
+    let month = MONTHS.iter().position(|m| m == month)? as u64 + 1;
+    let mut clock = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+    let date = year.parse::<u64>().ok()? * 10_000 + month * 100 + day.parse::<u64>().ok()?;
+    Some(date * 1_000_000 + hour * 10_000 + minute * 100 + second)
+}
+
+/// PID and argv of every running process whose executable is named `name`
+/// (a trailing `*` matches a prefix, e.g. `qemu-system-*`).
+/// `ps` joins argv with spaces, so arguments that contain spaces come back split.
+pub fn command_lines_for(name: &str) -> Result<Vec<(i32, Vec<String>)>> {
+    Ok(command_lines_for_each(&[name])?
+        .into_iter()
+        .map(|(_, pid, argv)| (pid, argv))
+        .collect())
+}
+
+/// [`command_lines_for`] for several names from a single `ps` run, each
+/// process tagged with the name it matched.
+pub fn command_lines_for_each<'a>(names: &[&'a str]) -> Result<Vec<(&'a str, i32, Vec<String>)>> {
+    let output = output_with_timeout(
+        Command::new("ps").args(["-axww", "-o", "pid=,args="]),
+        PS_TIMEOUT,
+    )
+    .context("failed to execute ps")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ps failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse::<i32>().ok()?;
+            let argv: Vec<String> = parts.map(str::to_string).collect();
+            let program = argv.first()?.rsplit('/').next()?;
+            let name = names.iter().find(|name| program_matches(program, name))?;
+            Some((*name, pid, argv))
+        })
+        .collect())
+}
+
+fn program_matches(program: &str, name: &str) -> bool {
+    match name.strip_suffix('*') {
+        Some(prefix) => program.starts_with(prefix),
+        None => program == name,
+    }
+}
+
+// Extract the bound address from an lsof name field; `None` for "*".
+pub fn parse_address_from_lsof(name: &str) -> Option<IpAddr> {
+    let (host, _) = name.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    // Link-local IPv6 carries a zone, e.g. "fe80::1%lo0"
+    host.split('%').next()?.parse().ok()
+}
+
 // Extract a port number from an lsof name field.
 // Handles "*:3000", "127.0.0.1:5173", and "[::1]:8000".
 pub fn parse_port_from_lsof(name: &str) -> Option<u16> {
@@ -117,7 +394,51 @@ pub fn parse_port_from_lsof(name: &str) -> Option<u16> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_port_from_lsof;
+    use super::{
+        parse_address_from_lsof, parse_env_words, parse_lstart, parse_port_from_lsof,
+        parse_procargs,
+    };
+
+    #[test]
+    fn parses_ps_environment() {
+        assert_eq!(
+            parse_env_words(" PATH=/usr/bin:/bin GREETING=hello world NODE_ENV=development"),
+            vec![
+                ("PATH".to_string(), "/usr/bin:/bin".to_string()),
+                ("GREETING".to_string(), "hello world".to_string()),
+                ("NODE_ENV".to_string(), "development".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_procargs_buffer() {
+        let mut buffer = 2i32.to_ne_bytes().to_vec();
+        buffer.extend_from_slice(
+            b"/usr/local/bin/node\0\0\0\0node\0/Users/me/My App/server.js\0PATH=/bin\0",
+        );
+        assert_eq!(
+            parse_procargs(&buffer),
+            Some(vec![
+                "node".to_string(),
+                "/Users/me/My App/server.js".to_string()
+            ])
+        );
+        assert_eq!(parse_procargs(b"\x01"), None);
+    }
+
+    #[test]
+    fn parses_process_start_time() {
+        assert_eq!(
+            parse_lstart("Sat Oct 18 10:02:03 2026"),
+            Some(20261018100203)
+        );
+        assert_eq!(
+            parse_lstart("Mon Feb  2 09:00:00 2026"),
+            Some(20260202090000)
+        );
+        assert_eq!(parse_lstart("garbage"), None);
+    }
 
     #[test]
     fn parses_ipv4_wildcard() {
@@ -134,6 +455,19 @@ mod tests {
         assert_eq!(parse_port_from_lsof("[::1]:8000"), Some(8000));
     }
 
+    #[test]
+    fn parses_bound_address() {
+        assert_eq!(parse_address_from_lsof("*:3000"), None);
+        assert_eq!(
+            parse_address_from_lsof("192.168.1.20:5173"),
+            Some([192, 168, 1, 20].into())
+        );
+        assert_eq!(
+            parse_address_from_lsof("[::1]:8000"),
+            Some(std::net::Ipv6Addr::LOCALHOST.into())
+        );
+    }
+
     #[test]
     fn rejects_non_listen_or_flow() {
         assert_eq!(parse_port_from_lsof("127.0.0.1:abcd"), None);
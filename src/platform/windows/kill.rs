@@ -4,6 +4,9 @@ use std::thread;
 use std::time::Duration;
 
 use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+use windows::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject,
+};
 use windows::Win32::System::Threading::{
     OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE, TerminateProcess,
     WaitForSingleObject,
@@ -17,6 +20,15 @@ const FORCE_TIMEOUT: Duration = Duration::from_secs(1);
 const POLL_STEP: Duration = Duration::from_millis(200);
 
 pub fn terminate_pid(pid: i32) -> KillOutcome {
+    terminate(pid, GRACEFUL_TIMEOUT)
+}
+
+/// Terminate immediately, without giving the process a chance to exit on its own.
+pub fn force_kill_pid(pid: i32) -> KillOutcome {
+    terminate(pid, Duration::ZERO)
+}
+
+fn terminate(pid: i32, graceful_timeout: Duration) -> KillOutcome {
     // TOCTOU mitigation: verify PID is still a TCP listener before killing
     if !verify_pid_is_listener(pid) {
         log::warn!(
@@ -52,7 +64,7 @@ pub fn terminate_pid(pid: i32) -> KillOutcome {
         // Try to close gracefully first by waiting a bit
         // Console apps don't have message queues, so we just wait briefly
         // This gives apps a chance to handle their cleanup if they're monitoring for termination
-        if wait_for_exit(handle, GRACEFUL_TIMEOUT) {
+        if wait_for_exit(handle, graceful_timeout) {
             let _ = CloseHandle(handle);
             return KillOutcome::Success;
         }
@@ -83,6 +95,62 @@ pub fn terminate_pid(pid: i32) -> KillOutcome {
     }
 }
 
+/// A child launched with `detached_command` and everything it spawns, held
+/// together by a job object. Processes the child starts before it is
+/// assigned (within microseconds of spawning) are not covered.
+pub struct ProcessTree {
+    job: Option<HANDLE>,
+}
+
+// SAFETY: the job handle is owned by this value and only used through it
+unsafe impl Send for ProcessTree {}
+
+impl ProcessTree {
+    pub fn of(child: &std::process::Child) -> Self {
+        use std::os::windows::io::AsRawHandle;
+
+        // SAFETY: the child handle stays valid while `child` is borrowed, and
+        // the job handle is closed on failure or when the tree is dropped
+        let job = unsafe {
+            CreateJobObjectW(None, windows::core::PCWSTR::null())
+                .ok()
+                .and_then(|job| {
+                    match AssignProcessToJobObject(job, HANDLE(child.as_raw_handle() as _)) {
+                        Ok(()) => Some(job),
+                        Err(err) => {
+                            log::warn!("Failed to put PID {} in a job: {}", child.id(), err);
+                            let _ = CloseHandle(job);
+                            None
+                        }
+                    }
+                })
+        };
+        Self { job }
+    }
+
+    /// Windows has no polite signal for windowless processes; listeners are
+    /// stopped one by one before the tree is killed.
+    pub fn terminate(&self) {}
+
+    /// Terminate every process in the job.
+    pub fn kill(&self) {
+        if let Some(job) = self.job {
+            // SAFETY: job is a valid handle owned by self
+            let _ = unsafe { TerminateJobObject(job, 1) };
+        }
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        if let Some(job) = self.job.take() {
+            // Without JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE the processes keep running
+            // SAFETY: job is a valid handle owned by self
+            let _ = unsafe { CloseHandle(job) };
+        }
+    }
+}
+
 /// Wait for process to exit
 unsafe fn wait_for_exit(handle: HANDLE, timeout: Duration) -> bool {
     let deadline = std::time::Instant::now() + timeout;
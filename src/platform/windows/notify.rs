@@ -5,7 +5,7 @@ use std::env;
 
 use crate::utils::hidden_command;
 
-use crate::model::{AppState, ProcessInfo};
+use crate::model::{AppState, ProcessInfo, PublicTunnel};
 
 pub fn notify_startup() {
     let title = "PortKiller Started";
@@ -21,31 +21,86 @@ pub fn maybe_notify_changes(state: &AppState, prev: &[ProcessInfo]) {
     let prev_ports: HashSet<u16> = prev.iter().map(|p| p.port).collect();
     let curr_ports: HashSet<u16> = state.processes.iter().map(|p| p.port).collect();
 
+    // Ranges with `notify: false` stay quiet
+    let wants_notification = |port: &u16| {
+        state
+            .config
+            .monitoring
+            .range_for(*port)
+            .is_none_or(|range| range.notify)
+    };
+
     // Notify for added ports
-    let added: Vec<u16> = curr_ports.difference(&prev_ports).copied().collect();
+    let added: Vec<u16> = curr_ports
+        .difference(&prev_ports)
+        .copied()
+        .filter(wants_notification)
+        .collect();
     for port in added {
-        if let Some(process) = state.processes.iter().find(|p| p.port == port) {
-            let title = format!("Port {} Started", port);
+        if let Some(process) = state
+            .processes
+            .iter()
+            .find(|p| p.port == port && !state.is_hidden(p))
+        {
+            let title = format_title(port, "Started", state);
             let body = format_body(process, state);
             show_toast(&title, &body);
         }
     }
 
     // Notify for removed ports
-    let removed: Vec<u16> = prev_ports.difference(&curr_ports).copied().collect();
+    let removed: Vec<u16> = prev_ports
+        .difference(&curr_ports)
+        .copied()
+        .filter(wants_notification)
+        .collect();
     for port in removed {
-        if let Some(process) = prev.iter().find(|p| p.port == port) {
-            let title = format!("Port {} Stopped", port);
+        if let Some(process) = prev.iter().find(|p| p.port == port && !state.is_hidden(p)) {
+            let title = format_title(port, "Stopped", state);
             let body = format_body(process, state);
             show_toast(&title, &body);
         }
     }
 }
 
+/// Warn when a tunnel starts exposing a local port to the internet.
+pub fn maybe_notify_tunnels(state: &AppState, prev: &[PublicTunnel]) {
+    if !state.config.notifications.enabled {
+        return;
+    }
+    for tunnel in &state.public_tunnels {
+        if prev
+            .iter()
+            .any(|p| p.port == tunnel.port && p.provider == tunnel.provider)
+        {
+            continue;
+        }
+        let title = format!("Port {} Is Public", tunnel.port);
+        let body = match &tunnel.url {
+            Some(url) => format!("Exposed by {} at {}", tunnel.provider, url),
+            None => format!("Exposed by {}", tunnel.provider),
+        };
+        show_toast(&title, &body);
+    }
+}
+
+/// "Frontend Port 3000 Started" when the port is in a named range.
+fn format_title(port: u16, event: &str, state: &AppState) -> String {
+    match state
+        .config
+        .monitoring
+        .range_for(port)
+        .and_then(|range| range.label())
+    {
+        Some(name) => format!("{} Port {} {}", name, port, event),
+        None => format!("Port {} {}", port, event),
+    }
+}
+
 fn format_body(process: &ProcessInfo, state: &AppState) -> String {
-    let command = truncate(&process.command, 40);
-    if let Some(project) = state.project_cache.get(&process.pid) {
-        format!("{} ({}) • {}", command, process.pid, project.name)
+    let command = truncate(&state.describe(process), 40);
+    if let Some(project) = state.project_cache.get(&process.identity()) {
+        format!("{} ({}) • {}", command, process.pid, project.label())
     } else {
         format!("{} ({})", command, process.pid)
     }
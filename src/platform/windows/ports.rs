@@ -1,18 +1,15 @@
 //! Windows port scanning implementation using netstat
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::utils::hidden_command;
 
 use anyhow::{Context, Result, anyhow};
 
 use crate::model::ProcessInfo;
+use crate::port_set::PortSet;
 
-pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
-    fn in_ranges(port: u16, ranges: &[(u16, u16)]) -> bool {
-        ranges.iter().any(|(s, e)| port >= *s && port <= *e)
-    }
-
+pub fn scan_ports(ports: &PortSet) -> Result<Vec<ProcessInfo>> {
     // Run netstat to get listening ports (hidden to prevent console flicker)
     let output = hidden_command("netstat")
         .args(["-ano", "-p", "TCP"])
@@ -51,7 +48,7 @@ pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
             None => continue,
         };
 
-        if !in_ranges(port, port_ranges) {
+        if !ports.contains(port) {
             continue;
         }
 
@@ -73,13 +70,26 @@ pub fn scan_ports(port_ranges: &[(u16, u16)]) -> Result<Vec<ProcessInfo>> {
         // Get process name from PID
         let command = get_process_name(pid as u32).unwrap_or_else(|| format!("PID {}", pid));
 
-        results.push(ProcessInfo { port, pid, command });
+        results.push(ProcessInfo {
+            port,
+            pid,
+            command,
+            start_time: process_start_time(pid),
+            address: parse_ip_from_address(parts[1]),
+        });
     }
 
     results.sort();
     Ok(results)
 }
 
+/// Parse the IP from an address like "0.0.0.0:3000" or "[fe80::1%4]:3000"
+fn parse_ip_from_address(addr: &str) -> Option<std::net::IpAddr> {
+    let (host, _) = addr.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.split('%').next()?.parse().ok()
+}
+
 /// Parse port from address like "0.0.0.0:3000" or "[::]:3000" or "127.0.0.1:8080"
 fn parse_port_from_address(addr: &str) -> Option<u16> {
     // Handle IPv6 format like "[::]:3000" or "[::1]:3000"
@@ -126,6 +136,373 @@ fn get_process_name(pid: u32) -> Option<String> {
     }
 }
 
+/// Creation time of a process in 100ns ticks, used to tell a reused PID apart.
+pub fn process_start_time(pid: i32) -> Option<u64> {
+    use windows::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows::Win32::System::Threading::{
+        GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid as u32).ok()?;
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        let result = GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+        Some(((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64)
+    }
+}
+
+/// Current directory of a process, read from its PEB
+/// (`RTL_USER_PROCESS_PARAMETERS.CurrentDirectory`).
+pub fn process_cwd(pid: i32) -> Option<std::path::PathBuf> {
+    let cwd = with_process_parameters(pid, |read, params| {
+        read_unicode_string(read, params + PARAMS_CURRENT_DIRECTORY)
+    })?;
+    // Directories other than a drive root keep a trailing backslash
+    let cwd = match cwd.strip_suffix('\\') {
+        Some(trimmed) if !trimmed.ends_with(':') => trimmed.to_string(),
+        _ => cwd,
+    };
+    Some(std::path::PathBuf::from(cwd))
+}
+
+/// Argv of a process, split from the command line in its PEB.
+pub fn process_argv(pid: i32) -> Option<Vec<String>> {
+    let line = with_process_parameters(pid, |read, params| {
+        read_unicode_string(read, params + PARAMS_COMMAND_LINE)
+    })?;
+    let argv = split_command_line(&line);
+    (!argv.is_empty()).then_some(argv)
+}
+
+/// Environment block of a process, read from its PEB.
+pub fn process_environment(pid: i32) -> Option<Vec<(String, String)>> {
+    let wide = with_process_parameters(pid, |read, params| {
+        let mut pointer = [0u8; 8];
+        read(params + PARAMS_ENVIRONMENT, &mut pointer)?;
+        let mut size = [0u8; 8];
+        read(params + PARAMS_ENVIRONMENT_SIZE, &mut size)?;
+        // Cap the read so a corrupt size can't trigger a huge allocation
+        let size = (u64::from_le_bytes(size) as usize).min(1 << 20);
+        let mut raw = vec![0u8; size];
+        read(u64::from_le_bytes(pointer) as usize, &mut raw)?;
+        Some(
+            raw.chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<u16>>(),
+        )
+    })?;
+
+    // "KEY=value\0KEY=value\0\0"; entries like "=C:=C:\dir" are per-drive cwds
+    Some(
+        wide.split(|&unit| unit == 0)
+            .take_while(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let entry = String::from_utf16_lossy(entry);
+                let (key, value) = entry.split_once('=')?;
+                (!key.is_empty()).then(|| (key.to_string(), value.to_string()))
+            })
+            .collect(),
+    )
+}
+
+// Offsets into the 64-bit PEB and RTL_USER_PROCESS_PARAMETERS
+const PEB_PROCESS_PARAMETERS: usize = 0x20;
+const PARAMS_CURRENT_DIRECTORY: usize = 0x38;
+const PARAMS_COMMAND_LINE: usize = 0x70;
+const PARAMS_ENVIRONMENT: usize = 0x80;
+const PARAMS_ENVIRONMENT_SIZE: usize = 0x3F0;
+
+/// Run `query` with a memory reader for the process and the address of its
+/// `RTL_USER_PROCESS_PARAMETERS`. 64-bit layout only.
+#[cfg(target_pointer_width = "64")]
+fn with_process_parameters<T>(
+    pid: i32,
+    query: impl FnOnce(&dyn Fn(usize, &mut [u8]) -> Option<()>, usize) -> Option<T>,
+) -> Option<T> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    use windows::Win32::System::Threading::IsWow64Process;
+
+    with_process(pid, |handle| {
+        // 32-bit processes keep their live parameters in a separate 32-bit PEB
+        let mut wow64 = BOOL::default();
+        if unsafe { IsWow64Process(handle, &mut wow64) }.is_err() || wow64.as_bool() {
+            return None;
+        }
+        let read = |address: usize, buffer: &mut [u8]| unsafe {
+            ReadProcessMemory(
+                handle,
+                address as *const c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len(),
+                None,
+            )
+            .ok()
+        };
+        let info = unsafe { basic_information(handle)? };
+        let mut pointer = [0u8; 8];
+        read(
+            info.PebBaseAddress as usize + PEB_PROCESS_PARAMETERS,
+            &mut pointer,
+        )?;
+        query(&read, u64::from_le_bytes(pointer) as usize)
+    })
+}
+
+/// The offsets above don't apply to a 32-bit build.
+#[cfg(not(target_pointer_width = "64"))]
+fn with_process_parameters<T>(
+    _pid: i32,
+    _query: impl FnOnce(&dyn Fn(usize, &mut [u8]) -> Option<()>, usize) -> Option<T>,
+) -> Option<T> {
+    None
+}
+
+/// Read a `UNICODE_STRING { Length: u16, MaximumLength: u16, padding, Buffer: *const u16 }`.
+fn read_unicode_string(
+    read: &dyn Fn(usize, &mut [u8]) -> Option<()>,
+    address: usize,
+) -> Option<String> {
+    let mut unicode = [0u8; 16];
+    read(address, &mut unicode)?;
+    let length = u16::from_le_bytes([unicode[0], unicode[1]]) as usize;
+    let buffer = u64::from_le_bytes(unicode[8..16].try_into().ok()?) as usize;
+    let mut raw = vec![0u8; length];
+    read(buffer, &mut raw)?;
+    let wide: Vec<u16> = raw
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&wide))
+}
+
+/// PID of the process that started `pid`.
+pub fn parent_pid(pid: i32) -> Option<i32> {
+    with_process(pid, |handle| {
+        let info = unsafe { basic_information(handle)? };
+        i32::try_from(info.InheritedFromUniqueProcessId).ok()
+    })
+}
+
+/// Full path of a process's executable.
+pub fn process_executable(pid: i32) -> Option<std::path::PathBuf> {
+    use windows::Win32::System::Threading::{PROCESS_NAME_WIN32, QueryFullProcessImageNameW};
+    use windows::core::PWSTR;
+
+    with_process(pid, |handle| unsafe {
+        let mut name = [0u16; 1024];
+        let mut len = name.len() as u32;
+        QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(name.as_mut_ptr()),
+            &mut len,
+        )
+        .ok()?;
+        Some(std::path::PathBuf::from(String::from_utf16_lossy(
+            &name[..len as usize],
+        )))
+    })
+}
+
+/// Account that owns a process, e.g. `SYSTEM` or `LOCAL SERVICE`.
+pub fn process_user(pid: i32) -> Option<String> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        GetTokenInformation, LookupAccountSidW, SID_NAME_USE, TOKEN_QUERY, TOKEN_USER, TokenUser,
+    };
+    use windows::Win32::System::Threading::{
+        OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::core::{PCWSTR, PWSTR};
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid as u32).ok()?;
+        let mut token = HANDLE::default();
+        let opened = OpenProcessToken(process, TOKEN_QUERY, &mut token);
+        let _ = CloseHandle(process);
+        opened.ok()?;
+
+        // u64s keep the TOKEN_USER pointer-aligned
+        let mut buffer = [0u64; 64];
+        let mut len = 0u32;
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            std::mem::size_of_val(&buffer) as u32,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.ok()?;
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+
+        let mut name = [0u16; 256];
+        let mut name_len = name.len() as u32;
+        let mut domain = [0u16; 256];
+        let mut domain_len = domain.len() as u32;
+        let mut kind = SID_NAME_USE::default();
+        LookupAccountSidW(
+            PCWSTR::null(),
+            user.User.Sid,
+            PWSTR(name.as_mut_ptr()),
+            &mut name_len,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_len,
+            &mut kind,
+        )
+        .ok()?;
+        Some(String::from_utf16_lossy(&name[..name_len as usize]))
+    }
+}
+
+/// Run `query` against a process handle opened for reading, closing it afterwards.
+fn with_process<T>(
+    pid: i32,
+    query: impl FnOnce(windows::Win32::Foundation::HANDLE) -> Option<T>,
+) -> Option<T> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
+    };
+
+    unsafe {
+        let handle = OpenProcess(
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
+            false,
+            pid as u32,
+        )
+        .ok()?;
+        let result = query(handle);
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+unsafe fn basic_information(
+    handle: windows::Win32::Foundation::HANDLE,
+) -> Option<windows::Win32::System::Threading::PROCESS_BASIC_INFORMATION> {
+    use windows::Wdk::System::Threading::{NtQueryInformationProcess, ProcessBasicInformation};
+    use windows::Win32::System::Threading::PROCESS_BASIC_INFORMATION;
+
+    let mut info = PROCESS_BASIC_INFORMATION::default();
+    let status = unsafe {
+        NtQueryInformationProcess(
+            handle,
+            ProcessBasicInformation,
+            &mut info as *mut _ as *mut std::ffi::c_void,
+            std::mem::size_of::<PROCESS_BASIC_INFORMATION>() as u32,
+            std::ptr::null_mut(),
+        )
+    };
+    status.is_ok().then_some(info)
+}
+
+/// PID and argv of every running process whose executable is `<name>.exe`
+/// (a trailing `*` matches a prefix, e.g. `qemu-system-*`).
+/// Processes whose memory can't be read, such as other users', protected or
+/// 32-bit ones, are left out.
+pub fn command_lines_for(name: &str) -> Result<Vec<(i32, Vec<String>)>> {
+    Ok(command_lines_for_each(&[name])?
+        .into_iter()
+        .map(|(_, pid, argv)| (pid, argv))
+        .collect())
+}
+
+/// [`command_lines_for`] for several names from a single process snapshot,
+/// each process tagged with the name it matched.
+pub fn command_lines_for_each<'a>(names: &[&'a str]) -> Result<Vec<(&'a str, i32, Vec<String>)>> {
+    Ok(process_snapshot()?
+        .into_iter()
+        .filter_map(|(pid, _, exe)| {
+            let exe = exe.to_ascii_lowercase();
+            let stem = exe.strip_suffix(".exe").unwrap_or(&exe);
+            let name = names.iter().find(|name| match name.strip_suffix('*') {
+                Some(prefix) => stem.starts_with(prefix),
+                None => stem == **name,
+            })?;
+            Some((*name, pid, process_argv(pid)?))
+        })
+        .collect())
+}
+
+/// Parent PID of every running process.
+pub fn process_parents() -> Result<HashMap<i32, i32>> {
+    Ok(process_snapshot()?
+        .into_iter()
+        .map(|(pid, parent, _)| (pid, parent))
+        .collect())
+}
+
+/// PID, parent PID and executable file name (`node.exe`) of every running process.
+fn process_snapshot() -> Result<Vec<(i32, i32, String)>> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
+        TH32CS_SNAPPROCESS,
+    };
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+            .context("failed to snapshot running processes")?;
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut processes = Vec::new();
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            let exe = &entry.szExeFile;
+            let len = exe.iter().position(|&unit| unit == 0).unwrap_or(exe.len());
+            processes.push((
+                entry.th32ProcessID as i32,
+                entry.th32ParentProcessID as i32,
+                String::from_utf16_lossy(&exe[..len]),
+            ));
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+        Ok(processes)
+    }
+}
+
+/// Split a command line on whitespace, honouring double quotes.
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
 /// Verify that a PID is still associated with a TCP listener.
 /// Used to mitigate TOCTOU race conditions before killing a process.
 pub fn verify_pid_is_listener(pid: i32) -> bool {
@@ -149,7 +526,7 @@ pub fn verify_pid_is_listener(pid: i32) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_port_from_address;
+    use super::{parse_port_from_address, split_command_line};
 
     #[test]
     fn parses_ipv4_any() {
@@ -170,4 +547,17 @@ mod tests {
     fn parses_ipv6_localhost() {
         assert_eq!(parse_port_from_address("[::1]:9000"), Some(9000));
     }
+
+    #[test]
+    fn splits_quoted_command_lines() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\kubectl.exe" port-forward  svc/api 8080:80"#),
+            vec![
+                r"C:\Program Files\kubectl.exe",
+                "port-forward",
+                "svc/api",
+                "8080:80"
+            ]
+        );
+    }
 }
@@ -0,0 +1,229 @@
+//! Sets of TCP ports written as `"3000-3010, 5173, 8000-8100, !8081"`
+//!
+//! Terms are single ports or inclusive ranges, separated by commas; a leading
+//! `!` excludes. `all` selects every port except [`SYSTEM_PORTS`], and ports
+//! listed after it are added back. Exclusions always win, in any position.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Left out of `all`: OS services that listen on every machine and are never dev servers.
+pub const SYSTEM_PORTS: &[(u16, u16)] = &[
+    (1, 1023),      // privileged ports: ssh, dns, smb, cups, …
+    (3389, 3389),   // Remote Desktop
+    (5000, 5000),   // AirPlay receiver on macOS
+    (5353, 5353),   // mDNS
+    (7000, 7000),   // AirPlay receiver on macOS
+    (49152, 65535), // ephemeral ports used by system RPC listeners
+];
+
+const WORDS: usize = (u16::MAX as usize + 1) / 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Term {
+    All,
+    Include(u16, u16),
+    Exclude(u16, u16),
+}
+
+/// A parsed port set with constant-time membership checks.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PortSet {
+    terms: Vec<Term>,
+    /// One bit per port
+    bits: Box<[u64]>,
+}
+
+impl PortSet {
+    /// Inclusive `(start, end)` ranges; callers check `start <= end`.
+    pub fn from_ranges(ranges: &[(u16, u16)]) -> Self {
+        Self::from_terms(
+            ranges
+                .iter()
+                .map(|&(start, end)| Term::Include(start, end))
+                .collect(),
+        )
+    }
+
+    fn from_terms(terms: Vec<Term>) -> Self {
+        let mut set = Self {
+            terms,
+            bits: vec![0; WORDS].into_boxed_slice(),
+        };
+        for term in set.terms.clone() {
+            match term {
+                Term::All => {
+                    set.fill(1, u16::MAX, true);
+                    for &(start, end) in SYSTEM_PORTS {
+                        set.fill(start, end, false);
+                    }
+                }
+                Term::Include(start, end) => set.fill(start, end, true),
+                Term::Exclude(..) => {}
+            }
+        }
+        for term in set.terms.clone() {
+            if let Term::Exclude(start, end) = term {
+                set.fill(start, end, false);
+            }
+        }
+        set
+    }
+
+    fn fill(&mut self, start: u16, end: u16, value: bool) {
+        for port in start..=end {
+            let (word, bit) = (port as usize / 64, port as usize % 64);
+            if value {
+                self.bits[word] |= 1 << bit;
+            } else {
+                self.bits[word] &= !(1 << bit);
+            }
+        }
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.bits[port as usize / 64] & (1 << (port as usize % 64)) != 0
+    }
+
+    /// Ports in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut terms = self.terms.clone();
+        terms.extend(other.terms.iter().copied());
+        let bits = self
+            .bits
+            .iter()
+            .zip(other.bits.iter())
+            .map(|(a, b)| a | b)
+            .collect();
+        // The terms can no longer be re-evaluated as one list (exclusions were per set)
+        Self { terms, bits }
+    }
+
+    /// The single `(start, end)` range this set was written as, if it's that simple.
+    pub fn as_range(&self) -> Option<(u16, u16)> {
+        match self.terms.as_slice() {
+            [Term::Include(start, end)] => Some((*start, *end)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for PortSet {
+    fn default() -> Self {
+        Self::from_terms(Vec::new())
+    }
+}
+
+impl FromStr for PortSet {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let port = |text: &str| {
+            text.trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|port| *port != 0)
+                .ok_or_else(|| format!("invalid port {:?} in {:?}", text.trim(), spec))
+        };
+        let mut terms = Vec::new();
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            if part.eq_ignore_ascii_case("all") {
+                terms.push(Term::All);
+                continue;
+            }
+            let (excluded, range) = match part.strip_prefix('!') {
+                Some(rest) => (true, rest.trim()),
+                None => (false, part),
+            };
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (port(start)?, port(end)?),
+                None => (port(range)?, port(range)?),
+            };
+            if start > end {
+                return Err(format!("invalid port range {:?}: start > end", range));
+            }
+            terms.push(if excluded {
+                Term::Exclude(start, end)
+            } else {
+                Term::Include(start, end)
+            });
+        }
+        if !terms
+            .iter()
+            .any(|term| matches!(term, Term::All | Term::Include(..)))
+        {
+            return Err(format!("no ports in {:?}", spec));
+        }
+        Ok(Self::from_terms(terms))
+    }
+}
+
+impl fmt::Display for PortSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| match *term {
+                Term::All => "all".to_string(),
+                Term::Include(start, end) if start == end => start.to_string(),
+                Term::Include(start, end) => format!("{}-{}", start, end),
+                Term::Exclude(start, end) if start == end => format!("!{}", start),
+                Term::Exclude(start, end) => format!("!{}-{}", start, end),
+            })
+            .collect();
+        f.write_str(&terms.join(", "))
+    }
+}
+
+impl fmt::Debug for PortSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PortSet({:?})", self.to_string())
+    }
+}
+
+impl Serialize for PortSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PortSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges_exclusions_and_all() {
+        let set: PortSet = "3000-3010, 5173, 8000-8100, !8081".parse().unwrap();
+        assert!(set.contains(3000) && set.contains(3010) && set.contains(5173));
+        assert!(set.contains(8080) && !set.contains(8081) && !set.contains(3011));
+        assert_eq!(set.to_string(), "3000-3010, 5173, 8000-8100, !8081");
+
+        // Exclusions win wherever they're written
+        let set: PortSet = "!3001, 3000-3002".parse().unwrap();
+        assert!(set.contains(3000) && !set.contains(3001));
+
+        let set: PortSet = "all, 5000, !9229".parse().unwrap();
+        assert!(set.contains(3000) && set.contains(5000));
+        assert!(!set.contains(22) && !set.contains(9229) && !set.contains(50000));
+
+        assert!("3010-3000".parse::<PortSet>().is_err());
+        assert!("http".parse::<PortSet>().is_err());
+        assert!("0".parse::<PortSet>().is_err());
+        assert!("!3000".parse::<PortSet>().is_err());
+        assert!(" , ".parse::<PortSet>().is_err());
+    }
+}
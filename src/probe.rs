@@ -0,0 +1,346 @@
+//! Listener health probing
+//!
+//! A bound port doesn't mean the server behind it is serving. Each probe
+//! connects over TCP to the address the socket is bound to. With
+//! `health.http` set it also checks whether the listener speaks TLS, and
+//! otherwise sends a plain HTTP `GET` to read the status code and page `<title>`.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::HealthConfig;
+
+/// How long a listener gets to answer a TLS ClientHello before it's assumed to be plain TCP.
+const TLS_PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+/// Enough of a page to find its `<title>`.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+/// Probes run at once; a hung listener holds its worker for the whole timeout.
+const PROBE_WORKERS: usize = 8;
+
+/// A port and the address it's bound to, if known.
+pub type Listener = (u16, Option<IpAddr>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthStatus {
+    Healthy,
+    /// Answered slower than `health.slow_ms`, or not at all before the timeout
+    Slow,
+    /// Nothing accepts connections on the listener's address
+    Refusing,
+    /// HTTP 5xx
+    ServerError(u16),
+}
+
+impl HealthStatus {
+    pub fn dot(self) -> &'static str {
+        match self {
+            Self::Healthy => "🟢",
+            Self::Slow => "🟡",
+            Self::ServerError(_) => "🟠",
+            Self::Refusing => "🔴",
+        }
+    }
+
+    /// Higher is worse; used to summarise a process with several ports.
+    pub fn severity(self) -> u8 {
+        match self {
+            Self::Healthy => 0,
+            Self::Slow => 1,
+            Self::ServerError(_) => 2,
+            Self::Refusing => 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListenerHealth {
+    pub status: HealthStatus,
+    pub tls: bool,
+    /// `<title>` of the page served at the probed path
+    pub title: Option<String>,
+}
+
+impl ListenerHealth {
+    pub fn scheme(&self) -> &'static str {
+        if self.tls { "https" } else { "http" }
+    }
+}
+
+/// Probe every listener, a few at a time.
+pub fn probe_ports(listeners: &[Listener], config: &HealthConfig) -> HashMap<u16, ListenerHealth> {
+    let queue = Mutex::new(listeners.iter());
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..PROBE_WORKERS.min(listeners.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    // The lock is released before probing
+                    while let Some(&(port, address)) =
+                        queue.lock().ok().and_then(|mut queue| queue.next())
+                    {
+                        results.push((port, probe_port(port, address, config)));
+                    }
+                    results
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().ok())
+            .flatten()
+            .collect()
+    })
+}
+
+pub fn probe_port(port: u16, address: Option<IpAddr>, config: &HealthConfig) -> ListenerHealth {
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let slow = Duration::from_millis(config.slow_ms);
+    let refusing = ListenerHealth {
+        status: HealthStatus::Refusing,
+        tls: false,
+        title: None,
+    };
+
+    let started = Instant::now();
+    let Some(addr) = connect(port, address, timeout).and_then(|s| s.peer_addr().ok()) else {
+        return refusing;
+    };
+    let connected_in = started.elapsed();
+    let by_latency = |latency: Duration| {
+        if latency > slow {
+            HealthStatus::Slow
+        } else {
+            HealthStatus::Healthy
+        }
+    };
+
+    if !config.http {
+        return ListenerHealth {
+            status: by_latency(connected_in),
+            tls: false,
+            title: None,
+        };
+    }
+
+    if speaks_tls(addr) {
+        return ListenerHealth {
+            status: by_latency(connected_in),
+            tls: true,
+            title: None,
+        };
+    }
+
+    let path = config
+        .paths
+        .get(&port)
+        .map(String::as_str)
+        .unwrap_or(&config.http_path);
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, timeout) else {
+        return refusing;
+    };
+    // Dev servers tend to reject unknown Host headers but always accept localhost
+    let host = if addr.ip().is_loopback() {
+        format!("localhost:{}", port)
+    } else {
+        addr.to_string()
+    };
+    let Some(response) = http_get(&mut stream, &host, path, timeout) else {
+        // Accepted the connection but never answered
+        return ListenerHealth {
+            status: HealthStatus::Slow,
+            tls: false,
+            title: None,
+        };
+    };
+    let latency = response.first_byte;
+    let text = String::from_utf8_lossy(&response.bytes);
+    match parse_status_code(&text) {
+        Some(code) if code >= 500 => ListenerHealth {
+            status: HealthStatus::ServerError(code),
+            tls: false,
+            title: None,
+        },
+        Some(_) => ListenerHealth {
+            status: by_latency(latency),
+            tls: false,
+            title: parse_title(&text),
+        },
+        // Not HTTP (a database, a cache): the TCP connect is all we can judge
+        None => ListenerHealth {
+            status: by_latency(connected_in),
+            tls: false,
+            title: None,
+        },
+    }
+}
+
+/// Connect to the bound address, or to loopback when bound to all interfaces.
+fn connect(port: u16, address: Option<IpAddr>, timeout: Duration) -> Option<TcpStream> {
+    let candidates = match address {
+        Some(ip) if !ip.is_unspecified() => vec![SocketAddr::new(ip, port)],
+        _ => vec![
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+        ],
+    };
+    candidates
+        .iter()
+        .find_map(|addr| TcpStream::connect_timeout(addr, timeout).ok())
+}
+
+/// Send a minimal TLS ClientHello: TLS servers answer with a handshake or alert record.
+fn speaks_tls(addr: SocketAddr) -> bool {
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, TLS_PROBE_TIMEOUT) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(TLS_PROBE_TIMEOUT));
+    if stream.write_all(&client_hello()).is_err() {
+        return false;
+    }
+    let mut header = [0u8; 2];
+    match stream.read_exact(&mut header) {
+        // 0x16 handshake or 0x15 alert, then the 0x03 major version
+        Ok(()) => matches!(header, [0x15 | 0x16, 0x03]),
+        Err(_) => false,
+    }
+}
+
+fn client_hello() -> Vec<u8> {
+    let mut body = vec![0x03, 0x03]; // TLS 1.2
+    body.extend_from_slice(&[0u8; 32]); // random
+    body.push(0); // no session id
+    let suites: [u16; 6] = [0x1301, 0x1302, 0xc02b, 0xc02f, 0xc02c, 0xc030];
+    body.extend_from_slice(&((suites.len() * 2) as u16).to_be_bytes());
+    for suite in suites {
+        body.extend_from_slice(&suite.to_be_bytes());
+    }
+    body.extend_from_slice(&[0x01, 0x00]); // null compression only
+
+    let mut handshake = vec![0x01]; // ClientHello
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(body);
+
+    let mut record = vec![0x16, 0x03, 0x01];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+pub struct HttpResponse {
+    pub bytes: Vec<u8>,
+    /// Time from sending the request to the first byte of the reply
+    pub first_byte: Duration,
+}
+
+/// Plain HTTP/1.0 `GET`, reading until the server closes the connection, the
+/// size cap or the timeout. `None` when the timeout passed without a reply.
+pub fn http_get(
+    stream: &mut TcpStream,
+    host: &str,
+    path: &str,
+    timeout: Duration,
+) -> Option<HttpResponse> {
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: text/html,*/*\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    let started = Instant::now();
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let deadline = started + timeout;
+    let mut bytes = Vec::new();
+    let mut first_byte = None;
+    let mut chunk = [0u8; 8192];
+    while bytes.len() < MAX_RESPONSE_BYTES {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || stream.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+        match stream.read(&mut chunk) {
+            // Closing without a reply is an answer too (non-HTTP servers do this)
+            Ok(0) => {
+                first_byte.get_or_insert_with(|| started.elapsed());
+                break;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionReset => {
+                first_byte.get_or_insert_with(|| started.elapsed());
+                break;
+            }
+            Err(_) => break,
+            Ok(read) => {
+                first_byte.get_or_insert_with(|| started.elapsed());
+                bytes.extend_from_slice(&chunk[..read]);
+            }
+        }
+    }
+    first_byte.map(|first_byte| HttpResponse { bytes, first_byte })
+}
+
+pub fn parse_status_code(head: &str) -> Option<u16> {
+    let rest = head.strip_prefix("HTTP/")?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Text of the first `<title>` element, with whitespace collapsed.
+fn parse_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'");
+    (!title.is_empty()).then(|| title.chars().take(60).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_page_titles() {
+        assert_eq!(
+            parse_title(
+                "HTTP/1.1 200 OK\r\n\r\n<html><head><TITLE data-x>\n  Vite &amp; React\n</TITLE>"
+            ),
+            Some("Vite & React".to_string())
+        );
+        assert_eq!(parse_title("<title></title>"), None);
+        assert_eq!(parse_title("{\"ok\": true}"), None);
+        assert_eq!(parse_status_code("HTTP/1.1 204 No"), Some(204));
+        assert_eq!(parse_status_code("garbage"), None);
+    }
+
+    #[test]
+    fn probes_local_http_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            // The connect check, the TLS check, then the GET
+            for stream in listener.incoming().take(3) {
+                let mut stream = stream.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream
+                    .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+        let config = HealthConfig {
+            http: true,
+            ..HealthConfig::default()
+        };
+        let health = probe_port(port, Some(Ipv4Addr::LOCALHOST.into()), &config);
+        assert_eq!(health.status, HealthStatus::ServerError(503));
+        assert!(!health.tls);
+    }
+}
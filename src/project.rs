@@ -0,0 +1,813 @@
+//! Project detection for listening processes
+//!
+//! A process's working directory is mapped to a project name by walking up to
+//! the nearest manifest (`package.json`, `Cargo.toml`, …) so monorepo packages
+//! and git worktrees get meaningful names instead of `apps` or `web`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{ProjectResolver, ProjectsConfig};
+use crate::model::{ProcessIdentity, ProcessInfo, ProjectInfo};
+use crate::process::ports::{parent_pid, process_cwd, process_executable};
+use crate::utils::expand_home;
+
+/// How often the branch and dirty state of known work trees are re-read.
+pub const GIT_STATUS_INTERVAL: Duration = Duration::from_secs(30);
+/// How long a process without a project (unsafe or unreadable cwd) is left alone.
+const NEGATIVE_RESULT_TTL: Duration = Duration::from_secs(120);
+/// How far up the process tree to look for a more telling working directory.
+const MAX_PARENT_HOPS: usize = 3;
+
+/// Projects of scanned processes, keyed by process identity so a reused PID
+/// never inherits another process's project.
+#[derive(Clone, Debug, Default)]
+pub struct ProjectCache {
+    entries: HashMap<ProcessIdentity, CachedProject>,
+}
+
+#[derive(Clone, Debug)]
+struct CachedProject {
+    info: Option<ProjectInfo>,
+    resolved_at: Instant,
+}
+
+impl ProjectCache {
+    pub fn get(&self, process: &ProcessIdentity) -> Option<&ProjectInfo> {
+        self.entries.get(process)?.info.as_ref()
+    }
+
+    /// Resolve processes that are new or whose negative result has expired.
+    /// Found projects are kept for the life of the process; their branch and
+    /// dirty state are refreshed separately by [`GitStatusReader`]. Returns
+    /// whether a newly resolved project is in a git work tree.
+    pub fn resolve_missing(&mut self, processes: &[ProcessInfo], config: &ProjectsConfig) -> bool {
+        let now = Instant::now();
+        let stale: HashSet<ProcessIdentity> = processes
+            .iter()
+            .map(ProcessInfo::identity)
+            .filter(|process| match self.entries.get(process) {
+                Some(CachedProject { info: Some(_), .. }) => false,
+                Some(CachedProject {
+                    info: None,
+                    resolved_at,
+                }) => now.duration_since(*resolved_at) >= NEGATIVE_RESULT_TTL,
+                None => true,
+            })
+            .collect();
+        let mut new_work_tree = false;
+        for process in stale {
+            let info = resolve_project_info(process.pid, config);
+            new_work_tree |= info.as_ref().is_some_and(|info| info.worktree.is_some());
+            self.entries.insert(
+                process,
+                CachedProject {
+                    info,
+                    resolved_at: now,
+                },
+            );
+        }
+        new_work_tree
+    }
+
+    /// Drop entries for processes that are no longer listening.
+    pub fn retain_active(&mut self, processes: &[ProcessInfo]) {
+        let active: HashSet<ProcessIdentity> =
+            processes.iter().map(ProcessInfo::identity).collect();
+        self.entries.retain(|process, _| active.contains(process));
+    }
+
+    /// Projects of the cached processes, one entry per process.
+    pub fn projects(&self) -> impl Iterator<Item = &ProjectInfo> {
+        self.entries
+            .values()
+            .filter_map(|entry| entry.info.as_ref())
+    }
+
+    /// Work trees of the cached projects, each once.
+    pub fn work_trees(&self) -> Vec<PathBuf> {
+        let mut work_trees: Vec<PathBuf> = self
+            .projects()
+            .filter_map(|info| info.worktree.clone())
+            .collect();
+        work_trees.sort();
+        work_trees.dedup();
+        work_trees
+    }
+
+    /// Take in git state read by [`GitStatusReader`]; returns whether any project changed.
+    pub fn apply_git_status(&mut self, statuses: &HashMap<PathBuf, GitStatus>) -> bool {
+        let mut changed = false;
+        for info in self.entries.values_mut().filter_map(|e| e.info.as_mut()) {
+            let Some(status) = info.worktree.as_ref().and_then(|tree| statuses.get(tree)) else {
+                continue;
+            };
+            if info.branch != status.branch || info.dirty != status.dirty {
+                info.branch = status.branch.clone();
+                info.dirty = status.dirty;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Branch and dirty state of a work tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    pub dirty: bool,
+}
+
+/// Reads [`GitStatus`] off the UI thread, remembering which work trees were
+/// dirty so an untouched index can skip the stat walk.
+#[derive(Debug, Default)]
+pub struct GitStatusReader {
+    /// Index mtime of each work tree last found dirty
+    dirty_at: HashMap<PathBuf, SystemTime>,
+}
+
+impl GitStatusReader {
+    pub fn read_all(&mut self, work_trees: &[PathBuf]) -> HashMap<PathBuf, GitStatus> {
+        self.dirty_at.retain(|tree, _| work_trees.contains(tree));
+        work_trees
+            .iter()
+            .filter_map(|tree| Some((tree.clone(), self.read(tree)?)))
+            .collect()
+    }
+
+    fn read(&mut self, work_tree: &Path) -> Option<GitStatus> {
+        let checkout = GitCheckout::find(work_tree)?;
+        let index_mtime = fs::metadata(checkout.git_dir.join("index"))
+            .and_then(|meta| meta.modified())
+            .ok();
+        // Edits are only cleared by git commands, and those rewrite the index, so
+        // a dirty tree with the same index is still dirty
+        let dirty = match (index_mtime, self.dirty_at.get(work_tree)) {
+            (Some(mtime), Some(dirty_at)) if mtime == *dirty_at => true,
+            _ => checkout.is_dirty(),
+        };
+        match index_mtime.filter(|_| dirty) {
+            Some(mtime) => self.dirty_at.insert(work_tree.to_path_buf(), mtime),
+            None => self.dirty_at.remove(work_tree),
+        };
+        Some(GitStatus {
+            branch: checkout.branch(),
+            dirty,
+        })
+    }
+}
+
+pub fn resolve_project_info(pid: i32, config: &ProjectsConfig) -> Option<ProjectInfo> {
+    let path = project_cwd(pid)?;
+    if let Some(reason) = unsafe_path_reason(&path, config) {
+        log::debug!(
+            "Skipping project resolution for {}: {}",
+            path.display(),
+            reason
+        );
+        return None;
+    }
+    let checkout = GitCheckout::find(&path);
+    let name =
+        project_name(&path, config, checkout.as_ref()).unwrap_or_else(|| "(unknown)".to_string());
+    Some(ProjectInfo {
+        name,
+        branch: checkout.as_ref().and_then(GitCheckout::branch),
+        // Walking the index is slow for big repositories; GitStatusReader fills this in
+        dirty: false,
+        worktree: checkout.map(|c| c.work_tree),
+        path,
+    })
+}
+
+/// The working directory that says which project a process belongs to. Servers
+/// started through a runtime or launcher often sit in `/` or the runtime's
+/// install directory, in which case the nearest parent's directory is used.
+fn project_cwd(pid: i32) -> Option<PathBuf> {
+    let own = process_cwd(pid)?;
+    let (mut current, mut cwd) = (pid, own.clone());
+    for hop in 0..=MAX_PARENT_HOPS {
+        if !is_uninformative_cwd(&cwd, current) {
+            return Some(cwd);
+        }
+        if hop == MAX_PARENT_HOPS {
+            break;
+        }
+        let Some(parent) = parent_pid(current).filter(|ppid| *ppid > 1) else {
+            break;
+        };
+        let Some(parent_cwd) = process_cwd(parent) else {
+            break;
+        };
+        log::debug!(
+            "PID {} has no useful working directory, trying parent {} in {}",
+            pid,
+            parent,
+            parent_cwd.display()
+        );
+        (current, cwd) = (parent, parent_cwd);
+    }
+    Some(own)
+}
+
+/// A filesystem root, the system directory or the directory holding the executable itself.
+fn is_uninformative_cwd(cwd: &Path, pid: i32) -> bool {
+    if cwd.parent().is_none() {
+        return true;
+    }
+    #[cfg(target_os = "windows")]
+    if let Ok(system_root) = std::env::var("SystemRoot")
+        && cwd.starts_with(system_root)
+    {
+        return true;
+    }
+    process_executable(pid).is_some_and(|exe| exe.parent() == Some(cwd))
+}
+
+/// A configured override, else the first resolver hit in the nearest directory
+/// that has one, walking up to the repository root.
+fn project_name(
+    path: &Path,
+    config: &ProjectsConfig,
+    checkout: Option<&GitCheckout>,
+) -> Option<String> {
+    if let Some(name) = override_for(path, config) {
+        return Some(name);
+    }
+
+    let manifests: Vec<ProjectResolver> = config
+        .resolvers
+        .iter()
+        .copied()
+        .filter(|r| r.is_manifest())
+        .collect();
+    if !manifests.is_empty() {
+        let home = dirs::home_dir();
+        for dir in path.ancestors() {
+            if let Some(name) = manifests.iter().find_map(|r| manifest_name(*r, dir)) {
+                return Some(name);
+            }
+            // Don't pick up manifests from outside the repository or in the home directory
+            if dir.join(".git").exists() || home.as_deref().is_some_and(|h| dir.parent() == Some(h))
+            {
+                break;
+            }
+        }
+    }
+
+    config.resolvers.iter().find_map(|resolver| match resolver {
+        ProjectResolver::Git => checkout.and_then(GitCheckout::repo_name),
+        ProjectResolver::Directory => dir_name(path),
+        _ => None,
+    })
+}
+
+/// The most specific `overrides` entry containing `path`.
+fn override_for(path: &Path, config: &ProjectsConfig) -> Option<String> {
+    config
+        .overrides
+        .iter()
+        .filter_map(|(prefix, name)| {
+            let prefix = expand_home(prefix);
+            let prefix = prefix.canonicalize().unwrap_or(prefix);
+            path.starts_with(&prefix)
+                .then(|| (prefix.components().count(), name))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, name)| name.clone())
+}
+
+fn manifest_name(resolver: ProjectResolver, dir: &Path) -> Option<String> {
+    match resolver {
+        ProjectResolver::PackageJson => json_name(&dir.join("package.json")),
+        ProjectResolver::Composer => json_name(&dir.join("composer.json")),
+        ProjectResolver::Cargo => {
+            let manifest = read_toml(&dir.join("Cargo.toml"))?;
+            match toml_str(&manifest, &["package", "name"]) {
+                Some(name) => Some(name),
+                // A virtual workspace has no name of its own; its root directory is the project
+                None if manifest.get("workspace").is_some() => dir_name(dir),
+                None => None,
+            }
+        }
+        ProjectResolver::Pyproject => {
+            let manifest = read_toml(&dir.join("pyproject.toml"))?;
+            toml_str(&manifest, &["project", "name"])
+                .or_else(|| toml_str(&manifest, &["tool", "poetry", "name"]))
+        }
+        ProjectResolver::GoMod => {
+            let content = fs::read_to_string(dir.join("go.mod")).ok()?;
+            go_module_name(&content)
+        }
+        ProjectResolver::Git | ProjectResolver::Directory => None,
+    }
+}
+
+fn json_name(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    manifest["name"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    fs::read_to_string(path).ok()?.parse().ok()
+}
+
+fn toml_str(table: &toml::Table, keys: &[&str]) -> Option<String> {
+    let (last, tables) = keys.split_last()?;
+    let mut table = table;
+    for key in tables {
+        table = table.get(*key)?.as_table()?;
+    }
+    table
+        .get(*last)?
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// `module github.com/acme/shop/v2` → `shop`
+fn go_module_name(go_mod: &str) -> Option<String> {
+    let module = go_mod
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))?
+        .trim()
+        .trim_matches('"');
+    let mut segments = module.rsplit('/');
+    let last = segments.next()?;
+    let is_major_version =
+        last.len() > 1 && last.starts_with('v') && last[1..].chars().all(|c| c.is_ascii_digit());
+    if is_major_version {
+        segments.next().map(str::to_string)
+    } else {
+        Some(last.to_string())
+    }
+}
+
+/// Why project resolution won't look inside `path`, if it isn't under a safe root.
+fn unsafe_path_reason(path: &Path, config: &ProjectsConfig) -> Option<String> {
+    // Resolve to canonical path to prevent traversal attacks
+    let canonical = match path.canonicalize() {
+        Ok(p) => strip_verbatim_prefix(p),
+        Err(err) => return Some(format!("cannot canonicalize: {}", err)),
+    };
+    if let Some(root) = config
+        .excluded_roots
+        .iter()
+        .find(|root| root_contains(root, &canonical))
+    {
+        return Some(format!("excluded by {:?}", root));
+    }
+    if config
+        .safe_roots
+        .iter()
+        .any(|root| root_contains(root, &canonical))
+    {
+        return None;
+    }
+    Some(format!("not under any of {:?}", config.safe_roots))
+}
+
+/// Whether project config files in `dir` may be loaded: it is inside one of `trusted_roots`.
+pub fn is_trusted(dir: &Path, config: &ProjectsConfig) -> bool {
+    let Ok(canonical) = dir.canonicalize().map(strip_verbatim_prefix) else {
+        return false;
+    };
+    config
+        .trusted_roots
+        .iter()
+        .any(|root| root_contains(root, &canonical))
+}
+
+/// The `trusted_roots` that name a single folder rather than a pattern.
+pub fn trusted_dirs(config: &ProjectsConfig) -> Vec<PathBuf> {
+    config
+        .trusted_roots
+        .iter()
+        .filter(|root| !root.contains(['*', '?', '[']))
+        .filter_map(|root| expand_root(root, false))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Whether `path` is `root` or inside it.
+fn root_contains(root: &str, path: &Path) -> bool {
+    let Some(pattern) = root_pattern(root) else {
+        return false;
+    };
+    let options = glob::MatchOptions {
+        case_sensitive: !cfg!(windows),
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    path.ancestors()
+        .any(|dir| pattern.matches_path_with(dir, options))
+}
+
+/// A root as a glob pattern, with `~`, `$VAR`, `${VAR}` and `%VAR%` expanded.
+/// Roots without wildcards are canonicalized so `/tmp` also covers `/private/tmp`.
+/// A root naming an unset variable matches nothing.
+fn root_pattern(root: &str) -> Option<glob::Pattern> {
+    if root.contains(['*', '?', '[']) {
+        return glob::Pattern::new(&expand_root(root, true)?).ok();
+    }
+    let path = PathBuf::from(expand_root(root, false)?);
+    let path = path
+        .canonicalize()
+        .map(strip_verbatim_prefix)
+        .unwrap_or(path);
+    glob::Pattern::new(&glob::Pattern::escape(&path.to_string_lossy())).ok()
+}
+
+fn expand_root(root: &str, escape: bool) -> Option<String> {
+    let quote = |value: &str| {
+        if escape {
+            glob::Pattern::escape(value)
+        } else {
+            value.to_string()
+        }
+    };
+    let mut expanded = String::new();
+    let mut rest = root;
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        expanded.push_str(&quote(&dirs::home_dir()?.to_string_lossy()));
+        rest = &rest[1..];
+    }
+    while let Some(start) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}')?;
+            (&braced[..end], &braced[end + 1..])
+        } else if rest[start..].starts_with('%') {
+            let end = after.find('%')?;
+            (&after[..end], &after[end + 1..])
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        let value = std::env::var(name).ok().filter(|value| !value.is_empty())?;
+        expanded.push_str(&quote(&value));
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Some(expanded)
+}
+
+/// `\\?\C:\src` → `C:\src`, so canonical Windows paths compare with roots as users write them.
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    match path.to_str().and_then(|p| p.strip_prefix(r"\\?\")) {
+        Some(stripped) if !stripped.starts_with("UNC\\") => PathBuf::from(stripped),
+        _ => path,
+    }
+}
+
+// === git ===
+
+/// Where a work tree keeps its git metadata, found by reading `.git` directly.
+#[derive(Debug)]
+struct GitCheckout {
+    work_tree: PathBuf,
+    /// Per-worktree directory with `HEAD` and `index`
+    git_dir: PathBuf,
+    /// Directory shared by all worktrees of the repository
+    common_dir: PathBuf,
+}
+
+impl GitCheckout {
+    /// The work tree containing `path`, following `gitdir:` links of linked worktrees.
+    fn find(path: &Path) -> Option<Self> {
+        let dir = path.ancestors().find(|dir| dir.join(".git").exists())?;
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(Self {
+                work_tree: dir.to_path_buf(),
+                git_dir: dot_git.clone(),
+                common_dir: dot_git,
+            });
+        }
+        // `gitdir: /repo/.git/worktrees/<name>`, which has `commondir` pointing back at `/repo/.git`
+        let link = fs::read_to_string(&dot_git).ok()?;
+        let git_dir = dir.join(link.trim().strip_prefix("gitdir:")?.trim());
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(common) => git_dir.join(common.trim()),
+            Err(_) => git_dir.clone(),
+        };
+        Some(Self {
+            work_tree: dir.to_path_buf(),
+            common_dir: common_dir.canonicalize().unwrap_or(common_dir),
+            git_dir,
+        })
+    }
+
+    /// The main repository's name, also for linked worktrees (whose own
+    /// directory is usually named after the branch).
+    fn repo_name(&self) -> Option<String> {
+        // Worktrees share `<repo>/.git`; submodules and others use the work tree root
+        if self
+            .common_dir
+            .file_name()
+            .is_some_and(|name| name == ".git")
+        {
+            dir_name(self.common_dir.parent()?)
+        } else {
+            dir_name(&self.work_tree)
+        }
+    }
+
+    /// The checked-out branch, or the abbreviated commit when HEAD is detached.
+    fn branch(&self) -> Option<String> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        let head = head.trim();
+        match head.strip_prefix("ref:") {
+            Some(reference) => {
+                let reference = reference.trim();
+                Some(
+                    reference
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(reference)
+                        .to_string(),
+                )
+            }
+            None => head.get(..7).map(str::to_string),
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        index_is_dirty(&self.git_dir.join("index"), &self.work_tree).unwrap_or(false)
+    }
+}
+
+/// Whether a tracked file was modified or deleted since it was staged, judged by
+/// the size and mtime recorded in the index (the stat check `git status` starts
+/// with, without hashing contents). Untracked files don't count.
+fn index_is_dirty(index: &Path, work_tree: &Path) -> Option<bool> {
+    const ENTRY_HEADER_LEN: usize = 62;
+    const GITLINK_MODE: u32 = 0o160000;
+
+    let data = fs::read(index).ok()?;
+    if data.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = read_u32(&data, 4)?;
+    let count = read_u32(&data, 8)?;
+    let mut offset = 12;
+    let mut name: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        let mtime = read_u32(&data, offset + 8)?;
+        let mode = read_u32(&data, offset + 24)?;
+        let size = read_u32(&data, offset + 36)?;
+        let flags = read_u16(&data, offset + 60)?;
+        let mut cursor = offset + ENTRY_HEADER_LEN;
+        let mut skip_worktree = false;
+        if version >= 3 && flags & 0x4000 != 0 {
+            skip_worktree = read_u16(&data, cursor)? & 0x4000 != 0;
+            cursor += 2;
+        }
+
+        // Version 4 prefix-compresses paths against the previous entry
+        if version >= 4 {
+            let (strip, len) = read_varint(data.get(cursor..)?)?;
+            cursor += len;
+            name.truncate(name.len().checked_sub(strip)?);
+        } else {
+            name.clear();
+        }
+        let end = cursor + data.get(cursor..)?.iter().position(|b| *b == 0)?;
+        name.extend_from_slice(&data[cursor..end]);
+        offset = if version >= 4 {
+            end + 1
+        } else {
+            // NUL-padded to a multiple of 8 bytes
+            offset + (end - offset + 8) / 8 * 8
+        };
+
+        let assume_valid = flags & 0x8000 != 0;
+        let stage = (flags >> 12) & 0x3;
+        if stage != 0 {
+            return Some(true); // unresolved merge conflict
+        }
+        if assume_valid || skip_worktree || mode == GITLINK_MODE {
+            continue;
+        }
+        let path = work_tree.join(String::from_utf8_lossy(&name).as_ref());
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            return Some(true);
+        };
+        let modified = meta
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        if meta.len() as u32 != size || modified as u32 != mtime {
+            return Some(true);
+        }
+    }
+    Some(false)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// git's offset varint: 7 bits per byte, each continuation adding one.
+fn read_varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut bytes = data.iter();
+    let mut byte = *bytes.next()?;
+    let mut value = (byte & 0x7f) as usize;
+    let mut len = 1;
+    while byte & 0x80 != 0 {
+        byte = *bytes.next()?;
+        len += 1;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+    }
+    Some((value, len))
+}
+
+fn dir_name(path: &Path) -> Option<String> {
+    path.file_name().map(|s| s.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("portkiller-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_nearest_manifest_and_overrides() {
+        let root = scratch_dir("project-names");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "shop", "private": true}"#,
+        )
+        .unwrap();
+        let web = root.join("apps/web");
+        fs::create_dir_all(web.join("src")).unwrap();
+        fs::write(web.join("package.json"), r#"{"name": "@shop/web"}"#).unwrap();
+        let api = root.join("services/api");
+        fs::create_dir_all(&api).unwrap();
+        fs::write(api.join("Cargo.toml"), "[package]\nname = \"shop-api\"\n").unwrap();
+        fs::write(
+            api.join("go.mod"),
+            "module github.com/acme/shop-api-go/v2\n",
+        )
+        .unwrap();
+
+        let mut config = ProjectsConfig::default();
+        assert_eq!(
+            project_name(&web.join("src"), &config, None).as_deref(),
+            Some("@shop/web")
+        );
+        assert_eq!(
+            project_name(&api, &config, None).as_deref(),
+            Some("shop-api")
+        );
+        assert_eq!(
+            project_name(&root.join("apps"), &config, None).as_deref(),
+            Some("shop")
+        );
+
+        config.resolvers = vec![ProjectResolver::GoMod, ProjectResolver::Cargo];
+        assert_eq!(
+            project_name(&api, &config, None).as_deref(),
+            Some("shop-api-go")
+        );
+
+        config.overrides =
+            BTreeMap::from([(web.to_string_lossy().to_string(), "storefront".to_string())]);
+        assert_eq!(
+            project_name(&web.join("src"), &config, None).as_deref(),
+            Some("storefront")
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn checks_paths_against_safe_and_excluded_roots() {
+        let root = scratch_dir("safe-roots");
+        let app = root.join("srv/src/shop");
+        let vendored = root.join("srv/src/vendor/lib");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&vendored).unwrap();
+
+        let root_str = root.canonicalize().unwrap().to_string_lossy().to_string();
+        let config = ProjectsConfig {
+            safe_roots: vec![format!("{}/*/src", root_str)],
+            excluded_roots: vec![format!("{}/**/vendor", root_str)],
+            ..ProjectsConfig::default()
+        };
+        assert_eq!(unsafe_path_reason(&app, &config), None);
+        assert!(
+            unsafe_path_reason(&vendored, &config)
+                .unwrap()
+                .starts_with("excluded by")
+        );
+        assert!(
+            unsafe_path_reason(&root, &config)
+                .unwrap()
+                .starts_with("not under")
+        );
+        assert!(unsafe_path_reason(&root.join("missing"), &config).is_some());
+
+        assert!(!root_contains("$PORTKILLER_UNSET_ROOT", &app));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    fn index_with(name: &str, meta: &fs::Metadata) -> Vec<u8> {
+        let mtime = meta
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
+        let mut entry = vec![0u8; 62];
+        entry[8..12].copy_from_slice(&mtime.to_be_bytes());
+        entry[24..28].copy_from_slice(&0o100644u32.to_be_bytes());
+        entry[36..40].copy_from_slice(&(meta.len() as u32).to_be_bytes());
+        entry[60..62].copy_from_slice(&(name.len() as u16).to_be_bytes());
+        entry.extend_from_slice(name.as_bytes());
+        entry.resize((entry.len() + 8) / 8 * 8, 0);
+
+        let mut index = b"DIRC".to_vec();
+        index.extend_from_slice(&2u32.to_be_bytes());
+        index.extend_from_slice(&1u32.to_be_bytes());
+        index.extend(entry);
+        index
+    }
+
+    #[test]
+    fn reads_branch_worktree_and_dirty_state_from_git_dir() {
+        let root = scratch_dir("git-checkout");
+        let repo = root.join("shop");
+        fs::create_dir_all(repo.join(".git/worktrees/cart")).unwrap();
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(repo.join("app.js"), "listen(3000)\n").unwrap();
+        let meta = fs::metadata(repo.join("app.js")).unwrap();
+        fs::write(repo.join(".git/index"), index_with("app.js", &meta)).unwrap();
+
+        let checkout = GitCheckout::find(&repo.join("src")).unwrap();
+        assert_eq!(checkout.branch().as_deref(), Some("main"));
+        assert!(!checkout.is_dirty());
+        fs::write(repo.join("app.js"), "listen(3000, host)\n").unwrap();
+        assert!(checkout.is_dirty());
+        let statuses = GitStatusReader::default().read_all(std::slice::from_ref(&repo));
+        assert_eq!(
+            statuses[&repo],
+            GitStatus {
+                branch: Some("main".to_string()),
+                dirty: true
+            }
+        );
+
+        // A linked worktree named after its branch still reports the repository name
+        let worktree = root.join("shop-cart");
+        fs::create_dir_all(&worktree).unwrap();
+        let git_dir = repo.join(".git/worktrees/cart");
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/feature/cart\n").unwrap();
+        fs::write(git_dir.join("commondir"), "../..\n").unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", git_dir.display()),
+        )
+        .unwrap();
+
+        let checkout = GitCheckout::find(&worktree).unwrap();
+        assert_eq!(checkout.repo_name().as_deref(), Some("shop"));
+        assert_eq!(checkout.branch().as_deref(), Some("feature/cart"));
+        assert_eq!(checkout.work_tree, worktree);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,196 @@
+//! Restarting a listener with the command line, directory and environment
+//! it was originally started with.
+
+use std::fs::File;
+use std::io::Read;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::config::ensure_log_dir;
+use crate::process::ports::{process_argv, process_cwd, process_environment, process_executable};
+use crate::utils::detached_command;
+
+/// How long to wait for the old process to release its ports.
+pub const PORT_RELEASE_TIMEOUT: Duration = Duration::from_secs(10);
+/// A relaunched process that exits within this window is reported as a failure.
+const STARTUP_GRACE: Duration = Duration::from_millis(1500);
+const POLL_STEP: Duration = Duration::from_millis(100);
+
+/// Variables that describe the shell session rather than the server, and
+/// would be stale (or misleading) in a relaunched process.
+const VOLATILE_VARS: &[&str] = &[
+    "PWD",
+    "OLDPWD",
+    "SHLVL",
+    "_",
+    "TERM_SESSION_ID",
+    "ITERM_SESSION_ID",
+    "SECURITYSESSIONID",
+    "WINDOWID",
+    "TMUX",
+    "TMUX_PANE",
+];
+
+/// Everything needed to start a process again.
+#[derive(Debug, Clone)]
+pub struct LaunchSpec {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    /// Only the variables that differ from PortKiller's own environment.
+    pub env: Vec<(String, String)>,
+}
+
+impl LaunchSpec {
+    /// Capture how `pid` was launched. Must be called while it's still running.
+    pub fn capture(pid: i32) -> Result<Self> {
+        let argv = process_argv(pid).ok_or_else(|| anyhow!("could not read its command line"))?;
+        let cwd =
+            process_cwd(pid).ok_or_else(|| anyhow!("could not read its working directory"))?;
+        let (first, args) = argv
+            .split_first()
+            .ok_or_else(|| anyhow!("empty command line"))?;
+        // Prefer the resolved executable: argv[0] is often a bare name looked
+        // up on a shell PATH that PortKiller doesn't have
+        let program = process_executable(pid)
+            .filter(|path| path.is_absolute())
+            .unwrap_or_else(|| PathBuf::from(first));
+        let env = process_environment(pid)
+            .map(|captured| filter_environment(captured, |key| std::env::var(key).ok()))
+            .unwrap_or_default();
+        Ok(Self {
+            program,
+            args: args.to_vec(),
+            cwd,
+            env,
+        })
+    }
+
+    /// Start the process detached from PortKiller, with output going to `log_path`.
+    /// Returns the new PID once it has survived the startup grace period.
+    pub fn spawn(&self, log_path: &Path) -> Result<i32> {
+        let log = File::create(log_path)
+            .with_context(|| format!("failed to create {}", log_path.display()))?;
+        let mut cmd = detached_command(&self.program);
+        cmd.args(&self.args)
+            .current_dir(&self.cwd)
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("failed to start {}", self.program.display()))?;
+        let pid = child.id() as i32;
+
+        let deadline = Instant::now() + STARTUP_GRACE;
+        while Instant::now() < deadline {
+            if let Some(status) = child.try_wait()? {
+                let mut output = String::new();
+                if let Ok(mut file) = File::open(log_path) {
+                    let _ = file.read_to_string(&mut output);
+                }
+                let tail = output.lines().rev().find(|line| !line.trim().is_empty());
+                bail!("exited with {}: {}", status, tail.unwrap_or("no output"));
+            }
+            thread::sleep(POLL_STEP);
+        }
+
+        // Reap the process when it eventually exits
+        thread::spawn(move || {
+            let _ = child.wait();
+        });
+        Ok(pid)
+    }
+}
+
+/// Where a restarted process's output goes, in the private log directory.
+pub fn log_path(port: u16) -> Result<PathBuf> {
+    Ok(ensure_log_dir()?.join(format!("restart-{}.log", port)))
+}
+
+/// Wait until nothing accepts connections on `ports` any more.
+pub fn wait_for_ports_free(ports: &[u16], timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let busy: Vec<u16> = ports
+            .iter()
+            .copied()
+            .filter(|&port| port_in_use(port))
+            .collect();
+        if busy.is_empty() {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "port {} still in use after {}s",
+                busy.iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                timeout.as_secs()
+            );
+        }
+        thread::sleep(POLL_STEP);
+    }
+}
+
+/// Whether anything accepts connections on `localhost:port` (IPv4 or IPv6).
+pub fn port_in_use(port: u16) -> bool {
+    [
+        SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+        SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+    ]
+    .iter()
+    .any(|addr| TcpStream::connect_timeout(addr, Duration::from_millis(200)).is_ok())
+}
+
+/// Keep the variables the process was started with that PortKiller itself
+/// doesn't already have, minus per-shell-session noise.
+fn filter_environment(
+    captured: Vec<(String, String)>,
+    own: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, String)> {
+    captured
+        .into_iter()
+        .filter(|(key, value)| {
+            !key.is_empty()
+                && !key.starts_with('=')
+                && !VOLATILE_VARS.contains(&key.as_str())
+                && own(key).as_deref() != Some(value.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_environment_that_differs() {
+        let captured = vec![
+            ("HOME".to_string(), "/Users/dev".to_string()),
+            ("PATH".to_string(), "/opt/node/bin:/usr/bin".to_string()),
+            ("NODE_ENV".to_string(), "development".to_string()),
+            ("PWD".to_string(), "/Users/dev/shop".to_string()),
+            ("SHLVL".to_string(), "2".to_string()),
+        ];
+        let own = |key: &str| match key {
+            "HOME" => Some("/Users/dev".to_string()),
+            "PATH" => Some("/usr/bin".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            filter_environment(captured, own),
+            vec![
+                ("PATH".to_string(), "/opt/node/bin:/usr/bin".to_string()),
+                ("NODE_ENV".to_string(), "development".to_string()),
+            ]
+        );
+    }
+}
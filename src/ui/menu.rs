@@ -1,58 +1,40 @@
 use std::collections::BTreeMap;
 
 use anyhow::Result;
-use tray_icon::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 
-use crate::model::{AppState, FeedbackSeverity, KillFeedback, ProcessInfo};
+use crate::model::{
+    AppState, FeedbackSeverity, IntegrationHealth, KillFeedback, ProcessIdentity, ProcessInfo,
+};
 
 const MAX_TOOLTIP_ENTRIES: usize = 5;
+const MAX_STATUS_ERROR_LEN: usize = 60;
 const MENU_ID_KILL_ALL: &str = "kill_all";
-const MENU_ID_DOCKER_STOP_ALL: &str = "docker_stop_all";
-#[cfg(target_os = "macos")]
-const MENU_ID_BREW_STOP_ALL: &str = "brew_stop_all";
-#[cfg(target_os = "windows")]
-const MENU_ID_SERVICE_STOP_ALL: &str = "service_stop_all";
 const MENU_ID_QUIT: &str = "quit";
 const MENU_ID_EDIT_CONFIG: &str = "edit_config";
 const MENU_ID_RELOAD_CONFIG: &str = "reload_config";
+const MENU_ID_SHOW_EFFECTIVE_CONFIG: &str = "show_effective_config";
 const MENU_ID_LAUNCH_AT_LOGIN: &str = "launch_at_login";
 const MENU_ID_PROCESS_PREFIX: &str = "process_";
-const MENU_ID_DOCKER_STOP_PREFIX: &str = "docker_stop_";
-#[cfg(target_os = "macos")]
-const MENU_ID_BREW_STOP_PREFIX: &str = "brew_stop_";
-#[cfg(target_os = "windows")]
-const MENU_ID_SERVICE_STOP_PREFIX: &str = "service_stop_";
+const MENU_ID_RESTART_PREFIX: &str = "restart_";
+// Per-process actions: `process_<pid>_<port>_<action>`
+const PROCESS_ACTION_FORCE_KILL: &str = "force";
+const PROCESS_ACTION_OPEN: &str = "open";
+const PROCESS_ACTION_FOLDER: &str = "folder";
+const PROCESS_ACTION_EDITOR: &str = "editor";
+const PROCESS_ACTION_COPY_PID: &str = "copypid";
+const PROCESS_ACTION_COPY_URL: &str = "copyurl";
+const MENU_ID_TUNNEL_PREFIX: &str = "tunnel_";
+const MENU_ID_ROUTE_PREFIX: &str = "route_";
+// Integration actions are `<id>_stop_<unit>`, `<id>_stop_all` and `<id>_do_<action>_<unit>`.
+// Integration ids and action ids never contain `_`, so the first `_` ends the id.
+const MENU_ID_INTEGRATION_STOP: &str = "stop_";
+const MENU_ID_INTEGRATION_DO: &str = "do_";
+const MENU_ID_INTEGRATION_STOP_ALL: &str = "all";
+// Written before a unit named `all`; not an identifier character, so parsing drops it
+const MENU_ID_UNIT_ESCAPE: &str = "!";
 const MENU_ID_EMPTY: &str = "empty";
 
-/// Maps common container names to friendly display names
-fn friendly_container_name(raw_name: &str) -> String {
-    // Strip common prefixes
-    let name = raw_name
-        .trim_start_matches("portkiller-")
-        .trim_start_matches("test-")
-        .trim_start_matches("dev-");
-
-    // Map to friendly names
-    match name {
-        "postgres" | "postgresql" => "PostgreSQL".to_string(),
-        "mongo" | "mongodb" => "MongoDB".to_string(),
-        "redis" => "Redis".to_string(),
-        "mysql" => "MySQL".to_string(),
-        "nginx" => "Nginx".to_string(),
-        "rabbitmq" => "RabbitMQ".to_string(),
-        "elasticsearch" => "Elasticsearch".to_string(),
-        "memcached" => "Memcached".to_string(),
-        _ => {
-            // Capitalize first letter of unknown containers
-            let mut chars = name.chars();
-            match chars.next() {
-                None => name.to_string(),
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-            }
-        }
-    }
-}
-
 pub fn build_menu_with_context(state: &AppState) -> Result<Menu> {
     let menu = Menu::new();
     let processes = &state.processes;
@@ -68,286 +50,364 @@ pub fn build_menu_with_context(state: &AppState) -> Result<Menu> {
     menu.append(&header)?;
     menu.append(&PredefinedMenuItem::separator())?;
 
-    if processes.is_empty() {
-        let item = MenuItem::with_id(MENU_ID_EMPTY, "No dev ports listening", false, None);
-        menu.append(&item)?;
-    } else {
-        // Separate processes into Docker, managed services, and regular processes
-        let mut docker_items: Vec<(&ProcessInfo, &crate::model::DockerContainerInfo)> = Vec::new();
-        #[cfg(target_os = "macos")]
-        let mut brew_items: Vec<(&ProcessInfo, String)> = Vec::new();
-        #[cfg(target_os = "windows")]
-        let mut service_items: Vec<(&ProcessInfo, String)> = Vec::new();
-        let mut regular_processes: Vec<&ProcessInfo> = Vec::new();
-
-        for process in processes {
-            if let Some(dc) = state.docker_port_map.get(&process.port) {
-                docker_items.push((process, dc));
-            } else {
-                #[cfg(target_os = "macos")]
-                {
-                    if let Some(service) = crate::integrations::brew::get_brew_managed_service(
-                        &process.command,
-                        process.port,
-                        &state.brew_services_map,
-                    ) {
-                        brew_items.push((process, service));
-                        continue;
-                    }
-                }
-                #[cfg(target_os = "windows")]
-                {
-                    if let Some(service) =
-                        crate::integrations::windows_services::get_windows_managed_service(
-                            &process.command,
-                            process.port,
-                            &state.windows_services_map,
-                        )
-                    {
-                        service_items.push((process, service));
-                        continue;
-                    }
-                }
-                regular_processes.push(process);
+    // Separate processes claimed by an integration from regular processes
+    let mut claimed: BTreeMap<&str, BTreeMap<String, Vec<u16>>> = BTreeMap::new();
+    let mut regular_processes: Vec<&ProcessInfo> = Vec::new();
+    let mut hidden_processes: Vec<&ProcessInfo> = Vec::new();
+
+    for process in processes {
+        if let Some((integration, unit)) = state.integration_for(process) {
+            let ports = claimed
+                .entry(integration.id())
+                .or_default()
+                .entry(unit)
+                .or_default();
+            if !ports.contains(&process.port) {
+                ports.push(process.port);
             }
+        } else if state.ignore.matches(process) {
+            hidden_processes.push(process);
+        } else {
+            regular_processes.push(process);
         }
+    }
 
-        let mut has_any_section = false;
-
-        // === PROCESSES SECTION ===
-        if !regular_processes.is_empty() {
-            has_any_section = true;
-
-            // Group by PID to count unique processes
-            let mut by_pid: BTreeMap<i32, (String, Vec<u16>)> = BTreeMap::new();
-            for p in &regular_processes {
-                let entry = by_pid
-                    .entry(p.pid)
-                    .or_insert_with(|| (p.command.clone(), Vec::new()));
-                if !entry.1.contains(&p.port) {
-                    entry.1.push(p.port);
-                }
+    // Some integrations keep units listed without a scanned listener
+    for integration in state.integrations.iter() {
+        if !integration.lists_idle_units() {
+            continue;
+        }
+        if let Some(units) = state.integration_units.get(integration.id()) {
+            let by_unit = claimed.entry(integration.id()).or_default();
+            for unit in units {
+                by_unit.entry(unit.name.clone()).or_default();
             }
+        }
+    }
 
-            let header = MenuItem::with_id(
-                "header_processes",
-                format!("Processes · {}", by_pid.len()),
-                false,
-                None,
-            );
-            menu.append(&header)?;
-
-            // Create clickable menu item for each process (grouped by PID)
-            for (pid, (command, ports)) in &mut by_pid {
-                ports.sort();
-
-                // Get project name for this PID
-                let project_name = state.project_cache.get(pid).map(|pi| pi.name.clone());
+    let mut has_any_section = false;
 
-                // Build main menu label: "ports · command · project"
-                let ports_str = ports
-                    .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                let main_label = if let Some(ref project) = project_name {
-                    format!("{} · {} · {}", ports_str, command, project)
-                } else {
-                    format!("{} · {}", ports_str, command)
-                };
+    if processes.len() == hidden_processes.len() {
+        let item = MenuItem::with_id(MENU_ID_EMPTY, "No dev ports listening", false, None);
+        menu.append(&item)?;
+        has_any_section = true;
+    }
 
-                // Create clickable menu item that kills the process when clicked
-                let process_item = MenuItem::with_id(
-                    MenuId::new(process_menu_id(*pid, ports[0])),
-                    main_label,
-                    true,
-                    None,
-                );
-                menu.append(&process_item)?;
+    // === PROCESSES SECTION ===
+    if !regular_processes.is_empty() {
+        has_any_section = true;
+
+        // Group by PID to count unique processes
+        let mut by_pid: BTreeMap<ProcessIdentity, (String, Vec<u16>)> = BTreeMap::new();
+        for p in &regular_processes {
+            let entry = by_pid
+                .entry(p.identity())
+                .or_insert_with(|| (p.command.clone(), Vec::new()));
+            if !entry.1.contains(&p.port) {
+                entry.1.push(p.port);
             }
+        }
 
-            // Kill All only if multiple processes
-            if by_pid.len() > 1 {
-                let kill_all =
-                    MenuItem::with_id(MENU_ID_KILL_ALL, "Kill All Processes", true, None);
-                menu.append(&kill_all)?;
+        // Group processes by the named port range they listen on; ungrouped ones come first
+        let monitoring = &state.config.monitoring;
+        let mut groups: Vec<(Option<&str>, Vec<&ProcessIdentity>)> = Vec::new();
+        for ports in by_pid.values_mut().map(|(_, ports)| ports) {
+            ports.sort();
+        }
+        for (process, (_, ports)) in &by_pid {
+            let group = monitoring.group_for(ports);
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, members)) => members.push(process),
+                None => groups.push((group, vec![process])),
             }
         }
+        groups.sort_by_key(|(group, _)| group.map(|name| monitoring.group_position(name)));
 
-        // === DOCKER SECTION ===
-        if !docker_items.is_empty() {
-            if has_any_section {
+        for (index, (group, members)) in groups.iter().enumerate() {
+            if index > 0 {
                 menu.append(&PredefinedMenuItem::separator())?;
             }
-            has_any_section = true;
-
-            // Group by container name
-            let mut by_container: BTreeMap<String, Vec<u16>> = BTreeMap::new();
-            for (process, dc) in &docker_items {
-                by_container
-                    .entry(dc.name.clone())
-                    .or_default()
-                    .push(process.port);
-            }
-
-            let header = MenuItem::with_id(
-                "header_docker",
-                format!("Docker Containers · {}", by_container.len()),
-                false,
-                None,
-            );
+            let header = match group {
+                Some(name) => MenuItem::with_id(
+                    format!("header_group_{}", index),
+                    format!("{} · {}", name, members.len()),
+                    false,
+                    None,
+                ),
+                None => MenuItem::with_id(
+                    "header_processes",
+                    format!("Processes · {}", members.len()),
+                    false,
+                    None,
+                ),
+            };
             menu.append(&header)?;
 
-            // Check if we need Stop All before consuming the map
-            let needs_stop_all = by_container.len() > 1;
-
-            // Create clickable menu item for each container
-            for (container_name, mut ports) in by_container {
-                ports.sort();
-                let friendly = friendly_container_name(&container_name);
+            // Create clickable menu item for each process (grouped by PID)
+            for &process in members {
+                let (command, ports) = &by_pid[process];
+                // Get project name for this PID
+                let project_name = state.project_cache.get(process).map(|pi| pi.label());
 
-                // Build label: "ports · container_name"
+                // Build main menu label: "ports · command · project"
                 let ports_str = ports
                     .iter()
                     .map(|p| p.to_string())
                     .collect::<Vec<_>>()
                     .join(", ");
-                let main_label = format!("{} · {}", ports_str, friendly);
-
-                // Create clickable menu item that stops the container when clicked
-                let container_item = MenuItem::with_id(
-                    format!("{}{}", MENU_ID_DOCKER_STOP_PREFIX, container_name),
-                    main_label,
-                    true,
-                    None,
-                );
-                menu.append(&container_item)?;
-            }
-
-            // Stop All only if multiple containers
-            if needs_stop_all {
-                let stop_all =
-                    MenuItem::with_id(MENU_ID_DOCKER_STOP_ALL, "Stop All Containers", true, None);
-                menu.append(&stop_all)?;
-            }
-        }
-
-        // === BREW SECTION (macOS only) ===
-        #[cfg(target_os = "macos")]
-        if !brew_items.is_empty() {
-            if has_any_section {
-                menu.append(&PredefinedMenuItem::separator())?;
-            }
 
-            // Group by service name
-            let mut by_service: BTreeMap<String, Vec<u16>> = BTreeMap::new();
-            for (process, service) in &brew_items {
-                by_service
-                    .entry(service.clone())
-                    .or_default()
-                    .push(process.port);
-            }
-
-            let header = MenuItem::with_id(
-                "header_brew",
-                format!("Brew Services · {}", by_service.len()),
-                false,
-                None,
-            );
-            menu.append(&header)?;
-
-            // Check if we need Stop All before consuming the map
-            let needs_stop_all = by_service.len() > 1;
+                // Hostnames a local reverse proxy routes to these ports
+                let mut hostnames: Vec<&str> = Vec::new();
+                for route in &state.proxy_routes {
+                    if ports.contains(&route.port) && !hostnames.contains(&route.hostname.as_str())
+                    {
+                        hostnames.push(&route.hostname);
+                    }
+                }
 
-            // Create clickable menu item for each service
-            for (service_name, mut ports) in by_service {
-                ports.sort();
+                // A page title or catalog name reads better than the raw command line
+                let friendly = ports
+                    .iter()
+                    .find_map(|port| state.listener_health.get(port)?.title.as_deref())
+                    .or_else(|| state.catalog.describe_process(process, ports));
+                let mut main_label = match friendly {
+                    Some(name) => format!("{} · {} ({})", ports_str, name, command),
+                    None => format!("{} · {}", ports_str, command),
+                };
+                if let Some(dot) = health_dot(state, ports) {
+                    main_label = format!("{} {}", dot, main_label);
+                }
+                if !hostnames.is_empty() {
+                    main_label = format!("{} · {}", main_label, hostnames.join(", "));
+                }
+                if let Some(ref project) = project_name {
+                    main_label = format!("{} · {}", main_label, project);
+                }
 
-                // Build label: "ports · service_name"
-                let ports_str = ports
+                let exposed = ports
                     .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                let main_label = format!("{} · {}", ports_str, service_name);
+                    .any(|port| state.public_tunnels.iter().any(|t| t.port == *port));
+                if exposed {
+                    main_label.push_str(" · 🌐 public");
+                }
 
-                // Create clickable menu item that stops the service when clicked
-                let service_item = MenuItem::with_id(
-                    format!("{}{}", MENU_ID_BREW_STOP_PREFIX, service_name),
-                    main_label,
+                // Nothing happens on a plain click: every action lives in the submenu
+                let submenu = Submenu::new(main_label, true);
+                let item = |action: &str, label: String, enabled: bool| {
+                    MenuItem::with_id(
+                        process_action_menu_id(process.pid, ports[0], action),
+                        label,
+                        enabled,
+                        None,
+                    )
+                };
+                // Protected ranges (databases and the like) can't be killed by a stray click
+                let protected = ports.iter().any(|&port| monitoring.is_protected(port));
+                let kill_label = |label: &str| {
+                    if protected {
+                        format!("{} (protected)", label)
+                    } else {
+                        label.to_string()
+                    }
+                };
+                submenu.append(&MenuItem::with_id(
+                    process_menu_id(process.pid, ports[0]),
+                    kill_label("Kill"),
+                    !protected,
+                    None,
+                ))?;
+                submenu.append(&item(
+                    PROCESS_ACTION_FORCE_KILL,
+                    kill_label("Force Kill"),
+                    !protected,
+                ))?;
+                submenu.append(&MenuItem::with_id(
+                    restart_menu_id(process.pid, ports[0]),
+                    "Restart",
                     true,
                     None,
-                );
-                menu.append(&service_item)?;
-            }
-
-            // Stop All only if multiple services
-            if needs_stop_all {
-                let stop_all =
-                    MenuItem::with_id(MENU_ID_BREW_STOP_ALL, "Stop All Services", true, None);
-                menu.append(&stop_all)?;
+                ))?;
+                submenu.append(&PredefinedMenuItem::separator())?;
+                for port in ports.iter() {
+                    submenu.append(&MenuItem::with_id(
+                        process_action_menu_id(process.pid, *port, PROCESS_ACTION_OPEN),
+                        format!("Open {}", state.local_url(*port)),
+                        true,
+                        None,
+                    ))?;
+                }
+                for port in ports.iter() {
+                    let routes = state.proxy_routes.iter().filter(|r| r.port == *port);
+                    for (index, route) in routes.enumerate() {
+                        submenu.append(&MenuItem::with_id(
+                            route_menu_id(*port, index),
+                            format!("Open {}", route.url),
+                            true,
+                            None,
+                        ))?;
+                    }
+                }
+                for port in ports.iter() {
+                    let tunnels = state.public_tunnels.iter().filter(|t| t.port == *port);
+                    for (index, tunnel) in tunnels.enumerate() {
+                        let item = match &tunnel.url {
+                            Some(url) => MenuItem::with_id(
+                                tunnel_menu_id(*port, index),
+                                format!("{}: {}", tunnel.provider, url),
+                                true,
+                                None,
+                            ),
+                            None => MenuItem::with_id(
+                                tunnel_menu_id(*port, index),
+                                format!("{}: {} (URL unknown)", tunnel.provider, port),
+                                false,
+                                None,
+                            ),
+                        };
+                        submenu.append(&item)?;
+                    }
+                }
+                submenu.append(&PredefinedMenuItem::separator())?;
+                let has_project = state.project_cache.get(process).is_some();
+                submenu.append(&item(
+                    PROCESS_ACTION_FOLDER,
+                    "Open Project Folder".into(),
+                    has_project,
+                ))?;
+                submenu.append(&item(
+                    PROCESS_ACTION_EDITOR,
+                    "Open in Editor".into(),
+                    has_project,
+                ))?;
+                submenu.append(&PredefinedMenuItem::separator())?;
+                submenu.append(&item(
+                    PROCESS_ACTION_COPY_PID,
+                    format!("Copy PID ({})", process.pid),
+                    true,
+                ))?;
+                for port in ports.iter() {
+                    submenu.append(&MenuItem::with_id(
+                        process_action_menu_id(process.pid, *port, PROCESS_ACTION_COPY_URL),
+                        format!("Copy {}", state.local_url(*port)),
+                        true,
+                        None,
+                    ))?;
+                }
+                menu.append(&submenu)?;
             }
         }
 
-        // === WINDOWS SERVICES SECTION (Windows only) ===
-        #[cfg(target_os = "windows")]
-        if !service_items.is_empty() {
-            if has_any_section {
-                menu.append(&PredefinedMenuItem::separator())?;
-            }
-
-            // Group by service name
-            let mut by_service: BTreeMap<String, Vec<u16>> = BTreeMap::new();
-            for (process, service) in &service_items {
-                by_service
-                    .entry(service.clone())
-                    .or_default()
-                    .push(process.port);
-            }
+        // Kill All only if multiple processes it would touch
+        let killable = by_pid
+            .values()
+            .filter(|(_, ports)| !ports.iter().any(|&port| monitoring.is_protected(port)))
+            .count();
+        if killable > 1 {
+            let kill_all = MenuItem::with_id(MENU_ID_KILL_ALL, "Kill All Processes", true, None);
+            menu.append(&kill_all)?;
+        }
+    }
 
-            let header = MenuItem::with_id(
-                "header_services",
-                format!("Windows Services · {}", by_service.len()),
+    // === HIDDEN LISTENERS (ignore rules), collapsed ===
+    if !hidden_processes.is_empty() {
+        if has_any_section {
+            menu.append(&PredefinedMenuItem::separator())?;
+        }
+        has_any_section = true;
+        let submenu = Submenu::new(format!("Hidden ({})", hidden_processes.len()), true);
+        for process in &hidden_processes {
+            submenu.append(&MenuItem::with_id(
+                format!("hidden_{}_{}", process.pid, process.port),
+                format!(
+                    "{} · {} (PID {})",
+                    process.port, process.command, process.pid
+                ),
                 false,
                 None,
-            );
-            menu.append(&header)?;
+            ))?;
+        }
+        menu.append(&submenu)?;
+    }
 
-            // Check if we need Stop All before consuming the map
-            let needs_stop_all = by_service.len() > 1;
+    // === INTEGRATION SECTIONS (registry order) ===
+    for integration in state.integrations.iter() {
+        let Some(by_unit) = claimed.remove(integration.id()) else {
+            continue;
+        };
+        if has_any_section {
+            menu.append(&PredefinedMenuItem::separator())?;
+        }
+        has_any_section = true;
 
-            // Create clickable menu item for each service
-            for (service_name, mut ports) in by_service {
-                ports.sort();
+        let header = MenuItem::with_id(
+            format!("header_{}", integration.id()),
+            format!("{} · {}", integration.section_title(), by_unit.len()),
+            false,
+            None,
+        );
+        menu.append(&header)?;
 
-                // Build label with friendly name
-                let friendly =
-                    crate::integrations::windows_services::friendly_service_name(&service_name);
-                let ports_str = ports
-                    .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                let main_label = format!("{} · {}", ports_str, friendly);
+        // Check if we need Stop All before consuming the map
+        let needs_stop_all = by_unit.len() > 1;
 
-                // Create clickable menu item that stops the service when clicked
-                let service_item = MenuItem::with_id(
-                    format!("{}{}", MENU_ID_SERVICE_STOP_PREFIX, service_name),
-                    main_label,
-                    true,
-                    None,
-                );
-                menu.append(&service_item)?;
+        // Create clickable menu item for each unit
+        for (unit, mut ports) in by_unit {
+            ports.sort();
+            let mut main_label = integration.unit_menu_label(&unit, &ports);
+            if let Some(dot) = health_dot(state, &ports) {
+                main_label = format!("{} {}", dot, main_label);
             }
 
-            // Stop All only if multiple services
-            if needs_stop_all {
-                let stop_all =
-                    MenuItem::with_id(MENU_ID_SERVICE_STOP_ALL, "Stop All Services", true, None);
-                menu.append(&stop_all)?;
+            let stop_id = integration_stop_menu_id(integration.id(), &unit);
+            let actions = integration.unit_actions(&unit);
+            if actions.is_empty() {
+                // Create clickable menu item that stops the unit when clicked
+                let unit_item = MenuItem::with_id(stop_id, main_label, true, None);
+                menu.append(&unit_item)?;
+            } else {
+                // Units with extra actions get a submenu: Stop plus each action
+                let submenu = Submenu::new(main_label, true);
+                submenu.append(&MenuItem::with_id(stop_id, "Stop", true, None))?;
+                for (action, label) in actions {
+                    let action_id = integration_action_menu_id(integration.id(), action, &unit);
+                    submenu.append(&MenuItem::with_id(action_id, *label, true, None))?;
+                }
+                menu.append(&submenu)?;
             }
         }
+
+        // Stop All only if multiple units
+        if needs_stop_all {
+            let stop_all = MenuItem::with_id(
+                integration_stop_all_menu_id(integration.id()),
+                integration.stop_all_label(),
+                true,
+                None,
+            );
+            menu.append(&stop_all)?;
+        }
+    }
+
+    // Integration status: only surfaced when an integration is failing or backing
+    // off, in which case its units are the last known ones
+    for (name, health) in &state.integration_health {
+        let label = match health {
+            IntegrationHealth::Healthy => continue,
+            IntegrationHealth::Failing { error, failures } => format!(
+                "⚠ {} failing ({}×), showing last known: {}",
+                name,
+                failures,
+                summarize_error(error)
+            ),
+            IntegrationHealth::BackingOff { error, retry_in } => format!(
+                "⚠ {} paused, showing last known, retry in {}s: {}",
+                name,
+                retry_in.as_secs().max(1),
+                summarize_error(error)
+            ),
+        };
+        let item = MenuItem::with_id(format!("integration_status_{}", name), label, false, None);
+        menu.append(&item)?;
     }
 
     menu.append(&PredefinedMenuItem::separator())?;
@@ -357,6 +417,13 @@ pub fn build_menu_with_context(state: &AppState) -> Result<Menu> {
     let reload_config_item =
         MenuItem::with_id(MENU_ID_RELOAD_CONFIG, "Reload Configuration", true, None);
     menu.append(&reload_config_item)?;
+    let effective_config_item = MenuItem::with_id(
+        MENU_ID_SHOW_EFFECTIVE_CONFIG,
+        "Show Effective Configuration",
+        true,
+        None,
+    );
+    menu.append(&effective_config_item)?;
 
     // Add checkable Launch at Login item
     let launch_enabled = state.config.system.launch_at_login;
@@ -377,57 +444,129 @@ pub fn build_menu_with_context(state: &AppState) -> Result<Menu> {
     Ok(menu)
 }
 
+/// Dot for the worst health among `ports`, once any of them has been probed.
+fn health_dot(state: &AppState, ports: &[u16]) -> Option<&'static str> {
+    ports
+        .iter()
+        .filter_map(|port| state.listener_health.get(port))
+        .map(|health| health.status)
+        .max_by_key(|status| status.severity())
+        .map(|status| status.dot())
+}
+
 pub fn process_menu_id(pid: i32, port: u16) -> String {
     format!("{}{}_{}", MENU_ID_PROCESS_PREFIX, pid, port)
 }
 
+pub fn process_action_menu_id(pid: i32, port: u16, action: &str) -> String {
+    format!("{}_{}", process_menu_id(pid, port), action)
+}
+
+pub fn restart_menu_id(pid: i32, port: u16) -> String {
+    format!("{}{}_{}", MENU_ID_RESTART_PREFIX, pid, port)
+}
+
+pub fn route_menu_id(port: u16, index: usize) -> String {
+    format!("{}{}_{}", MENU_ID_ROUTE_PREFIX, port, index)
+}
+
+pub fn tunnel_menu_id(port: u16, index: usize) -> String {
+    format!("{}{}_{}", MENU_ID_TUNNEL_PREFIX, port, index)
+}
+
+pub fn integration_stop_menu_id(integration: &str, unit: &str) -> String {
+    let escape = if unit == MENU_ID_INTEGRATION_STOP_ALL {
+        MENU_ID_UNIT_ESCAPE
+    } else {
+        ""
+    };
+    format!(
+        "{}_{}{}{}",
+        integration, MENU_ID_INTEGRATION_STOP, escape, unit
+    )
+}
+
+pub fn integration_stop_all_menu_id(integration: &str) -> String {
+    format!(
+        "{}_{}{}",
+        integration, MENU_ID_INTEGRATION_STOP, MENU_ID_INTEGRATION_STOP_ALL
+    )
+}
+
+pub fn integration_action_menu_id(integration: &str, action: &str, unit: &str) -> String {
+    format!(
+        "{}_{}{}_{}",
+        integration, MENU_ID_INTEGRATION_DO, action, unit
+    )
+}
+
 pub fn parse_menu_action(id: &MenuId) -> Option<crate::model::MenuAction> {
     let raw = id.as_ref();
     if raw == MENU_ID_KILL_ALL {
         Some(crate::model::MenuAction::KillAll)
-    } else if raw == MENU_ID_DOCKER_STOP_ALL {
-        Some(crate::model::MenuAction::DockerStopAll)
     } else if raw == MENU_ID_QUIT {
         Some(crate::model::MenuAction::Quit)
     } else if raw == MENU_ID_EDIT_CONFIG {
         Some(crate::model::MenuAction::EditConfig)
     } else if raw == MENU_ID_RELOAD_CONFIG {
         Some(crate::model::MenuAction::ReloadConfig)
+    } else if raw == MENU_ID_SHOW_EFFECTIVE_CONFIG {
+        Some(crate::model::MenuAction::ShowEffectiveConfig)
     } else if raw == MENU_ID_LAUNCH_AT_LOGIN {
         Some(crate::model::MenuAction::LaunchAtLogin)
-    } else if let Some(rest) = raw.strip_prefix(MENU_ID_DOCKER_STOP_PREFIX) {
-        Some(crate::model::MenuAction::DockerStop {
-            container: sanitize_identifier(rest),
-        })
     } else if let Some(remainder) = raw.strip_prefix(MENU_ID_PROCESS_PREFIX) {
         let mut parts = remainder.split('_');
         let pid = parts.next()?.parse::<i32>().ok()?;
-        let _port = parts.next()?.parse::<u16>().ok()?;
-        Some(crate::model::MenuAction::KillPid { pid })
-    } else {
-        // Platform-specific service actions
-        #[cfg(target_os = "macos")]
-        {
-            if raw == MENU_ID_BREW_STOP_ALL {
-                return Some(crate::model::MenuAction::BrewStopAll);
-            }
-            if let Some(rest) = raw.strip_prefix(MENU_ID_BREW_STOP_PREFIX) {
-                return Some(crate::model::MenuAction::BrewStop {
-                    service: sanitize_identifier(rest),
-                });
+        let port = parts.next()?.parse::<u16>().ok()?;
+        match parts.next() {
+            None => Some(crate::model::MenuAction::KillPid { pid }),
+            Some(PROCESS_ACTION_FORCE_KILL) => Some(crate::model::MenuAction::ForceKillPid { pid }),
+            Some(PROCESS_ACTION_OPEN) => Some(crate::model::MenuAction::OpenLocalhost { port }),
+            Some(PROCESS_ACTION_FOLDER) => {
+                Some(crate::model::MenuAction::OpenProjectFolder { pid })
             }
+            Some(PROCESS_ACTION_EDITOR) => Some(crate::model::MenuAction::OpenInEditor { pid }),
+            Some(PROCESS_ACTION_COPY_PID) => Some(crate::model::MenuAction::CopyPid { pid }),
+            Some(PROCESS_ACTION_COPY_URL) => Some(crate::model::MenuAction::CopyUrl { port }),
+            Some(_) => None,
         }
-        #[cfg(target_os = "windows")]
-        {
-            if raw == MENU_ID_SERVICE_STOP_ALL {
-                return Some(crate::model::MenuAction::WindowsServiceStopAll);
-            }
-            if let Some(rest) = raw.strip_prefix(MENU_ID_SERVICE_STOP_PREFIX) {
-                return Some(crate::model::MenuAction::WindowsServiceStop {
-                    service: sanitize_identifier(rest),
-                });
+    } else if let Some(remainder) = raw.strip_prefix(MENU_ID_RESTART_PREFIX) {
+        let (pid, _port) = remainder.split_once('_')?;
+        Some(crate::model::MenuAction::RestartPid {
+            pid: pid.parse().ok()?,
+        })
+    } else if let Some(remainder) = raw.strip_prefix(MENU_ID_ROUTE_PREFIX) {
+        let (port, index) = remainder.split_once('_')?;
+        Some(crate::model::MenuAction::OpenRoute {
+            port: port.parse().ok()?,
+            index: index.parse().ok()?,
+        })
+    } else if let Some(remainder) = raw.strip_prefix(MENU_ID_TUNNEL_PREFIX) {
+        let (port, index) = remainder.split_once('_')?;
+        Some(crate::model::MenuAction::OpenTunnelUrl {
+            port: port.parse().ok()?,
+            index: index.parse().ok()?,
+        })
+    } else if let Some((integration, rest)) = raw.split_once('_') {
+        let integration = sanitize_identifier(integration);
+        if let Some(unit) = rest.strip_prefix(MENU_ID_INTEGRATION_STOP) {
+            if unit == MENU_ID_INTEGRATION_STOP_ALL {
+                Some(crate::model::MenuAction::IntegrationStopAll { integration })
+            } else {
+                Some(crate::model::MenuAction::IntegrationStop {
+                    integration,
+                    unit: sanitize_identifier(unit),
+                })
             }
+        } else {
+            let (action, unit) = rest.strip_prefix(MENU_ID_INTEGRATION_DO)?.split_once('_')?;
+            Some(crate::model::MenuAction::IntegrationAction {
+                integration,
+                action: sanitize_identifier(action),
+                unit: sanitize_identifier(unit),
+            })
         }
+    } else {
         None
     }
 }
@@ -464,9 +603,20 @@ pub fn build_tooltip(processes: &[ProcessInfo], feedback: Option<&KillFeedback>)
     lines.join("\n")
 }
 
+/// First line of an integration error, truncated to fit a menu item
+fn summarize_error(error: &str) -> String {
+    let line = error.lines().next().unwrap_or("").trim();
+    if line.chars().count() <= MAX_STATUS_ERROR_LEN {
+        line.to_string()
+    } else {
+        let truncated: String = line.chars().take(MAX_STATUS_ERROR_LEN - 1).collect();
+        format!("{}…", truncated)
+    }
+}
+
 fn sanitize_identifier(s: &str) -> String {
     s.chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
         .collect()
 }
 
@@ -494,11 +644,11 @@ pub fn format_command_label(command: &str, ports: &[u16]) -> String {
 }
 
 pub fn collect_targets_for_all(processes: &[ProcessInfo]) -> Vec<crate::model::KillTarget> {
-    let mut map: BTreeMap<i32, (String, Vec<u16>)> = BTreeMap::new();
+    let mut map: BTreeMap<ProcessIdentity, (String, Vec<u16>)> = BTreeMap::new();
 
     for process in processes {
         let entry = map
-            .entry(process.pid)
+            .entry(process.identity())
             .or_insert_with(|| (process.command.clone(), Vec::new()));
         if !entry.1.contains(&process.port) {
             entry.1.push(process.port);
@@ -509,13 +659,13 @@ pub fn collect_targets_for_all(processes: &[ProcessInfo]) -> Vec<crate::model::K
     }
 
     map.into_iter()
-        .filter_map(|(pid, (command, mut ports))| {
+        .filter_map(|(process, (command, mut ports))| {
             if ports.is_empty() {
                 return None;
             }
             ports.sort();
             let label = format_command_label(&command, &ports);
-            Some(crate::model::KillTarget { pid, label })
+            Some(crate::model::KillTarget { process, label })
         })
         .collect()
 }
@@ -539,30 +689,63 @@ mod tests {
             parse_menu_action(&MenuId::new("edit_config")),
             Some(MenuAction::EditConfig)
         ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new("show_effective_config")),
+            Some(MenuAction::ShowEffectiveConfig)
+        ));
     }
 
     #[test]
     fn parse_targeted_actions() {
         assert!(matches!(
             parse_menu_action(&MenuId::new("docker_stop_mycontainer")),
-            Some(MenuAction::DockerStop { container }) if container == "mycontainer"
+            Some(MenuAction::IntegrationStop { integration, unit })
+                if integration == "docker" && unit == "mycontainer"
         ));
         assert!(matches!(
             parse_menu_action(&MenuId::new("brew_stop_postgresql")),
-            Some(MenuAction::BrewStop { service }) if service == "postgresql"
+            Some(MenuAction::IntegrationStop { integration, unit })
+                if integration == "brew" && unit == "postgresql"
         ));
         assert!(matches!(
             parse_menu_action(&MenuId::new("process_1234_3000")),
             Some(MenuAction::KillPid { pid }) if pid == 1234
         ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new(restart_menu_id(1234, 3000))),
+            Some(MenuAction::RestartPid { pid }) if pid == 1234
+        ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new(process_action_menu_id(1234, 3000, "force"))),
+            Some(MenuAction::ForceKillPid { pid }) if pid == 1234
+        ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new(process_action_menu_id(1234, 3001, "copyurl"))),
+            Some(MenuAction::CopyUrl { port }) if port == 3001
+        ));
         assert!(matches!(
             parse_menu_action(&MenuId::new("docker_stop_all")),
-            Some(MenuAction::DockerStopAll)
+            Some(MenuAction::IntegrationStopAll { integration }) if integration == "docker"
         ));
         assert!(matches!(
             parse_menu_action(&MenuId::new("brew_stop_all")),
-            Some(MenuAction::BrewStopAll)
+            Some(MenuAction::IntegrationStopAll { integration }) if integration == "brew"
         ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new("service_stop_all")),
+            Some(MenuAction::IntegrationStopAll { integration }) if integration == "service"
+        ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new(integration_stop_menu_id("brew", "all"))),
+            Some(MenuAction::IntegrationStop { integration, unit })
+                if integration == "brew" && unit == "all"
+        ));
+        assert!(matches!(
+            parse_menu_action(&MenuId::new(integration_action_menu_id("pm2", "restart", "my_api"))),
+            Some(MenuAction::IntegrationAction { integration, action, unit })
+                if integration == "pm2" && action == "restart" && unit == "my_api"
+        ));
+        assert!(parse_menu_action(&MenuId::new("header_docker")).is_none());
     }
 
     #[test]
@@ -581,28 +764,34 @@ mod tests {
             port: 3000,
             pid: 111,
             command: "node".into(),
+            start_time: None,
+            address: None,
         };
         let p2 = ProcessInfo {
             port: 3001,
             pid: 111,
             command: "node".into(),
+            start_time: None,
+            address: None,
         };
         let p3 = ProcessInfo {
             port: 5173,
             pid: 222,
             command: "vite".into(),
+            start_time: None,
+            address: None,
         };
         let targets = collect_targets_for_all(&[p1, p2, p3]);
         assert_eq!(targets.len(), 2);
         assert!(
-            targets
-                .iter()
-                .any(|t| t.pid == 111 && t.label.contains("3000") && t.label.contains("3001"))
+            targets.iter().any(|t| t.process.pid == 111
+                && t.label.contains("3000")
+                && t.label.contains("3001"))
         );
         assert!(
             targets
                 .iter()
-                .any(|t| t.pid == 222 && t.label.contains("5173"))
+                .any(|t| t.process.pid == 222 && t.label.contains("5173"))
         );
     }
 }
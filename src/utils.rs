@@ -1,28 +1,23 @@
-/// Find an executable in common locations, falling back to PATH.
-/// Results are cached for efficiency.
+use std::collections::HashMap;
 #[cfg(target_os = "macos")]
 use std::path::Path;
-#[cfg(target_os = "macos")]
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock, PoisonError};
 
-#[cfg(target_os = "macos")]
+/// Find an executable in common locations, falling back to PATH.
+/// Each name is looked up once and cached for the life of the process.
 pub fn find_command(name: &str) -> &'static str {
-    // Use static caches for common commands
-    match name {
-        "docker" => {
-            static DOCKER: OnceLock<&'static str> = OnceLock::new();
-            DOCKER.get_or_init(|| find_in_paths(name, HOMEBREW_PATHS))
-        }
-        "brew" => {
-            static BREW: OnceLock<&'static str> = OnceLock::new();
-            BREW.get_or_init(|| find_in_paths(name, HOMEBREW_PATHS))
-        }
-        "terminal-notifier" => {
-            static NOTIFIER: OnceLock<&'static str> = OnceLock::new();
-            NOTIFIER.get_or_init(|| find_in_paths(name, HOMEBREW_PATHS))
-        }
-        _ => find_in_paths(name, HOMEBREW_PATHS),
+    static CACHE: OnceLock<Mutex<HashMap<String, &'static str>>> = OnceLock::new();
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(found) = cache.get(name) {
+        return found;
     }
+    // Leaked once per distinct name, so the set stays small
+    let found: &'static str = Box::leak(locate_command(name).into_boxed_str());
+    cache.insert(name.to_string(), found);
+    found
 }
 
 #[cfg(target_os = "macos")]
@@ -32,27 +27,41 @@ const HOMEBREW_PATHS: &[&str] = &[
 ];
 
 #[cfg(target_os = "macos")]
-fn find_in_paths(name: &str, prefix_paths: &[&str]) -> &'static str {
-    for prefix in prefix_paths {
-        let full_path = format!("{}/{}", prefix, name);
-        if Path::new(&full_path).exists() {
-            // Leak the string to get a 'static lifetime (acceptable for small, cached strings)
-            return Box::leak(full_path.into_boxed_str());
-        }
-    }
-    // Fallback to PATH lookup
-    Box::leak(name.to_string().into_boxed_str())
+fn locate_command(name: &str) -> String {
+    HOMEBREW_PATHS
+        .iter()
+        .map(|prefix| format!("{}/{}", prefix, name))
+        .find(|full_path| Path::new(full_path).exists())
+        // Fallback to PATH lookup
+        .unwrap_or_else(|| name.to_string())
 }
 
-/// Find an executable on Windows - just returns the name for PATH lookup
+/// On Windows: the full path of the first match on PATH, trying each PATHEXT
+/// extension so `code` finds the `code.cmd` shim, or the bare name when
+/// nothing matches.
 #[cfg(target_os = "windows")]
-pub fn find_command(name: &str) -> &'static str {
-    // On Windows, commands like docker and sc are typically in PATH
-    // Just return the name and let the OS handle path resolution
-    match name {
-        "docker" => "docker",
-        _ => Box::leak(name.to_string().into_boxed_str()),
-    }
+fn locate_command(name: &str) -> String {
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    let has_extension = std::path::Path::new(name).extension().is_some();
+    std::env::var_os("PATH")
+        .and_then(|path| {
+            std::env::split_paths(&path).find_map(|dir| {
+                let exact = has_extension.then(|| dir.join(name));
+                exact
+                    .into_iter()
+                    .chain(
+                        extensions
+                            .split(';')
+                            .filter(|ext| !ext.is_empty())
+                            .map(|ext| dir.join(format!("{}{}", name, ext))),
+                    )
+                    .find(|candidate| candidate.is_file())
+            })
+        })
+        .map_or_else(
+            || name.to_string(),
+            |path| path.to_string_lossy().into_owned(),
+        )
 }
 
 /// Create a Command that runs hidden on Windows (no console window).
@@ -73,6 +82,135 @@ pub fn hidden_command(program: &str) -> std::process::Command {
     std::process::Command::new(program)
 }
 
+/// Create a Command whose child runs in its own process group (and without a
+/// console window on Windows), so it outlives PortKiller and isn't hit by
+/// signals aimed at it.
+pub fn detached_command(program: impl AsRef<std::ffi::OsStr>) -> std::process::Command {
+    let mut cmd = std::process::Command::new(program);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW);
+    }
+    cmd
+}
+
+/// Open an http(s) URL in the default browser.
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("refusing to open non-http URL {}", url),
+        ));
+    }
+    #[cfg(target_os = "macos")]
+    std::process::Command::new("open").arg(url).spawn()?;
+    #[cfg(target_os = "windows")]
+    hidden_command("rundll32")
+        .args(["url.dll,FileProtocolHandler", url])
+        .spawn()?;
+    Ok(())
+}
+
+/// Show a folder in Finder or Explorer.
+pub fn open_path(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(target_os = "macos")]
+    std::process::Command::new("open").arg(path).spawn()?;
+    #[cfg(target_os = "windows")]
+    hidden_command("explorer").arg(path).spawn()?;
+    Ok(())
+}
+
+/// Put `text` on the system clipboard.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = std::process::Command::new("pbcopy");
+    #[cfg(target_os = "windows")]
+    let mut cmd = hidden_command("clip");
+    let mut child = cmd.stdin(std::process::Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "clipboard command exited with {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Expand a leading `~` so config paths can be written relative to the home directory.
+pub fn expand_home(path: &str) -> std::path::PathBuf {
+    if let Some(rest) = path.strip_prefix("~/").or(path.strip_prefix("~\\"))
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    std::path::PathBuf::from(path)
+}
+
+/// Run a command to completion, killing it if it exceeds `timeout`.
+/// Stdout/stderr are drained on helper threads so a chatty child can't block on a full pipe.
+/// A hung child yields an `io::ErrorKind::TimedOut` error instead of blocking the caller.
+pub fn output_with_timeout(
+    cmd: &mut std::process::Command,
+    timeout: std::time::Duration,
+) -> std::io::Result<std::process::Output> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("command timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(25));
+    };
+
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
 /// Check if Windows is using dark mode for apps
 /// Returns true if dark mode is enabled, false for light mode
 #[cfg(target_os = "windows")]